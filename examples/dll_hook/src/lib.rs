@@ -19,7 +19,9 @@ use patternsleuth::resolvers::unreal::{
     guobject_array::{
         FUObjectArrayAllocateUObjectIndex, FUObjectArrayFreeUObjectIndex, GUObjectArray,
     },
+    gworld::GWorld,
     kismet::{FFrameStep, FFrameStepExplicitProperty, FFrameStepViaExec},
+    process_event::UObjectProcessEvent,
     KismetSystemLibrary,
 };
 use simple_log::{error, info, LogConfigBuilder};
@@ -129,6 +131,7 @@ impl_try_collector! {
         allocate_uobject: FUObjectArrayAllocateUObjectIndex,
         free_uobject: FUObjectArrayFreeUObjectIndex,
         game_tick: UGameEngineTick,
+        gworld: GWorld,
         engine_loop_init: FEngineLoopInit,
         kismet_system_library: KismetSystemLibrary,
         fframe_step_via_exec: FFrameStepViaExec,
@@ -137,6 +140,7 @@ impl_try_collector! {
         fframe_kismet_execution_message: FFrameKismetExecutionMessage,
         ufunction_bind: UFunctionBind,
         uobject_base_utility_get_path_name: UObjectBaseUtilityGetPathName,
+        process_event: UObjectProcessEvent,
     }
 }
 
@@ -5,7 +5,7 @@ use crate::{
     RuntimeFunction,
 };
 
-use super::{Image, ImageType};
+use super::{Bitness, Image, ImageType};
 use gimli::{BaseAddresses, CieOrFde, EhFrame, EhFrameHdr, NativeEndian, UnwindSection};
 
 #[cfg(feature = "symbols")]
@@ -77,6 +77,13 @@ impl ElfImage {
     ) -> Result<Vec<Range<usize>>, MemoryAccessError> {
         Ok(self.functions.as_ref().unwrap().to_vec())
     }
+    pub fn relocated_ptr(
+        &self,
+        image: &Image<'_>,
+        address: usize,
+    ) -> Result<usize, MemoryAccessError> {
+        image.ptr(address)
+    }
 }
 
 // read_inner
@@ -236,10 +243,14 @@ impl ElfImage {
         };
         Ok(Image {
             base_address,
+            bitness: Bitness::B64,
+            endianness: object.endian(),
             memory,
             #[cfg(feature = "symbols")]
             symbols,
             imports: HashMap::default(),
+            exports: HashMap::default(),
+            forwarded_exports: HashMap::default(),
             image_type: ImageType::ElfImage(ElfImage {
                 functions: Some(functions),
             }),
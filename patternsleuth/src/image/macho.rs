@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+use object::{Object, ObjectSection, ObjectSegment};
+
+use super::{Bitness, Image, ImageType};
+use crate::{Memory, MemoryAccessError, NamedMemorySection, RuntimeFunction};
+
+/// Segments mapped into [`Memory`]
+const SEGMENTS: &[&str] = &["__TEXT", "__DATA", "__DATA_CONST"];
+
+pub struct MachOImage {}
+
+// TODO parse __unwind_info/__eh_frame for function ranges
+impl MachOImage {
+    pub fn get_function(
+        &self,
+        _image: &Image<'_>,
+        _address: usize,
+    ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
+        Ok(None)
+    }
+    pub fn get_root_function(
+        &self,
+        _image: &Image<'_>,
+        _address: usize,
+    ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
+        Ok(None)
+    }
+    pub fn get_root_function_range(
+        &self,
+        _image: &Image<'_>,
+        _address: usize,
+    ) -> Result<Option<Range<usize>>, MemoryAccessError> {
+        Ok(None)
+    }
+    pub fn get_child_functions(
+        &self,
+        _image: &Image<'_>,
+        _address: usize,
+    ) -> Result<Vec<RuntimeFunction>, MemoryAccessError> {
+        Ok(vec![])
+    }
+    pub fn get_root_functions(
+        &self,
+        _image: &Image<'_>,
+    ) -> Result<Vec<Range<usize>>, MemoryAccessError> {
+        Ok(vec![])
+    }
+    pub fn relocated_ptr(
+        &self,
+        image: &Image<'_>,
+        address: usize,
+    ) -> Result<usize, MemoryAccessError> {
+        image.ptr(address)
+    }
+}
+
+impl MachOImage {
+    /// Read and parse Mach-O object, mapping sections of the __TEXT and __DATA segments
+    pub fn read_inner<P: AsRef<std::path::Path>>(
+        base_addr: Option<usize>,
+        _exe_path: Option<P>,
+        _cache_functions: bool,
+        object: object::File<'_>,
+    ) -> Result<Image<'_>, anyhow::Error> {
+        let object::File::MachO64(ref inner) = object else {
+            bail!("not a Mach-O file");
+        };
+
+        // the preferred base is the vmaddr of the __TEXT segment, rebase sections if loaded at
+        // another address
+        let text_vmaddr = inner
+            .segments()
+            .find(|s| s.name().ok().flatten() == Some("__TEXT"))
+            .map(|s| s.address() as usize)
+            .unwrap_or_default();
+        let base_address = base_addr.unwrap_or(text_vmaddr);
+
+        let sections = inner
+            .sections()
+            .filter(|s| {
+                s.segment_name()
+                    .ok()
+                    .flatten()
+                    .is_some_and(|segment| SEGMENTS.contains(&segment))
+            })
+            .map(|s| {
+                Ok(NamedMemorySection::new(
+                    format!("{},{}", s.segment_name()?.unwrap_or_default(), s.name()?),
+                    s.address() as usize - text_vmaddr + base_address,
+                    s.kind(),
+                    s.data()?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Image {
+            base_address,
+            bitness: Bitness::B64,
+            endianness: if object.is_little_endian() {
+                object::Endianness::Little
+            } else {
+                object::Endianness::Big
+            },
+            memory: Memory { sections },
+            #[cfg(feature = "symbols")]
+            symbols: None,
+            imports: HashMap::default(),
+            exports: HashMap::default(),
+            forwarded_exports: HashMap::default(),
+            image_type: ImageType::MachOImage(MachOImage {}),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemoryAccessorTrait;
+
+    /// Build a minimal x86_64 Mach-O executable with a single __TEXT segment at 0x100000000
+    /// containing a __text section and a null terminated string at __TEXT+0x300
+    fn build_macho64(string: &str) -> Vec<u8> {
+        const TEXT_VMADDR: u64 = 0x100000000;
+
+        let mut data = vec![0u8; 0x400];
+        let mut write =
+            |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+
+        // mach header
+        write(0, &object::macho::MH_MAGIC_64.to_le_bytes());
+        write(4, &object::macho::CPU_TYPE_X86_64.to_le_bytes());
+        write(8, &object::macho::CPU_SUBTYPE_X86_64_ALL.to_le_bytes());
+        write(12, &object::macho::MH_EXECUTE.to_le_bytes());
+        write(16, &1u32.to_le_bytes()); // ncmds
+        write(20, &(72u32 + 80).to_le_bytes()); // sizeofcmds
+
+        // segment command
+        let seg = 32;
+        write(seg, &object::macho::LC_SEGMENT_64.to_le_bytes());
+        write(seg + 4, &(72u32 + 80).to_le_bytes()); // cmdsize
+        write(seg + 8, b"__TEXT");
+        write(seg + 24, &TEXT_VMADDR.to_le_bytes());
+        write(seg + 32, &0x1000u64.to_le_bytes()); // vmsize
+        write(seg + 40, &0u64.to_le_bytes()); // fileoff
+        write(seg + 48, &0x400u64.to_le_bytes()); // filesize
+        write(seg + 56, &5u32.to_le_bytes()); // maxprot r-x
+        write(seg + 60, &5u32.to_le_bytes()); // initprot r-x
+        write(seg + 64, &1u32.to_le_bytes()); // nsects
+
+        // section
+        let sec = seg + 72;
+        write(sec, b"__text");
+        write(sec + 16, b"__TEXT");
+        write(sec + 32, &(TEXT_VMADDR + 0x200).to_le_bytes()); // addr
+        write(sec + 40, &0x200u64.to_le_bytes()); // size
+        write(sec + 48, &0x200u32.to_le_bytes()); // offset
+        write(
+            sec + 64,
+            &(object::macho::S_ATTR_PURE_INSTRUCTIONS | object::macho::S_ATTR_SOME_INSTRUCTIONS)
+                .to_le_bytes(),
+        );
+
+        // section data
+        write(0x200, &[0xc3]);
+        write(0x300, string.as_bytes());
+
+        data
+    }
+
+    #[test]
+    fn test_base_address() {
+        let data = build_macho64("macho string\0");
+
+        let image = Image::builder().build(&data).unwrap();
+        assert!(matches!(image.image_type, ImageType::MachOImage(_)));
+        assert_eq!(0x100000000, image.base_address);
+        assert_eq!(
+            "__TEXT,__text",
+            image
+                .memory
+                .get_section_containing(0x100000200)
+                .unwrap()
+                .name()
+        );
+        assert_eq!(
+            "macho string",
+            image.memory.read_string(0x100000300).unwrap()
+        );
+
+        let rebased = Image::read::<&str>(Some(0x10000000), &data, None, false).unwrap();
+        assert_eq!(0x10000000, rebased.base_address);
+        assert_eq!(
+            "macho string",
+            rebased.memory.read_string(0x10000300).unwrap()
+        );
+    }
+}
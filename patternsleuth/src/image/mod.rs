@@ -1,5 +1,7 @@
 #[cfg(feature = "image-elf")]
 pub mod elf;
+#[cfg(feature = "image-macho")]
+pub mod macho;
 mod macros;
 #[cfg(feature = "image-pe")]
 pub mod pe;
@@ -8,18 +10,22 @@ use crate::*;
 use anyhow::Error;
 #[cfg(feature = "image-elf")]
 use elf::ElfImage;
+#[cfg(feature = "image-macho")]
+use macho::MachOImage;
+use object::Object;
 #[cfg(feature = "image-pe")]
 use pe::PEImage;
 
 use macros::*;
 
-#[cfg(not(any(feature = "image-pe", feature = "image-elf")))]
-compile_error!("requires at least one of image-pe or image-elf features");
+#[cfg(not(any(feature = "image-pe", feature = "image-elf", feature = "image-macho")))]
+compile_error!("requires at least one of image-pe, image-elf or image-macho features");
 
 image_type_dispatch! {
     @enum ImageType as _image_type_reflection {
         PEImage(PEImage, "image-pe"),
         ElfImage(ElfImage, "image-elf"),
+        MachOImage(MachOImage, "image-macho"),
     }
 
     @fns {
@@ -28,17 +34,60 @@ image_type_dispatch! {
         fn get_root_function_range(address: usize) -> Result<Option<Range<usize>>, MemoryAccessError>;
         fn get_child_functions(address: usize) -> Result<Vec<RuntimeFunction>, MemoryAccessError>;
         fn get_root_functions() -> Result<Vec<Range<usize>>, MemoryAccessError>;
+        fn relocated_ptr(address: usize) -> Result<usize, MemoryAccessError>;
     }
 }
 
 pub use _image_type_reflection as image_type_reflection;
 
+#[cfg(feature = "mmap")]
+pub use memmap2::Mmap;
+
+/// Memory map the file at `path`. Pass the mapping to [`ImageBuilder::build`] to avoid reading
+/// large images into memory. The resulting [`Image`] borrows from the mapping so it must be kept
+/// alive for as long as the image.
+///
+/// The file must not be modified while it is mapped.
+#[cfg(feature = "mmap")]
+pub fn mmap<P: AsRef<Path>>(path: P) -> Result<Mmap> {
+    let file = std::fs::File::open(path)?;
+    Ok(unsafe { Mmap::map(&file)? })
+}
+
+/// Pointer width of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bitness {
+    B32,
+    B64,
+}
+impl Bitness {
+    /// Bitness as expected by [`iced_x86::Decoder`]
+    pub fn bits(self) -> u32 {
+        match self {
+            Bitness::B32 => 32,
+            Bitness::B64 => 64,
+        }
+    }
+    pub fn pointer_size(self) -> usize {
+        match self {
+            Bitness::B32 => 4,
+            Bitness::B64 => 8,
+        }
+    }
+}
+
 pub struct Image<'data> {
     pub base_address: usize,
+    pub bitness: Bitness,
+    pub endianness: object::Endianness,
     pub memory: Memory<'data>,
     #[cfg(feature = "symbols")]
     pub symbols: Option<HashMap<usize, symbols::Symbol>>,
     pub imports: HashMap<String, HashMap<String, usize>>,
+    /// Exported symbol addresses. Ordinal-only exports are keyed by `#<ordinal>`
+    pub exports: HashMap<String, usize>,
+    /// Forwarded exports mapped to their `library.symbol` or `library.#ordinal` target
+    pub forwarded_exports: HashMap<String, String>,
     pub image_type: ImageType,
 }
 
@@ -57,15 +106,177 @@ impl<'data> Image<'data> {
                 ElfImage::read_inner(base_addr, exe_path, cache_functions, object)
             }
             #[cfg(feature = "image-pe")]
-            object::File::Pe64(_) => {
+            object::File::Pe32(_) | object::File::Pe64(_) => {
                 PEImage::read_inner(base_addr, exe_path, cache_functions, object)
             }
-            _ => Err(Error::msg("Unsupported file format")),
+            #[cfg(feature = "image-macho")]
+            object::File::MachO64(_) => {
+                MachOImage::read_inner(base_addr, exe_path, cache_functions, object)
+            }
+            _ => Err(Error::msg(format!(
+                "Unsupported file format: {}-bit {:?} ({:?})",
+                if object.is_64() { 64 } else { 32 },
+                object.format(),
+                object.architecture()
+            ))),
         }
     }
     pub fn builder() -> ImageBuilder {
         Default::default()
     }
+    /// Copy any borrowed section data so the image no longer borrows the data it was read from
+    pub fn into_owned(self) -> Image<'static> {
+        Image {
+            base_address: self.base_address,
+            bitness: self.bitness,
+            endianness: self.endianness,
+            memory: self.memory.into_owned(),
+            #[cfg(feature = "symbols")]
+            symbols: self.symbols,
+            imports: self.imports,
+            exports: self.exports,
+            forwarded_exports: self.forwarded_exports,
+            image_type: self.image_type,
+        }
+    }
+    /// Whether the image has sections named by a known packer (currently UPX). Sections of packed
+    /// images are compressed on disk so patterns will not match until the image is unpacked.
+    pub fn looks_packed(&self) -> bool {
+        self.memory
+            .sections()
+            .iter()
+            .any(|section| section.name().starts_with("UPX"))
+    }
+    /// Return pointer at `address` sized according to the image's bitness
+    pub fn ptr(&self, address: usize) -> Result<usize, MemoryAccessError> {
+        Ok(match self.bitness {
+            Bitness::B32 => self.memory.u32_le(address)? as usize,
+            Bitness::B64 => self.memory.u64_le(address)? as usize,
+        })
+    }
+    /// Address of export `name` (or `#<ordinal>` for ordinal-only exports)
+    pub fn get_export(&self, name: &str) -> Option<usize> {
+        self.exports.get(name).copied()
+    }
+    /// Iterate over all imports as `(library, symbol, address)`
+    pub fn imports_iter(&self) -> impl Iterator<Item = (&str, &str, usize)> {
+        self.imports.iter().flat_map(|(lib, symbols)| {
+            symbols
+                .iter()
+                .map(move |(name, address)| (lib.as_str(), name.as_str(), *address))
+        })
+    }
+    /// Address of import `name` from any library. `__imp_` prefixes are ignored so both
+    /// `__imp_Sleep` and `Sleep` match an import of `Sleep`.
+    pub fn find_import(&self, name: &str) -> Option<usize> {
+        fn strip(name: &str) -> &str {
+            name.strip_prefix("__imp_").unwrap_or(name)
+        }
+        let name = strip(name);
+        self.imports_iter()
+            .find(|(_, n, _)| strip(n) == name)
+            .map(|(_, _, address)| address)
+    }
+    /// Range of the function containing `address`. Uses exception data when available, otherwise
+    /// falls back to [`Self::function_range_by_disassembly`].
+    pub fn function_range(&self, address: usize) -> Result<Range<usize>, MemoryAccessError> {
+        match self.get_root_function(address)? {
+            Some(root) => Ok(self
+                .get_root_function_range(root.range.start)?
+                .unwrap_or(root.range)),
+            None => self.function_range_by_disassembly(address),
+        }
+    }
+    /// Range of the function starting at `address` derived by disassembling it, see
+    /// [`disassemble::function_range`]
+    pub fn function_range_by_disassembly(
+        &self,
+        address: usize,
+    ) -> Result<Range<usize>, MemoryAccessError> {
+        disassemble::function_range(self, address)
+    }
+    /// If the function at `address` is a thunk consisting of a single unconditional jump (`jmp
+    /// rel` or `jmp [rip + x]`, e.g. an import thunk or a stub left by identical code folding),
+    /// return the jump target. Chains of thunks are followed to the first non-thunk. Returns
+    /// `None` if `address` is not a thunk or the chain loops.
+    pub fn resolve_thunk(&self, address: usize) -> Option<usize> {
+        use iced_x86::{FlowControl, OpKind, Register};
+
+        let mut visited = std::collections::HashSet::new();
+        let mut current = address;
+        loop {
+            if !visited.insert(current) {
+                return None;
+            }
+            let inst = disassemble::disassemble_single(self, current).ok()??;
+            let target = match inst.flow_control() {
+                FlowControl::UnconditionalBranch => inst.near_branch_target() as usize,
+                FlowControl::IndirectBranch
+                    if inst.op0_kind() == OpKind::Memory && inst.memory_base() == Register::RIP =>
+                {
+                    self.ptr(inst.ip_rel_memory_address() as usize).ok()?
+                }
+                _ => return (current != address).then_some(current),
+            };
+            current = target;
+        }
+    }
+    /// All references to `address`: relative displacements (e.g. rip-relative `lea`/`mov`),
+    /// absolute pointers and relative `call`/`jmp` instructions, sorted by address. Each section
+    /// is scanned once per kind.
+    pub fn xrefs_to(&self, address: usize) -> Vec<Reference> {
+        let mut refs = vec![];
+        for section in self.memory.sections() {
+            let base_address = section.address();
+            let data = section.data();
+
+            let calls = patternsleuth_scanner::scan_relative_calls(&[address], base_address, data)
+                .swap_remove(0);
+            // the displacement of a call is also a relative reference so skip it
+            let relative = patternsleuth_scanner::scan_xref(&[&Xref(address)], base_address, data)
+                .swap_remove(0)
+                .into_iter()
+                .filter(|r| !calls.contains(&(r - 1)));
+            let absolute = match self.bitness {
+                Bitness::B32 => patternsleuth_scanner::scan_pattern(
+                    &[&Pattern::from_bytes((address as u32).to_le_bytes().into()).unwrap()],
+                    base_address,
+                    data,
+                ),
+                Bitness::B64 => {
+                    patternsleuth_scanner::scan_absolute(&[address as u64], base_address, data)
+                }
+            }
+            .swap_remove(0);
+
+            let reference = |kind| move |address| Reference { address, kind };
+            refs.extend(relative.map(reference(ReferenceKind::Relative)));
+            refs.extend(absolute.into_iter().map(reference(ReferenceKind::Absolute)));
+            refs.extend(calls.iter().copied().map(reference(ReferenceKind::Call)));
+        }
+        refs.sort_by_key(|r| r.address);
+        refs
+    }
+    /// 64-bit FNV-1a hash of the image base address and section contents. Stable across builds
+    /// and platforms so it can be used to key on-disk caches.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut write = |bytes: &[u8]| {
+            for b in bytes {
+                hash ^= *b as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        };
+        write(&(self.base_address as u64).to_le_bytes());
+        for section in self.memory.sections() {
+            write(&(section.name().len() as u64).to_le_bytes());
+            write(section.name().as_bytes());
+            write(&(section.address() as u64).to_le_bytes());
+            write(&(section.data().len() as u64).to_le_bytes());
+            write(section.data());
+        }
+        hash
+    }
     pub fn resolve<T: Send + Sync>(
         &self,
         resolver: &'static resolvers::ResolverFactory<T>,
@@ -73,6 +284,14 @@ impl<'data> Image<'data> {
         resolvers::resolve(self, resolver)
     }
 
+    pub fn resolve_with_overrides<T: Send + Sync>(
+        &self,
+        resolver: &'static resolvers::ResolverFactory<T>,
+        overrides: &resolvers::Overrides,
+    ) -> resolvers::Result<T> {
+        resolvers::resolve_with_overrides(self, resolver, overrides)
+    }
+
     pub fn resolve_many(
         &self,
         resolvers: &[fn() -> &'static resolvers::DynResolverFactory],
@@ -80,6 +299,22 @@ impl<'data> Image<'data> {
         resolvers::resolve_many(self, resolvers)
     }
 
+    pub fn resolve_many_with_progress(
+        &self,
+        resolvers: &[fn() -> &'static resolvers::DynResolverFactory],
+        progress: &mut dyn resolvers::ProgressSink,
+    ) -> Vec<resolvers::Result<std::sync::Arc<dyn resolvers::Resolution>>> {
+        resolvers::resolve_many_with_progress(self, resolvers, progress)
+    }
+
+    pub fn resolve_many_cancellable(
+        &self,
+        resolvers: &[fn() -> &'static resolvers::DynResolverFactory],
+        cancel: &resolvers::CancelToken,
+    ) -> resolvers::Result<Vec<resolvers::Result<std::sync::Arc<dyn resolvers::Resolution>>>> {
+        resolvers::resolve_many_cancellable(self, resolvers, &mut (), cancel)
+    }
+
     pub fn scan<'patterns, S>(
         &self,
         pattern_configs: &'patterns [PatternConfig<S>],
@@ -140,6 +375,13 @@ impl<'data> Image<'data> {
 
             for (addresses, scan) in scan_results {
                 for address in addresses {
+                    if scan
+                        .scan
+                        .align
+                        .is_some_and(|align| address % align.get() != 0)
+                    {
+                        continue;
+                    }
                     results.push((
                         &pattern_configs[scan.original_config_index],
                         Resolution { address },
@@ -152,28 +394,73 @@ impl<'data> Image<'data> {
     }
 }
 
+/// Reference to an address found by [`Image::xrefs_to`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// Address of the displacement for [`ReferenceKind::Relative`], of the pointer for
+    /// [`ReferenceKind::Absolute`] and of the opcode for [`ReferenceKind::Call`]
+    pub address: usize,
+    pub kind: ReferenceKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferenceKind {
+    /// 4-byte relative displacement, e.g. rip-relative `lea`/`mov`
+    Relative,
+    /// Absolute pointer sized according to the image's bitness
+    Absolute,
+    /// Relative `call`/`jmp` (`e8`/`e9`)
+    Call,
+}
+
+/// Unpacks the raw data of a packed image, see [`ImageBuilder::unpack`]
+pub type UnpackHook = Box<dyn Fn(&[u8]) -> Option<Vec<u8>>>;
+
+/// Decides which sections are kept when reading an image, see [`ImageBuilder::section_filter`]
+pub type SectionFilter = Box<dyn Fn(&NamedMemorySection) -> bool>;
+
 #[derive(Default)]
 pub struct ImageBuilder {
     functions: bool,
+    unpack: Option<UnpackHook>,
+    section_filter: Option<SectionFilter>,
 }
 pub struct ImageBuilderWithSymbols<P: AsRef<Path>> {
     symbols: Option<P>,
     functions: bool,
+    unpack: Option<UnpackHook>,
+    section_filter: Option<SectionFilter>,
 }
 impl ImageBuilder {
     pub fn functions(mut self, functions: bool) -> Self {
         self.functions = functions;
         self
     }
+    /// Hook invoked with the raw image data if the image [looks packed](Image::looks_packed).
+    /// If it returns the unpacked data, the image is read from that instead. Without a hook (or
+    /// if it returns `None`) a warning is logged and the packed image is used as is.
+    pub fn unpack(mut self, unpack: UnpackHook) -> Self {
+        self.unpack = Some(unpack);
+        self
+    }
+    /// Only keep sections for which `filter` returns true, e.g. to exclude huge resource
+    /// sections. Dropped sections are not scanned and reads from them fail. By default all
+    /// sections are kept.
+    pub fn section_filter(mut self, filter: SectionFilter) -> Self {
+        self.section_filter = Some(filter);
+        self
+    }
     #[cfg(feature = "symbols")]
     pub fn symbols<P: AsRef<Path>>(self, exe_path: P) -> ImageBuilderWithSymbols<P> {
         ImageBuilderWithSymbols {
             symbols: Some(exe_path),
             functions: self.functions,
+            unpack: self.unpack,
+            section_filter: self.section_filter,
         }
     }
     pub fn build(self, data: &[u8]) -> Result<Image<'_>> {
-        Image::read::<&str>(None, data, None, self.functions)
+        read_unpacked::<&str>(data, None, self.functions, self.unpack, self.section_filter)
     }
 }
 impl<P: AsRef<Path>> ImageBuilderWithSymbols<P> {
@@ -181,12 +468,49 @@ impl<P: AsRef<Path>> ImageBuilderWithSymbols<P> {
         self.functions = functions;
         self
     }
+    /// See [`ImageBuilder::unpack`]
+    pub fn unpack(mut self, unpack: UnpackHook) -> Self {
+        self.unpack = Some(unpack);
+        self
+    }
+    /// See [`ImageBuilder::section_filter`]
+    pub fn section_filter(mut self, filter: SectionFilter) -> Self {
+        self.section_filter = Some(filter);
+        self
+    }
     #[cfg(feature = "symbols")]
     pub fn symbols(mut self, exe_path: P) -> Self {
         self.symbols = Some(exe_path);
         self
     }
     pub fn build(self, data: &[u8]) -> Result<Image<'_>> {
-        Image::read(None, data, self.symbols, self.functions)
+        read_unpacked(
+            data,
+            self.symbols,
+            self.functions,
+            self.unpack,
+            self.section_filter,
+        )
+    }
+}
+
+fn read_unpacked<P: AsRef<Path>>(
+    data: &[u8],
+    exe_path: Option<P>,
+    functions: bool,
+    unpack: Option<UnpackHook>,
+    section_filter: Option<SectionFilter>,
+) -> Result<Image<'_>> {
+    let mut image = Image::read(None, data, exe_path.as_ref(), functions)?;
+    if image.looks_packed() {
+        if let Some(unpacked) = unpack.and_then(|unpack| unpack(data)) {
+            image = Image::read(None, &unpacked, exe_path.as_ref(), functions)?.into_owned();
+        } else {
+            tracing::warn!("image looks packed, patterns are unlikely to match");
+        }
+    }
+    if let Some(filter) = section_filter {
+        image.memory.sections.retain(|section| filter(section));
     }
+    Ok(image)
 }
@@ -1,18 +1,29 @@
 use std::collections::{HashMap, HashSet};
 use std::ops::Range;
+use std::sync::OnceLock;
 
 use anyhow::{bail, Context, Result};
 use itertools::Itertools;
 
-use super::{Image, ImageType};
+use super::{Bitness, Image, ImageType};
 #[cfg(feature = "symbols")]
 use crate::symbols;
 use crate::{Memory, MemoryAccessError, MemoryAccessorTrait, MemoryTrait, RuntimeFunction};
 use object::Object;
 
+/// Exception directory entries sorted by address paired with their root function
+type RootFunctionCache = Vec<(Range<usize>, Result<RuntimeFunction, MemoryAccessError>)>;
+
+#[derive(Default)]
 pub struct PEImage {
     pub exception_directory_range: Range<usize>,
     pub exception_children_cache: HashMap<usize, Vec<RuntimeFunction>>,
+    root_function_cache: OnceLock<RootFunctionCache>,
+    /// Image base the file was linked against
+    pub preferred_base: usize,
+    /// Addresses of pointers listed in the base relocation table. Only populated for images read
+    /// from a file as memory read from a running process is already relocated.
+    pub relocations: HashSet<usize>,
 }
 
 impl PEImage {
@@ -23,6 +34,10 @@ impl PEImage {
     ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
         // place holder only
         let size = 12;
+        if self.exception_directory_range.len() < size {
+            // no exception directory (e.g. 32-bit images)
+            return Ok(None);
+        }
         let mut min = 0;
         let mut max = self.exception_directory_range.len() / size - 1;
 
@@ -54,38 +69,61 @@ impl PEImage {
         image: &Image<'_>,
         address: usize,
     ) -> Result<Option<RuntimeFunction>, MemoryAccessError> {
-        if let Some(f) = self.get_function(image, address)? {
-            let mut f = RuntimeFunction {
-                range: f.range,
-                unwind: f.unwind,
-            };
+        let cache = self
+            .root_function_cache
+            .get_or_init(|| self.build_root_function_cache(image));
+        let i = cache.partition_point(|(range, _)| range.end <= address);
+        match cache.get(i) {
+            Some((range, root)) if range.start <= address => root.clone().map(Some),
+            _ => Ok(None),
+        }
+    }
 
-            loop {
-                let mut unwind_addr = f.unwind;
+    /// Resolve the root function of every exception directory entry
+    fn build_root_function_cache(&self, image: &Image<'_>) -> RootFunctionCache {
+        let mut cache = self
+            .exception_directory_range
+            .clone()
+            .step_by(12)
+            .filter_map(|i| {
+                let f = RuntimeFunction::read(&image.memory, image.base_address, i).ok()?;
+                Some((f.range(), self.walk_root_function(image, f)))
+            })
+            .collect_vec();
+        cache.sort_by_key(|(range, _)| range.start);
+        cache
+    }
 
-                let section = image.memory.get_section_containing(unwind_addr)?;
+    /// Follow chained unwind info of `f` to its root function
+    fn walk_root_function(
+        &self,
+        image: &Image<'_>,
+        mut f: RuntimeFunction,
+    ) -> Result<RuntimeFunction, MemoryAccessError> {
+        loop {
+            let mut unwind_addr = f.unwind;
 
-                let has_chain_info = section.section.index(unwind_addr)? >> 3 == 0x4;
-                if has_chain_info {
-                    let unwind_code_count = section.section.index(unwind_addr + 2)?;
+            let section = image.memory.get_section_containing(unwind_addr)?;
 
-                    unwind_addr += 4 + 2 * unwind_code_count as usize;
-                    if unwind_addr % 4 != 0 {
-                        // align
-                        unwind_addr += 2;
-                    }
+            let has_chain_info = section.section.index(unwind_addr)? >> 3 == 0x4;
+            if has_chain_info {
+                let unwind_code_count = section.section.index(unwind_addr + 2)?;
 
-                    if section.address() + section.data().len() > unwind_addr + 12 {
-                        f = RuntimeFunction::read(section, image.base_address, unwind_addr)?;
-                    } else {
-                        todo!("not adding chain info {unwind_addr}");
-                    }
+                unwind_addr += 4 + 2 * unwind_code_count as usize;
+                if unwind_addr % 4 != 0 {
+                    // align
+                    unwind_addr += 2;
+                }
+
+                if section.address() + section.data().len() > unwind_addr + 12 {
+                    f = RuntimeFunction::read(section, image.base_address, unwind_addr)?;
                 } else {
-                    return Ok(Some(f));
+                    // chain info runs past the end of the section
+                    return Err(MemoryAccessError::MemoryOutOfBoundsError);
                 }
+            } else {
+                return Ok(f);
             }
-        } else {
-            Ok(None)
         }
     }
 
@@ -161,6 +199,21 @@ impl PEImage {
             })
             .try_collect()
     }
+
+    /// Return pointer at `address`, adjusted by the difference between the preferred base and
+    /// [`Image::base_address`] if it is listed in the base relocation table
+    pub fn relocated_ptr(
+        &self,
+        image: &Image<'_>,
+        address: usize,
+    ) -> Result<usize, MemoryAccessError> {
+        let ptr = image.ptr(address)?;
+        Ok(if self.relocations.contains(&address) {
+            ptr.wrapping_add(image.base_address.wrapping_sub(self.preferred_base))
+        } else {
+            ptr
+        })
+    }
 }
 
 impl Image<'_> {
@@ -230,73 +283,154 @@ impl PEImage {
         #[cfg(feature = "symbols")]
         let symbols = if let Some(exe_path) = exe_path {
             let pdb_path = exe_path.as_ref().with_extension("pdb");
-            pdb_path
+            let mut symbols = pdb_path
                 .exists()
                 .then(|| symbols::dump_pdb_symbols(pdb_path, base_address))
-                .transpose()?
+                .transpose()?;
+
+            // linker map symbols fill in anything missing from the PDB
+            let map_path = exe_path.as_ref().with_extension("map");
+            if map_path.exists() {
+                let map_symbols = symbols::dump_map_symbols(
+                    map_path,
+                    object.relative_address_base() as usize,
+                    base_address,
+                )?;
+                let symbols = symbols.get_or_insert_with(Default::default);
+                for (address, symbol) in map_symbols {
+                    symbols.entry(address).or_insert(symbol);
+                }
+            }
+            symbols
         } else {
             None
         };
 
         let get_ex_dir = || -> Result<Range<usize>> {
-            Ok(match object {
+            let exception_directory = match object {
+                object::File::Pe32(ref inner) => {
+                    inner.data_directory(object::pe::IMAGE_DIRECTORY_ENTRY_EXCEPTION)
+                }
                 object::File::Pe64(ref inner) => {
-                    let exception_directory = inner
-                        .data_directory(object::pe::IMAGE_DIRECTORY_ENTRY_EXCEPTION)
-                        .context("no exception directory")?;
-
-                    let (address, size) = exception_directory.address_range();
-                    base_address + address as usize..base_address + (address + size) as usize
+                    inner.data_directory(object::pe::IMAGE_DIRECTORY_ENTRY_EXCEPTION)
                 }
                 _ => bail!("not a PE file"),
-            })
+            }
+            .context("no exception directory")?;
+
+            let (address, size) = exception_directory.address_range();
+            Ok(base_address + address as usize..base_address + (address + size) as usize)
         };
 
+        fn read_imports<Pe: object::read::pe::ImageNtHeaders>(
+            inner: &object::read::pe::PeFile<'_, Pe>,
+            base_address: usize,
+        ) -> Result<HashMap<String, HashMap<String, usize>>> {
+            use object::read::pe::ImageThunkData;
+            use object::LittleEndian as LE;
+
+            let thunk_size = if inner.is_64() { 8 } else { 4 };
+
+            let mut imports: HashMap<String, HashMap<String, usize>> = Default::default();
+
+            let import_table = inner.import_table()?.context("no import table")?;
+            let mut import_descs = import_table.descriptors()?;
+
+            while let Some(import_desc) = import_descs.next()? {
+                let mut cur = HashMap::new();
+
+                let Ok(lib_name) = import_table.name(import_desc.name.get(LE)) else {
+                    continue;
+                };
+                let lib_name = std::str::from_utf8(lib_name)?.to_ascii_lowercase();
+                let mut thunks = import_table.thunks(import_desc.original_first_thunk.get(LE))?;
+                let mut address = base_address + import_desc.first_thunk.get(LE) as usize;
+                while let Some(thunk) = thunks.next::<Pe>()? {
+                    if let Ok((_hint, name)) = import_table.hint_name(thunk.address()) {
+                        cur.insert(std::str::from_utf8(name)?.to_owned(), address);
+                        address += thunk_size;
+                    }
+                }
+                imports.insert(lib_name, cur);
+            }
+            Ok(imports)
+        }
+
         let get_imports = || -> Result<_> {
-            Ok(match object {
-                object::File::Pe64(ref inner) => {
-                    use object::pe::ImageNtHeaders64;
-                    use object::read::pe::ImageThunkData;
-                    use object::LittleEndian as LE;
-
-                    let mut imports: HashMap<String, HashMap<String, usize>> = Default::default();
-
-                    let import_table = inner.import_table()?.unwrap();
-                    let mut import_descs = import_table.descriptors()?;
-
-                    while let Some(import_desc) = import_descs.next()? {
-                        let mut cur = HashMap::new();
-
-                        let Ok(lib_name) = import_table.name(import_desc.name.get(LE)) else {
-                            continue;
-                        };
-                        let lib_name = std::str::from_utf8(lib_name)?.to_ascii_lowercase();
-                        let mut thunks =
-                            import_table.thunks(import_desc.original_first_thunk.get(LE))?;
-                        let mut address = base_address + import_desc.first_thunk.get(LE) as usize;
-                        while let Some(thunk) = thunks.next::<ImageNtHeaders64>()? {
-                            if let Ok((_hint, name)) = import_table.hint_name(thunk.address()) {
-                                cur.insert(std::str::from_utf8(name)?.to_owned(), address);
-                                address += 8;
-                            }
-                        }
-                        imports.insert(lib_name, cur);
+            match object {
+                object::File::Pe32(ref inner) => read_imports(inner, base_address),
+                object::File::Pe64(ref inner) => read_imports(inner, base_address),
+                _ => bail!("not a PE file"),
+            }
+        };
+
+        type Exports = (HashMap<String, usize>, HashMap<String, String>);
+
+        fn read_exports<Pe: object::read::pe::ImageNtHeaders>(
+            inner: &object::read::pe::PeFile<'_, Pe>,
+            base_address: usize,
+        ) -> Result<Exports> {
+            use object::read::pe::ExportTarget;
+
+            let mut exports: HashMap<String, usize> = Default::default();
+            let mut forwarded: HashMap<String, String> = Default::default();
+
+            let export_table = inner.export_table()?.context("no export table")?;
+            for export in export_table.exports()? {
+                // ordinal-only exports are keyed by "#<ordinal>"
+                let name = match export.name {
+                    Some(name) => std::str::from_utf8(name)?.to_owned(),
+                    None => format!("#{}", export.ordinal),
+                };
+                match export.target {
+                    ExportTarget::Address(0) => {}
+                    ExportTarget::Address(address) => {
+                        exports.insert(name, base_address + address as usize);
+                    }
+                    ExportTarget::ForwardByName(lib, target) => {
+                        let lib = std::str::from_utf8(lib)?;
+                        let target = std::str::from_utf8(target)?;
+                        forwarded.insert(name, format!("{lib}.{target}"));
+                    }
+                    ExportTarget::ForwardByOrdinal(lib, ordinal) => {
+                        let lib = std::str::from_utf8(lib)?;
+                        forwarded.insert(name, format!("{lib}.#{ordinal}"));
                     }
-                    imports
                 }
+            }
+            Ok((exports, forwarded))
+        }
+
+        let get_exports = || -> Result<_> {
+            match object {
+                object::File::Pe32(ref inner) => read_exports(inner, base_address),
+                object::File::Pe64(ref inner) => read_exports(inner, base_address),
                 _ => bail!("not a PE file"),
-            })
+            }
+        };
+        let (exports, forwarded_exports) = get_exports().unwrap_or_default();
+
+        let bitness = match object {
+            object::File::Pe32(_) => Bitness::B32,
+            _ => Bitness::B64,
         };
 
         let mut new = Image {
             base_address,
+            bitness,
+            endianness: object::Endianness::Little,
             memory,
             #[cfg(feature = "symbols")]
             symbols,
             imports: get_imports().unwrap_or_default(),
+            exports,
+            forwarded_exports,
             image_type: ImageType::PEImage(PEImage {
                 exception_directory_range: get_ex_dir().unwrap_or_default(),
                 exception_children_cache: Default::default(),
+                root_function_cache: Default::default(),
+                preferred_base: object.relative_address_base() as usize,
+                relocations: Default::default(),
             }),
         };
 
@@ -312,8 +446,740 @@ impl PEImage {
         cache_functions: bool,
         object: object::File<'_>,
     ) -> Result<Image<'_>, anyhow::Error> {
+        fn read_relocations<Pe: object::read::pe::ImageNtHeaders>(
+            inner: &object::read::pe::PeFile<'_, Pe>,
+        ) -> Result<HashSet<usize>> {
+            let preferred_base = inner.relative_address_base() as usize;
+            let mut relocations = HashSet::new();
+
+            let mut blocks = inner
+                .data_directories()
+                .relocation_blocks(inner.data(), &inner.section_table())?
+                .context("no base relocation table")?;
+            while let Some(block) = blocks.next()? {
+                for relocation in block {
+                    if matches!(
+                        relocation.typ,
+                        object::pe::IMAGE_REL_BASED_HIGHLOW | object::pe::IMAGE_REL_BASED_DIR64
+                    ) {
+                        relocations.insert(preferred_base + relocation.virtual_address as usize);
+                    }
+                }
+            }
+            Ok(relocations)
+        }
+
+        let relocations = match object {
+            object::File::Pe32(ref inner) => read_relocations(inner),
+            object::File::Pe64(ref inner) => read_relocations(inner),
+            _ => bail!("not a PE file"),
+        };
+
         let base_address = base_addr.unwrap_or(object.relative_address_base() as usize);
         let memory = Memory::new(&object)?;
-        Self::read_inner_memory(base_address, exe_path, cache_functions, memory, object)
+        let mut image =
+            Self::read_inner_memory(base_address, exe_path, cache_functions, memory, object)?;
+        #[allow(irrefutable_let_patterns)]
+        if let ImageType::PEImage(ref mut pe) = image.image_type {
+            pe.relocations = relocations.unwrap_or_default();
+        }
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resolvers;
+    use crate::NamedMemorySection;
+    use patternsleuth_scanner::Pattern;
+
+    /// Build a minimal 32-bit PE with a single .text section containing `mov eax, <string>; ret`
+    /// at the start of the section and a null terminated string at +0x100
+    fn build_pe32(string: &str) -> Vec<u8> {
+        const IMAGE_BASE: u32 = 0x400000;
+        const TEXT_RVA: u32 = 0x1000;
+
+        let mut data = vec![0u8; 0x400];
+        let mut write =
+            |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+
+        // DOS header
+        write(0, b"MZ");
+        write(0x3c, &0x40u32.to_le_bytes());
+
+        // COFF header
+        write(0x40, b"PE\0\0");
+        write(0x44, &object::pe::IMAGE_FILE_MACHINE_I386.to_le_bytes());
+        write(0x46, &1u16.to_le_bytes()); // NumberOfSections
+        write(0x54, &0xe0u16.to_le_bytes()); // SizeOfOptionalHeader
+        write(0x56, &0x0102u16.to_le_bytes()); // Characteristics
+
+        // optional header
+        let opt = 0x58;
+        write(
+            opt,
+            &object::pe::IMAGE_NT_OPTIONAL_HDR32_MAGIC.to_le_bytes(),
+        );
+        write(opt + 16, &TEXT_RVA.to_le_bytes()); // AddressOfEntryPoint
+        write(opt + 28, &IMAGE_BASE.to_le_bytes());
+        write(opt + 32, &0x1000u32.to_le_bytes()); // SectionAlignment
+        write(opt + 36, &0x200u32.to_le_bytes()); // FileAlignment
+        write(opt + 40, &4u16.to_le_bytes()); // MajorOperatingSystemVersion
+        write(opt + 48, &4u16.to_le_bytes()); // MajorSubsystemVersion
+        write(opt + 56, &0x2000u32.to_le_bytes()); // SizeOfImage
+        write(opt + 60, &0x200u32.to_le_bytes()); // SizeOfHeaders
+        write(opt + 68, &3u16.to_le_bytes()); // Subsystem
+        write(opt + 92, &16u32.to_le_bytes()); // NumberOfRvaAndSizes
+
+        // section header
+        let sec = opt + 0xe0;
+        write(sec, b".text\0\0\0");
+        write(sec + 8, &0x200u32.to_le_bytes()); // VirtualSize
+        write(sec + 12, &TEXT_RVA.to_le_bytes());
+        write(sec + 16, &0x200u32.to_le_bytes()); // SizeOfRawData
+        write(sec + 20, &0x200u32.to_le_bytes()); // PointerToRawData
+        write(sec + 36, &0x60000020u32.to_le_bytes()); // code | execute | read
+
+        // section data
+        let string_address = IMAGE_BASE + TEXT_RVA + 0x100;
+        write(0x200, &[0xb8]);
+        write(0x201, &string_address.to_le_bytes());
+        write(0x205, &[0xc3]);
+        write(0x300, string.as_bytes());
+
+        data
+    }
+
+    /// Build a 32-bit DLL from [`build_pe32`] with an export directory at .text+0x180 containing
+    /// a named export, an ordinal-only export and a forwarded export
+    fn build_dll32() -> Vec<u8> {
+        let mut data = build_pe32("dll string\0");
+        let mut write = |rva: u32, bytes: &[u8]| {
+            let offset = (rva - 0x1000 + 0x200) as usize;
+            data[offset..offset + bytes.len()].copy_from_slice(bytes)
+        };
+        let u32s = |values: &[u32]| {
+            values
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+
+        const EXPORT_RVA: u32 = 0x1180;
+        const EXPORT_SIZE: u32 = 0x80;
+
+        // export directory
+        write(EXPORT_RVA + 12, &0x11c0u32.to_le_bytes()); // Name
+        write(EXPORT_RVA + 16, &1u32.to_le_bytes()); // Base
+        write(EXPORT_RVA + 20, &3u32.to_le_bytes()); // NumberOfFunctions
+        write(EXPORT_RVA + 24, &2u32.to_le_bytes()); // NumberOfNames
+        write(EXPORT_RVA + 28, &0x11a8u32.to_le_bytes()); // AddressOfFunctions
+        write(EXPORT_RVA + 32, &0x11b4u32.to_le_bytes()); // AddressOfNames
+        write(EXPORT_RVA + 36, &0x11bcu32.to_le_bytes()); // AddressOfNameOrdinals
+
+        // ordinals 1 (named), 2 (ordinal-only), 3 (forwarded)
+        write(0x11a8, &u32s(&[0x1000, 0x1005, 0x11f0]));
+        // names must be sorted
+        write(0x11b4, &u32s(&[0x11e0, 0x11d0]));
+        write(0x11bc, &[2, 0, 0, 0]);
+        write(0x11c0, b"test.dll\0");
+        write(0x11d0, b"named_export\0");
+        write(0x11e0, b"forwarded\0");
+        write(0x11f0, b"other.Func\0");
+
+        // Characteristics |= IMAGE_FILE_DLL
+        data[0x56..0x58].copy_from_slice(&0x2102u16.to_le_bytes());
+        // export data directory
+        let dir = 0x58 + 96;
+        data[dir..dir + 4].copy_from_slice(&EXPORT_RVA.to_le_bytes());
+        data[dir + 4..dir + 8].copy_from_slice(&EXPORT_SIZE.to_le_bytes());
+
+        data
+    }
+
+    /// Build a 32-bit PE from [`build_pe32`] with an additional .data section holding two pointers
+    /// to the string, only the first of which is listed in the base relocation table at .text+0x180
+    fn build_reloc32() -> Vec<u8> {
+        let mut data = build_pe32("reloc string\0");
+        data.resize(0x600, 0);
+        let mut write =
+            |offset: usize, bytes: &[u8]| data[offset..offset + bytes.len()].copy_from_slice(bytes);
+
+        const RELOC_RVA: u32 = 0x1180;
+        const DATA_RVA: u32 = 0x2000;
+
+        let opt = 0x58;
+        write(0x46, &2u16.to_le_bytes()); // NumberOfSections
+        write(opt + 56, &0x3000u32.to_le_bytes()); // SizeOfImage
+
+        // base relocation data directory
+        write(opt + 96 + 5 * 8, &RELOC_RVA.to_le_bytes());
+        write(opt + 96 + 5 * 8 + 4, &12u32.to_le_bytes());
+
+        // section header
+        let sec = opt + 0xe0 + 40;
+        write(sec, b".data\0\0\0");
+        write(sec + 8, &0x200u32.to_le_bytes()); // VirtualSize
+        write(sec + 12, &DATA_RVA.to_le_bytes());
+        write(sec + 16, &0x200u32.to_le_bytes()); // SizeOfRawData
+        write(sec + 20, &0x400u32.to_le_bytes()); // PointerToRawData
+        write(sec + 36, &0xc0000040u32.to_le_bytes()); // initialized data | read | write
+
+        // relocation block for the .data page with one HIGHLOW entry and padding
+        write(0x380, &DATA_RVA.to_le_bytes());
+        write(0x384, &12u32.to_le_bytes());
+        write(0x388, &0x3000u16.to_le_bytes());
+
+        // section data
+        write(0x400, &0x401100u32.to_le_bytes());
+        write(0x404, &0x401100u32.to_le_bytes());
+
+        data
+    }
+
+    #[test]
+    fn test_unsupported_format() {
+        // bare 32-bit x86 ELF header
+        let mut data = vec![0u8; 0x34];
+        data[..7].copy_from_slice(b"\x7fELF\x01\x01\x01");
+        data[0x10..0x18].copy_from_slice(&[0x02, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00]);
+        data[0x28] = 0x34;
+
+        let err = Image::builder().build(&data).err().unwrap();
+        assert_eq!(
+            "Unsupported file format: 32-bit Elf (I386)",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_unpack_hook() {
+        use std::{cell::Cell, rc::Rc};
+
+        let mut packed = build_pe32("packed string\0");
+        packed[0x58 + 0xe0..0x58 + 0xe0 + 8].copy_from_slice(b"UPX1\0\0\0\0");
+
+        let calls = Rc::new(Cell::new(0));
+        let hook = |unpacked: Option<Vec<u8>>| -> crate::image::UnpackHook {
+            let calls = calls.clone();
+            Box::new(move |_| {
+                calls.set(calls.get() + 1);
+                unpacked.clone()
+            })
+        };
+
+        let unpacked = build_pe32("unpacked string\0");
+        let image = Image::builder()
+            .unpack(hook(Some(unpacked)))
+            .build(&packed)
+            .unwrap();
+        assert_eq!(1, calls.get());
+        assert!(!image.looks_packed());
+        assert_eq!(
+            "unpacked string",
+            image.memory.read_string(0x401100).unwrap()
+        );
+
+        // hook could not unpack so the packed image is used
+        let image = Image::builder().unpack(hook(None)).build(&packed).unwrap();
+        assert_eq!(2, calls.get());
+        assert!(image.looks_packed());
+        assert_eq!("packed string", image.memory.read_string(0x401100).unwrap());
+
+        // not packed
+        let data = build_pe32("plain string\0");
+        let image = Image::builder().unpack(hook(None)).build(&data).unwrap();
+        assert_eq!(2, calls.get());
+        assert_eq!("plain string", image.memory.read_string(0x401100).unwrap());
+    }
+
+    #[test]
+    fn test_section_filter() {
+        let data = build_reloc32();
+        let names = |image: &Image| {
+            image
+                .memory
+                .sections()
+                .iter()
+                .map(|s| s.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let image = Image::builder().build(&data).unwrap();
+        assert_eq!(vec![".text", ".data"], names(&image));
+        assert_eq!(0x401100, image.ptr(0x402000).unwrap());
+
+        let image = Image::builder()
+            .section_filter(Box::new(|section| section.name() != ".data"))
+            .build(&data)
+            .unwrap();
+        assert_eq!(vec![".text"], names(&image));
+        assert!(image.ptr(0x402000).is_err());
+    }
+
+    #[test]
+    fn test_relocated_ptr() {
+        let data = build_reloc32();
+
+        let image = Image::builder().build(&data).unwrap();
+        let ImageType::PEImage(pe) = &image.image_type else {
+            unreachable!()
+        };
+        assert_eq!(0x400000, pe.preferred_base);
+        assert_eq!(HashSet::from([0x402000]), pe.relocations);
+        assert_eq!(0x401100, image.relocated_ptr(0x402000).unwrap());
+        assert_eq!("reloc string", image.memory.read_string(0x401100).unwrap());
+
+        let rebased = Image::read::<&str>(Some(0x10000000), &data, None, false).unwrap();
+        assert_eq!(0x401100, rebased.ptr(0x402000).unwrap());
+        assert_eq!(0x10001100, rebased.relocated_ptr(0x402000).unwrap());
+        // not listed in the relocation table
+        assert_eq!(0x401100, rebased.relocated_ptr(0x402004).unwrap());
+    }
+
+    /// Build a 32-bit PE from [`build_pe32`] with an import directory at .text+0x180 importing
+    /// `GetTickCount` and `Sleep` from kernel32.dll
+    fn build_imports32() -> Vec<u8> {
+        let mut data = build_pe32("import string\0");
+        let mut write = |rva: u32, bytes: &[u8]| {
+            let offset = (rva - 0x1000 + 0x200) as usize;
+            data[offset..offset + bytes.len()].copy_from_slice(bytes)
+        };
+        let u32s = |values: &[u32]| {
+            values
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+
+        const IMPORT_RVA: u32 = 0x1180;
+        const IMPORT_SIZE: u32 = 40;
+
+        // import descriptor followed by a null descriptor
+        write(IMPORT_RVA, &0x11b0u32.to_le_bytes()); // OriginalFirstThunk
+        write(IMPORT_RVA + 12, &0x11d0u32.to_le_bytes()); // Name
+        write(IMPORT_RVA + 16, &0x11c0u32.to_le_bytes()); // FirstThunk
+
+        // lookup and address tables
+        write(0x11b0, &u32s(&[0x11e0, 0x11f0, 0]));
+        write(0x11c0, &u32s(&[0x11e0, 0x11f0, 0]));
+        write(0x11d0, b"KERNEL32.dll\0");
+        write(0x11e2, b"GetTickCount\0");
+        write(0x11f2, b"Sleep\0");
+
+        // import data directory
+        let dir = 0x58 + 96 + 8;
+        data[dir..dir + 4].copy_from_slice(&IMPORT_RVA.to_le_bytes());
+        data[dir + 4..dir + 8].copy_from_slice(&IMPORT_SIZE.to_le_bytes());
+
+        data
+    }
+
+    #[test]
+    fn test_imports() {
+        let data = build_imports32();
+        let image = Image::builder().build(&data).unwrap();
+
+        assert_eq!(
+            vec![
+                ("kernel32.dll", "GetTickCount", 0x4011c0),
+                ("kernel32.dll", "Sleep", 0x4011c4),
+            ],
+            image.imports_iter().sorted().collect_vec()
+        );
+        assert_eq!(Some(0x4011c4), image.find_import("Sleep"));
+        assert_eq!(Some(0x4011c4), image.find_import("__imp_Sleep"));
+        assert_eq!(Some(0x4011c0), image.find_import("__imp_GetTickCount"));
+        assert_eq!(None, image.find_import("sleep"));
+        assert_eq!(None, image.find_import("ExitProcess"));
+    }
+
+    #[test]
+    fn test_exports() {
+        let data = build_dll32();
+        let image = Image::builder().build(&data).unwrap();
+
+        assert_eq!(Some(0x401000), image.get_export("named_export"));
+        assert_eq!(Some(0x401005), image.get_export("#2"));
+        assert_eq!(None, image.get_export("forwarded"));
+        assert_eq!(2, image.exports.len());
+        assert_eq!(
+            Some("other.Func"),
+            image.forwarded_exports.get("forwarded").map(String::as_str)
+        );
+
+        // executables without an export directory
+        let data = build_pe32("\0");
+        let image = Image::builder().build(&data).unwrap();
+        assert!(image.exports.is_empty());
+        assert!(image.forwarded_exports.is_empty());
+    }
+
+    #[test]
+    fn test_pe32() {
+        let data = build_pe32("pe32 string\0");
+        let image = Image::builder().functions(true).build(&data).unwrap();
+
+        assert_eq!(Bitness::B32, image.bitness);
+        assert_eq!(0x400000, image.base_address);
+        assert_eq!(0x401100, image.ptr(0x401001).unwrap());
+        assert_eq!("pe32 string", image.memory.read_string(0x401100).unwrap());
+
+        // no exception directory
+        assert_eq!(None, image.get_function(0x401000).unwrap());
+        assert_eq!(
+            Vec::<Range<usize>>::new(),
+            image.get_root_functions().unwrap()
+        );
+
+        let inst = crate::disassemble::disassemble_single(&image, 0x401000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(iced_x86::Code::Mov_r32_imm32, inst.code());
+        assert_eq!(5, inst.len());
+
+        let refs = resolvers::eval(&image, |ctx| {
+            Box::pin(async {
+                let strings = ctx
+                    .scan(Pattern::from_bytes(b"pe32 string\0".to_vec()).unwrap())
+                    .await;
+                let mut refs = vec![];
+                for s in strings {
+                    refs.extend(ctx.scan(Pattern::new(format!("b8 0x{s:X}")).unwrap()).await);
+                }
+                refs
+            })
+        });
+        assert_eq!(vec![0x401000], refs);
+    }
+
+    #[test]
+    fn test_scan_file() {
+        let path = std::env::temp_dir().join(format!(
+            "patternsleuth-scan-file-{}.exe",
+            std::process::id()
+        ));
+        std::fs::write(&path, build_pe32("no engine here\0")).unwrap();
+
+        let named = resolvers::resolvers()
+            .filter(|r| r.name == "GMalloc" || r.name == "FNamePool")
+            .collect::<Vec<_>>();
+        let results = crate::scan_file(&path, &named).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            named.iter().map(|r| r.name).collect::<Vec<_>>(),
+            results.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+        );
+        assert!(results.iter().all(|(_, res)| res.is_err()));
+
+        assert!(crate::scan_file(&path, &named).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mmap() {
+        let data = build_pe32("mapped string\0");
+        let path =
+            std::env::temp_dir().join(format!("patternsleuth-mmap-{}.exe", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+
+        let mapped = crate::image::mmap(&path).unwrap();
+        let config = [crate::PatternConfig::new(
+            (),
+            "string".to_string(),
+            None,
+            Pattern::new("6d 61 70 70 65 64").unwrap(),
+        )];
+        let addresses = |image: &Image| {
+            image
+                .scan(&config)
+                .unwrap()
+                .results
+                .into_iter()
+                .map(|(_, res)| res.address)
+                .collect::<Vec<_>>()
+        };
+
+        let read = Image::builder().build(&data).unwrap();
+        let image = Image::builder().build(&mapped).unwrap();
+        assert_eq!(vec![0x401100], addresses(&image));
+        assert_eq!(addresses(&read), addresses(&image));
+
+        drop(image);
+        drop(mapped);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_xrefs_to() {
+        use crate::image::{Reference, ReferenceKind};
+
+        let target = 0x3000usize;
+        let rel = |end: usize| ((target as i64 - end as i64) as i32).to_le_bytes();
+        let mut text = vec![0x90; 0x10];
+        // lea rcx, [rip + target]
+        text[0..3].copy_from_slice(&[0x48, 0x8d, 0x0d]);
+        text[3..7].copy_from_slice(&rel(0x2007));
+        // call target
+        text[8] = 0xe8;
+        text[9..13].copy_from_slice(&rel(0x200d));
+        let mut data = vec![0; 0x10];
+        data[8..].copy_from_slice(&(target as u64).to_le_bytes());
+
+        let image = Image::test_image(vec![
+            NamedMemorySection::test_section(".text", 0x2000, object::SectionKind::Text, text),
+            NamedMemorySection::test_section(
+                ".rdata",
+                0x3000,
+                object::SectionKind::ReadOnlyData,
+                vec![0; 0x10],
+            ),
+            NamedMemorySection::test_section(".data", 0x4000, object::SectionKind::Data, data),
+        ]);
+
+        let reference = |address, kind| Reference { address, kind };
+        assert_eq!(
+            vec![
+                reference(0x2003, ReferenceKind::Relative),
+                reference(0x2008, ReferenceKind::Call),
+                reference(0x4008, ReferenceKind::Absolute),
+            ],
+            image.xrefs_to(target)
+        );
+        assert_eq!(Vec::<Reference>::new(), image.xrefs_to(0x5000));
+    }
+
+    #[test]
+    fn test_resolve_thunk() {
+        let mut text = vec![0xcc; 0x40];
+        // jmp 0x1010
+        text[0x00..0x05].copy_from_slice(&[0xe9, 0x0b, 0x00, 0x00, 0x00]);
+        // jmp 0x1000 (thunk to a thunk)
+        text[0x05..0x07].copy_from_slice(&[0xeb, 0xf9]);
+        // real function: ret
+        text[0x10] = 0xc3;
+        // jmp [rip + 0x1000] -> pointer to 0x1010 in .rdata
+        text[0x20..0x26].copy_from_slice(&[0xff, 0x25, 0xda, 0x0f, 0x00, 0x00]);
+        // jmp self
+        text[0x30..0x32].copy_from_slice(&[0xeb, 0xfe]);
+
+        let image = Image::test_image(vec![
+            NamedMemorySection::test_section(".text", 0x1000, object::SectionKind::Text, text),
+            NamedMemorySection::test_section(
+                ".rdata",
+                0x2000,
+                object::SectionKind::ReadOnlyData,
+                0x1010u64.to_le_bytes().to_vec(),
+            ),
+        ]);
+
+        assert_eq!(Some(0x1010), image.resolve_thunk(0x1000));
+        assert_eq!(Some(0x1010), image.resolve_thunk(0x1005));
+        assert_eq!(Some(0x1010), image.resolve_thunk(0x1020));
+        assert_eq!(None, image.resolve_thunk(0x1010));
+        assert_eq!(None, image.resolve_thunk(0x1030));
+    }
+
+    #[test]
+    fn test_function_range() {
+        let pdata = [0x1000u32, 0x100c, 0x2100]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut xdata = vec![0; 0x10];
+        xdata[0] = 0x01;
+
+        let mut text = vec![0xcc; 0x100];
+        let code = [
+            0x31, 0xc0, // xor eax, eax
+            0x85, 0xc9, // test ecx, ecx
+            0x74, 0x03, // je +3
+            0xff, 0xc0, // inc eax
+            0xc3, // ret
+            0xff, 0xc8, // dec eax
+            0xc3, // ret
+        ];
+        text[..code.len()].copy_from_slice(&code);
+        // leaf function without exception data: mov eax, 1; ret
+        text[0x10..0x16].copy_from_slice(&[0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3]);
+
+        let image = Image {
+            base_address: 0x10000,
+            image_type: ImageType::PEImage(PEImage {
+                exception_directory_range: 0x12000..0x1200c,
+                ..Default::default()
+            }),
+            ..Image::test_image(vec![
+                NamedMemorySection::test_section(".text", 0x11000, object::SectionKind::Text, text),
+                NamedMemorySection::test_section(
+                    ".pdata",
+                    0x12000,
+                    object::SectionKind::ReadOnlyData,
+                    pdata,
+                ),
+                NamedMemorySection::test_section(
+                    ".xdata",
+                    0x12100,
+                    object::SectionKind::ReadOnlyData,
+                    xdata,
+                ),
+            ])
+        };
+
+        assert_eq!(
+            Some(0x11000..0x1100c),
+            image.get_root_function_range(0x11000).unwrap()
+        );
+        assert_eq!(
+            0x11000..0x1100c,
+            image.function_range_by_disassembly(0x11000).unwrap()
+        );
+        assert_eq!(0x11000..0x1100c, image.function_range(0x11000).unwrap());
+        assert_eq!(0x11000..0x1100c, image.function_range(0x11004).unwrap());
+        assert_eq!(0x11000..0x1100c, image.function_range(0x1100b).unwrap());
+
+        // disassembly stops at the first instruction not covered by exception data
+        assert_eq!(None, image.get_root_function_range(0x11010).unwrap());
+        assert_eq!(0x11010..0x11010, image.function_range(0x11010).unwrap());
+    }
+
+    #[test]
+    fn test_ensure_function_start() {
+        let pdata = [0x1000u32, 0x1020, 0x2100]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut xdata = vec![0; 0x10];
+        xdata[0] = 0x01;
+
+        let image = Image {
+            base_address: 0x10000,
+            image_type: ImageType::PEImage(PEImage {
+                exception_directory_range: 0x12000..0x1200c,
+                ..Default::default()
+            }),
+            ..Image::test_image(vec![
+                NamedMemorySection::test_section(
+                    ".text",
+                    0x11000,
+                    object::SectionKind::Text,
+                    vec![0; 0x100],
+                ),
+                NamedMemorySection::test_section(
+                    ".pdata",
+                    0x12000,
+                    object::SectionKind::ReadOnlyData,
+                    pdata,
+                ),
+                NamedMemorySection::test_section(
+                    ".xdata",
+                    0x12100,
+                    object::SectionKind::ReadOnlyData,
+                    xdata,
+                ),
+            ])
+        };
+
+        let res = resolvers::eval(&image, |ctx| {
+            Box::pin(async {
+                [0x11000, 0x11010, 0x11080].map(|address| ctx.ensure_function_start(address).ok())
+            })
+        });
+        assert_eq!([Some(0x11000), Some(0x11000), None], res);
+    }
+
+    #[test]
+    fn test_root_function_cache() {
+        const BASE: usize = 0x10000;
+        const COUNT: usize = 200;
+
+        // every third function is a root, the others chain to their predecessor
+        let mut pdata = vec![];
+        let mut xdata = vec![0; COUNT * 0x20 + 0x20];
+        let entry = |i: usize| {
+            [
+                0x1000 + i * 0x10,
+                0x1000 + i * 0x10 + 0xc,
+                0x4000 + i * 0x20,
+            ]
+            .iter()
+            .flat_map(|v| (*v as u32).to_le_bytes())
+            .collect::<Vec<_>>()
+        };
+        for i in 0..COUNT {
+            pdata.extend(entry(i));
+            let unwind = &mut xdata[i * 0x20..];
+            if i % 3 == 0 {
+                unwind[0] = 0x01;
+            } else {
+                let count = i % 4;
+                unwind[0] = 0x21;
+                unwind[2] = count as u8;
+                let chained = (4 + 2 * count).next_multiple_of(4);
+                unwind[chained..chained + 12].copy_from_slice(&entry(i - 1));
+            }
+        }
+
+        let pdata_len = pdata.len();
+        let image = Image {
+            base_address: BASE,
+            image_type: ImageType::PEImage(PEImage {
+                exception_directory_range: 0x13000..0x13000 + pdata_len,
+                exception_children_cache: Default::default(),
+                root_function_cache: Default::default(),
+                preferred_base: Default::default(),
+                relocations: Default::default(),
+            }),
+            ..Image::test_image(vec![
+                NamedMemorySection::test_section(
+                    ".text",
+                    0x11000,
+                    object::SectionKind::Text,
+                    vec![0; 0x1000],
+                ),
+                NamedMemorySection::test_section(
+                    ".pdata",
+                    0x13000,
+                    object::SectionKind::ReadOnlyData,
+                    pdata,
+                ),
+                NamedMemorySection::test_section(
+                    ".xdata",
+                    0x14000,
+                    object::SectionKind::ReadOnlyData,
+                    xdata,
+                ),
+            ])
+        };
+        let ImageType::PEImage(pe) = &image.image_type else {
+            unreachable!()
+        };
+
+        let function = |i: usize| RuntimeFunction {
+            range: BASE + 0x1000 + i * 0x10..BASE + 0x1000 + i * 0x10 + 0xc,
+            unwind: BASE + 0x4000 + i * 0x20,
+        };
+        for address in (0x11000..0x11000 + COUNT * 0x10 + 0x20).step_by(2) {
+            let i = (address - 0x11000) / 0x10;
+            let expected =
+                (i < COUNT && address < function(i).range.end).then(|| function(i / 3 * 3));
+
+            // uncached lookup
+            let uncached = pe
+                .get_function(&image, address)
+                .unwrap()
+                .map(|f| pe.walk_root_function(&image, f).unwrap());
+
+            assert_eq!(expected, uncached, "{address:x}");
+            assert_eq!(
+                expected,
+                image.get_root_function(address).unwrap(),
+                "{address:x}"
+            );
+        }
+        assert_eq!(None, image.get_root_function(0x10000).unwrap());
+        assert_eq!(None, image.get_root_function(usize::MAX).unwrap());
     }
 }
@@ -14,6 +14,7 @@ use scanner::{Pattern, Xref};
 use std::{
     borrow::Cow,
     collections::HashMap,
+    num::NonZeroUsize,
     ops::{Index, Range, RangeFrom, RangeTo},
     path::Path,
 };
@@ -23,6 +24,27 @@ use object::{File, Object, ObjectSection};
 
 use image::Image;
 
+/// Named result of a single resolver returned by [`scan_file`]
+pub type NamedResolution = (
+    &'static str,
+    resolvers::Result<std::sync::Arc<dyn resolvers::Resolution>>,
+);
+
+/// Read and parse the executable at `path` and run `resolvers` against it. Results align 1:1
+/// with `resolvers`. Only fails if the file cannot be read or parsed, individual resolver
+/// failures are returned alongside their name.
+#[cfg(any(feature = "image-pe", feature = "image-elf", feature = "image-macho"))]
+pub fn scan_file<P: AsRef<Path>>(
+    path: P,
+    resolvers: &[&'static resolvers::NamedResolver],
+) -> Result<Vec<NamedResolution>> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    let image = Image::builder().build(&data)?;
+    let results = image.resolve_many(&resolvers.iter().map(|r| r.getter).collect::<Vec<_>>());
+    Ok(resolvers.iter().map(|r| r.name).zip(results).collect())
+}
+
 pub struct ResolveContext<'data, 'pattern> {
     pub exe: &'data Image<'data>,
     pub memory: &'data Memory<'data>,
@@ -35,10 +57,19 @@ pub struct ResolveContext<'data, 'pattern> {
 pub struct Resolution {
     pub address: usize,
 }
+impl Resolution {
+    /// Address moved from an image loaded at `image_base` to one loaded at `new_base`, e.g. to
+    /// compare against a debugger attached to a running game
+    pub fn rebased(&self, image_base: usize, new_base: usize) -> usize {
+        self.address.wrapping_sub(image_base).wrapping_add(new_base)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Scan {
     pub section: Option<object::SectionKind>,
+    /// Only accept matches whose address is a multiple of `align`
+    pub align: Option<NonZeroUsize>,
     pub scan_type: ScanType,
 }
 #[derive(Debug, Clone)]
@@ -89,6 +120,7 @@ impl<S> PatternConfig<S> {
             name,
             scan: Scan {
                 section,
+                align: None,
                 scan_type: pattern.into(),
             },
         }
@@ -99,10 +131,17 @@ impl<S> PatternConfig<S> {
             name,
             scan: Scan {
                 section,
+                align: None,
                 scan_type: xref.into(),
             },
         }
     }
+    /// Only accept matches whose address is a multiple of `align`, e.g. for vtables and other
+    /// pointer sized data. Panics if `align` is 0.
+    pub fn aligned(mut self, align: usize) -> Self {
+        self.scan.align = Some(NonZeroUsize::new(align).expect("align must be non-zero"));
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -152,6 +191,65 @@ impl RuntimeFunction {
     pub fn range(&self) -> Range<usize> {
         self.range.clone()
     }
+    /// Parse the x64 `UNWIND_INFO` referenced by this function
+    pub fn unwind_info<'data>(
+        &self,
+        memory: &impl MemoryTrait<'data>,
+    ) -> Result<UnwindInfo, MemoryAccessError> {
+        UnwindInfo::read(memory, self.unwind)
+    }
+}
+
+/// x64 `UNWIND_INFO` structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnwindInfo {
+    pub version: u8,
+    /// `UNW_FLAG_*` bits. `UNW_FLAG_CHAININFO` (0x4) means chained function info follows the codes
+    pub flags: u8,
+    pub prolog_size: u8,
+    pub frame_register: u8,
+    /// Scaled frame register offset (multiply by 16 for bytes)
+    pub frame_offset: u8,
+    /// Raw unwind code slots. Some operations span multiple slots
+    pub codes: Vec<UnwindCode>,
+}
+impl UnwindInfo {
+    pub fn read<'data>(
+        memory: &impl MemoryTrait<'data>,
+        address: usize,
+    ) -> Result<Self, MemoryAccessError> {
+        let header = memory.range(address..address + 4)?;
+        let count = header[2] as usize;
+        let codes = memory
+            .range(address + 4..address + 4 + count * 2)?
+            .chunks_exact(2)
+            .map(|slot| UnwindCode {
+                offset: slot[0],
+                op: slot[1] & 0xf,
+                info: slot[1] >> 4,
+            })
+            .collect();
+        Ok(UnwindInfo {
+            version: header[0] & 0x7,
+            flags: header[0] >> 3,
+            prolog_size: header[1],
+            frame_register: header[3] & 0xf,
+            frame_offset: header[3] >> 4,
+            codes,
+        })
+    }
+    pub fn has_chain_info(&self) -> bool {
+        self.flags == 0x4
+    }
+}
+
+/// Single `UNWIND_CODE` slot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnwindCode {
+    /// Offset in prolog of the end of the instruction
+    pub offset: u8,
+    pub op: u8,
+    pub info: u8,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -164,6 +262,8 @@ pub enum MemoryAccessError {
     Utf8Error,
     Utf16Error,
     MisalginedAddress(usize, usize),
+    ProtectionError(usize),
+    UnterminatedString(usize),
 }
 impl std::error::Error for MemoryAccessError {}
 impl std::fmt::Display for MemoryAccessError {
@@ -175,6 +275,16 @@ impl std::fmt::Display for MemoryAccessError {
             Self::MisalginedAddress(addr, align) => {
                 write!(f, "MisalginedAddress: address {:#x} != {:#x}", addr, align)
             }
+            Self::ProtectionError(addr) => {
+                write!(
+                    f,
+                    "ProtectionError: failed to change protection at {:#x}",
+                    addr
+                )
+            }
+            Self::UnterminatedString(addr) => {
+                write!(f, "UnterminatedString: no terminator found for {:#x}", addr)
+            }
         }
     }
 }
@@ -252,6 +362,38 @@ pub trait MemoryAccessorTrait<'data>: MemoryTrait<'data> {
                 .unwrap(),
         ))
     }
+    /// Return big-endian u16 at `address`
+    fn u16_be(&self, address: usize) -> Result<u16, MemoryAccessError> {
+        Ok(u16::from_be_bytes(
+            self.range(address..address + std::mem::size_of::<u16>())?
+                .try_into()
+                .unwrap(),
+        ))
+    }
+    /// Return big-endian i32 at `address`
+    fn i32_be(&self, address: usize) -> Result<i32, MemoryAccessError> {
+        Ok(i32::from_be_bytes(
+            self.range(address..address + std::mem::size_of::<i32>())?
+                .try_into()
+                .unwrap(),
+        ))
+    }
+    /// Return big-endian u32 at `address`
+    fn u32_be(&self, address: usize) -> Result<u32, MemoryAccessError> {
+        Ok(u32::from_be_bytes(
+            self.range(address..address + std::mem::size_of::<u32>())?
+                .try_into()
+                .unwrap(),
+        ))
+    }
+    /// Return big-endian u64 at `address`
+    fn u64_be(&self, address: usize) -> Result<u64, MemoryAccessError> {
+        Ok(u64::from_be_bytes(
+            self.range(address..address + std::mem::size_of::<u64>())?
+                .try_into()
+                .unwrap(),
+        ))
+    }
     /// Return ptr (usize) at `address`
     fn ptr(&self, address: usize) -> Result<usize, MemoryAccessError> {
         Ok(self.u64_le(address)? as usize)
@@ -286,26 +428,93 @@ pub trait MemoryAccessorTrait<'data>: MemoryTrait<'data> {
 
         Ok(String::from_utf16(data)?)
     }
+
+    /// Read null terminated big-endian wide string from `address`. A trailing unpaired byte is
+    /// ignored.
+    fn read_wstring_be(&self, address: usize) -> Result<String, MemoryAccessError> {
+        let data = &self
+            .range_from(address..)?
+            .chunks_exact(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .take_while(|n| *n != 0)
+            .collect::<Vec<u16>>();
+
+        Ok(String::from_utf16(data)?)
+    }
+
+    /// Read null terminated string of at most `max_len` bytes (excluding terminator) from
+    /// `address`
+    fn read_string_max(&self, address: usize, max_len: usize) -> Result<String, MemoryAccessError> {
+        let data = self.range_from(address..)?;
+        let data = &data[..data.len().min(max_len.saturating_add(1))];
+        let len = memchr::memchr(0, data).ok_or(MemoryAccessError::UnterminatedString(address))?;
+
+        Ok(std::str::from_utf8(&data[..len])?.to_string())
+    }
+
+    /// Read null terminated wide string of at most `max_len` chars (excluding terminator) from
+    /// `address`
+    fn read_wstring_max(
+        &self,
+        address: usize,
+        max_len: usize,
+    ) -> Result<String, MemoryAccessError> {
+        let mut data = vec![];
+        for chunk in self
+            .range_from(address..)?
+            .chunks_exact(2)
+            .take(max_len.saturating_add(1))
+        {
+            match u16::from_le_bytes([chunk[0], chunk[1]]) {
+                0 => return Ok(String::from_utf16(&data)?),
+                c => data.push(c),
+            }
+        }
+        Err(MemoryAccessError::UnterminatedString(address))
+    }
 }
 
 impl<'data, T: MemoryTrait<'data>> MemoryAccessorTrait<'data> for T {}
 
+/// Memory that can be patched
+pub trait WritableMemoryTrait {
+    /// Write `data` at `address`
+    fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError>;
+    /// Write u32 at `address`
+    fn write_u32_le(&mut self, address: usize, value: u32) -> Result<(), MemoryAccessError> {
+        self.write_bytes(address, &value.to_le_bytes())
+    }
+}
+
 impl<'data, T: MemoryBlockTrait<'data>> MemoryTrait<'data> for T {
     fn index(&self, address: usize) -> Result<u8, MemoryAccessError> {
-        // TODO bounds
-        Ok(self.data()[address - self.address()])
+        address
+            .checked_sub(self.address())
+            .and_then(|i| self.data().get(i))
+            .copied()
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)
     }
     fn range(&self, range: Range<usize>) -> Result<&[u8], MemoryAccessError> {
-        // TODO bounds
-        Ok(&self.data()[range.start - self.address()..range.end - self.address()])
+        range
+            .start
+            .checked_sub(self.address())
+            .zip(range.end.checked_sub(self.address()))
+            .and_then(|(start, end)| self.data().get(start..end))
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)
     }
     fn range_from(&self, range: RangeFrom<usize>) -> Result<&[u8], MemoryAccessError> {
-        // TODO bounds
-        Ok(&self.data()[range.start - self.address()..])
+        range
+            .start
+            .checked_sub(self.address())
+            .and_then(|start| self.data().get(start..))
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)
     }
     fn range_to(&self, range: RangeTo<usize>) -> Result<&[u8], MemoryAccessError> {
-        // TODO bounds
-        Ok(&self.data()[..range.end - self.address()])
+        range
+            .end
+            .checked_sub(self.address())
+            .and_then(|end| self.data().get(..end))
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)
     }
 }
 
@@ -336,6 +545,19 @@ impl<'data> MemoryBlockTrait<'data> for MemorySection<'data> {
         &self.data
     }
 }
+impl WritableMemoryTrait for MemorySection<'_> {
+    fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let start = address
+            .checked_sub(self.address)
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)?;
+        let end = start
+            .checked_add(data.len())
+            .filter(|end| *end <= self.data.len())
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)?;
+        self.data.to_mut()[start..end].copy_from_slice(data);
+        Ok(())
+    }
+}
 
 pub struct NamedMemorySection<'data> {
     name: String,
@@ -361,6 +583,16 @@ impl<'data> NamedMemorySection<'data> {
     }
 }
 impl NamedMemorySection<'_> {
+    fn into_owned(self) -> NamedMemorySection<'static> {
+        NamedMemorySection {
+            name: self.name,
+            kind: self.kind,
+            section: MemorySection {
+                address: self.section.address,
+                data: Cow::Owned(self.section.data.into_owned()),
+            },
+        }
+    }
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -393,6 +625,19 @@ pub struct Memory<'data> {
     sections: Vec<NamedMemorySection<'data>>,
 }
 
+impl Memory<'_> {
+    /// Copy any borrowed section data so the memory no longer borrows the image data
+    pub fn into_owned(self) -> Memory<'static> {
+        Memory {
+            sections: self
+                .sections
+                .into_iter()
+                .map(NamedMemorySection::into_owned)
+                .collect(),
+        }
+    }
+}
+
 impl<'data> Memory<'data> {
     pub fn new(object: &File<'data>) -> Result<Self> {
         Ok(Self {
@@ -441,21 +686,61 @@ impl<'data> Memory<'data> {
                 .collect::<Result<Vec<_>>>()?,
         })
     }
+    /// Build memory from arbitrary `(address, data, name, kind)` regions, e.g. for test fixtures
+    /// or regions already dumped from a live process
+    pub fn from_regions(regions: Vec<(u64, Vec<u8>, String, object::SectionKind)>) -> Self {
+        Self {
+            sections: regions
+                .into_iter()
+                .map(|(address, data, name, kind)| {
+                    NamedMemorySection::new(name, address as usize, kind, data)
+                })
+                .collect(),
+        }
+    }
     pub fn sections(&self) -> &[NamedMemorySection] {
         &self.sections
     }
+    /// Section containing `address`. Sections may overlap (e.g. a small aligned section inside
+    /// `.text`) in which case the one with the most data is returned, ties going to the first.
     pub fn get_section_containing(
         &self,
         address: usize,
     ) -> Result<&NamedMemorySection<'data>, MemoryAccessError> {
         self.sections
             .iter()
-            .find(|section| {
+            .filter(|section| {
                 address >= section.section.address
                     && address < section.section.address + section.section.data.len()
             })
+            .min_by_key(|section| std::cmp::Reverse(section.section.data.len()))
             .ok_or(MemoryAccessError::MemoryOutOfBoundsError)
     }
+    pub fn get_section_by_name(&self, name: &str) -> Option<&NamedMemorySection<'data>> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+    /// Return slice of u8 at `range` which may span multiple sections as long as they are
+    /// contiguous. Only copies if `range` does not fit within a single section.
+    pub fn range_contiguous(
+        &self,
+        range: Range<usize>,
+    ) -> Result<Cow<'_, [u8]>, MemoryAccessError> {
+        let first = self.get_section_containing(range.start)?;
+        if range.end <= first.address() + first.len() {
+            return first.range(range).map(Cow::Borrowed);
+        }
+
+        let mut data = Vec::with_capacity(range.len());
+        let mut address = range.start;
+        while address < range.end {
+            // fails if the next section does not start exactly where the previous ended
+            let section = self.get_section_containing(address)?;
+            let end = range.end.min(section.address() + section.len());
+            data.extend_from_slice(section.range(address..end)?);
+            address = end;
+        }
+        Ok(Cow::Owned(data))
+    }
     pub fn find<F>(&self, kind: object::SectionKind, filter: F) -> Option<usize>
     where
         F: Fn(usize, &[u8]) -> bool,
@@ -480,10 +765,8 @@ impl<'data> Memory<'data> {
 impl Index<usize> for Memory<'_> {
     type Output = u8;
     fn index(&self, index: usize) -> &Self::Output {
-        self.sections
-            .iter()
-            .find_map(|section| section.section.data.get(index - section.section.address))
-            .unwrap()
+        let section = self.get_section_containing(index).unwrap();
+        &section.section.data[index - section.section.address]
     }
 }
 impl Index<Range<usize>> for Memory<'_> {
@@ -508,21 +791,43 @@ impl Index<Range<usize>> for Memory<'_> {
 
 pub trait Addressable {
     fn rip(&self) -> usize;
+    /// Like [`Self::rip`] for a displacement followed by `extra_offset` more bytes of the
+    /// instruction, e.g. the immediate of `cmp byte ptr [rip+x], imm8`
+    fn rip_at(&self, extra_offset: usize) -> usize;
     fn ptr(&self) -> usize;
+    fn u8(&self) -> u8;
+    fn u16(&self) -> u16;
     fn u32(&self) -> u32;
+    fn i32(&self) -> i32;
+    fn i64(&self) -> i64;
 }
 impl Addressable for patternsleuth_scanner::Capture<'_> {
     fn rip(&self) -> usize {
-        (self.address + 4)
-            .checked_add_signed(i32::from_le_bytes(self.data.try_into().unwrap()) as isize)
+        self.rip_at(0)
+    }
+    fn rip_at(&self, extra_offset: usize) -> usize {
+        (self.address + 4 + extra_offset)
+            .checked_add_signed(self.i32() as isize)
             .unwrap()
     }
     fn ptr(&self) -> usize {
         usize::from_le_bytes(self.data.try_into().unwrap())
     }
+    fn u8(&self) -> u8 {
+        u8::from_le_bytes(self.data.try_into().unwrap())
+    }
+    fn u16(&self) -> u16 {
+        u16::from_le_bytes(self.data.try_into().unwrap())
+    }
     fn u32(&self) -> u32 {
         u32::from_le_bytes(self.data.try_into().unwrap())
     }
+    fn i32(&self) -> i32 {
+        i32::from_le_bytes(self.data.try_into().unwrap())
+    }
+    fn i64(&self) -> i64 {
+        i64::from_le_bytes(self.data.try_into().unwrap())
+    }
 }
 
 pub trait Matchable<'data> {
@@ -531,6 +836,11 @@ pub trait Matchable<'data> {
         pattern: &Pattern,
         address: usize,
     ) -> Result<Option<Vec<patternsleuth_scanner::Capture<'data>>>, MemoryAccessError>;
+    fn captures_named(
+        &'data self,
+        pattern: &Pattern,
+        address: usize,
+    ) -> Result<Option<HashMap<String, patternsleuth_scanner::Capture<'data>>>, MemoryAccessError>;
 }
 
 impl<'data> Matchable<'data> for Memory<'data> {
@@ -543,10 +853,22 @@ impl<'data> Matchable<'data> for Memory<'data> {
         // TODO bounds check data passed to captures
         Ok(pattern.captures(s.data(), s.address(), address - s.address()))
     }
+    fn captures_named(
+        &'data self,
+        pattern: &Pattern,
+        address: usize,
+    ) -> Result<Option<HashMap<String, patternsleuth_scanner::Capture<'data>>>, MemoryAccessError>
+    {
+        let s = self.get_section_containing(address)?;
+        Ok(pattern.captures_named(s.data(), s.address(), address - s.address()))
+    }
 }
 
 pub mod disassemble {
-    use std::{collections::HashSet, ops::Range};
+    use std::{
+        collections::{BTreeMap, BTreeSet, HashSet},
+        ops::Range,
+    };
 
     use iced_x86::{Decoder, DecoderOptions, FlowControl, Formatter, Instruction, NasmFormatter};
 
@@ -574,7 +896,7 @@ pub mod disassemble {
         address: usize,
     ) -> Result<Option<Instruction>, MemoryAccessError> {
         Ok(Decoder::with_ip(
-            64,
+            exe.bitness.bits(),
             exe.memory.range_from(address..)?,
             address as u64,
             DecoderOptions::NONE,
@@ -589,9 +911,42 @@ pub mod disassemble {
         Exit,
     }
 
+    #[derive(Debug, Clone, Copy)]
+    pub struct DisassembleOptions {
+        /// Descend into targets of direct calls
+        pub follow_calls: bool,
+        /// Maximum number of nested calls to follow
+        pub max_depth: usize,
+        /// Continue at the target of unconditional jumps. Tail calls are jumps too so when
+        /// enabled traversal may wander into other functions. When disabled an unconditional
+        /// jump ends the current block like a `ret`.
+        pub follow_unconditional_branches: bool,
+    }
+    impl Default for DisassembleOptions {
+        fn default() -> Self {
+            Self {
+                follow_calls: false,
+                max_depth: 0,
+                follow_unconditional_branches: true,
+            }
+        }
+    }
+
     pub fn disassemble<'mem, 'img: 'mem, F>(
         exe: &'img Image<'mem>,
         address: usize,
+        visitor: F,
+    ) -> Result<(), MemoryAccessError>
+    where
+        F: FnMut(&Instruction) -> Result<Control, MemoryAccessError>,
+    {
+        disassemble_with(exe, address, DisassembleOptions::default(), visitor)
+    }
+
+    pub fn disassemble_with<'mem, 'img: 'mem, F>(
+        exe: &'img Image<'mem>,
+        address: usize,
+        options: DisassembleOptions,
         mut visitor: F,
     ) -> Result<(), MemoryAccessError>
     where
@@ -599,9 +954,11 @@ pub mod disassemble {
     {
         struct Ctx<'mem, 'img: 'mem> {
             exe: &'img Image<'mem>,
-            queue: Vec<usize>,
+            /// Pending blocks and their call depth
+            queue: Vec<(usize, usize)>,
             visited: HashSet<usize>,
             address: usize,
+            depth: usize,
             block: &'mem [u8],
             decoder: Decoder<'mem>,
             instruction: Instruction,
@@ -613,8 +970,14 @@ pub mod disassemble {
             queue: Default::default(),
             visited: Default::default(),
             address,
+            depth: 0,
             block,
-            decoder: Decoder::with_ip(64, block, address as u64, DecoderOptions::NONE),
+            decoder: Decoder::with_ip(
+                exe.bitness.bits(),
+                block,
+                address as u64,
+                DecoderOptions::NONE,
+            ),
             instruction: Default::default(),
         };
 
@@ -646,13 +1009,18 @@ pub mod disassemble {
                 //println!("starting at {address:x}");
                 self.address = address;
                 self.block = self.exe.memory.range_from(self.address..)?;
-                self.decoder =
-                    Decoder::with_ip(64, self.block, self.address as u64, DecoderOptions::NONE);
+                self.decoder = Decoder::with_ip(
+                    self.exe.bitness.bits(),
+                    self.block,
+                    self.address as u64,
+                    DecoderOptions::NONE,
+                );
                 Ok(())
             }
             /// Returns true if pop was successful
             fn pop(&mut self) -> Result<bool, MemoryAccessError> {
-                Ok(if let Some(next) = self.queue.pop() {
+                Ok(if let Some((next, depth)) = self.queue.pop() {
+                    self.depth = depth;
                     self.start(next)?;
                     true
                 } else {
@@ -701,21 +1069,29 @@ pub mod disassemble {
 
             match ctx.instruction.flow_control() {
                 FlowControl::Next => {}
-                FlowControl::UnconditionalBranch => {
+                FlowControl::UnconditionalBranch if options.follow_unconditional_branches => {
                     // TODO figure out how to handle tail calls
                     ctx.start(ctx.instruction.near_branch_target() as usize)?;
                 }
+                FlowControl::UnconditionalBranch => {
+                    if !ctx.pop()? {
+                        break;
+                    }
+                }
                 //FlowControl::IndirectBranch => todo!(),
                 FlowControl::ConditionalBranch => {
                     ctx.queue
-                        .push(ctx.instruction.near_branch_target() as usize);
+                        .push((ctx.instruction.near_branch_target() as usize, ctx.depth));
                 }
                 FlowControl::Return => {
                     if !ctx.pop()? {
                         break;
                     }
                 }
-                //FlowControl::Call => todo!(),
+                FlowControl::Call if options.follow_calls && ctx.depth < options.max_depth => {
+                    ctx.queue
+                        .push((ctx.instruction.near_branch_target() as usize, ctx.depth + 1));
+                }
                 //FlowControl::IndirectCall => todo!(),
                 //FlowControl::Interrupt => todo!(),
                 //FlowControl::XbeginXabortXend => todo!(),
@@ -725,4 +1101,656 @@ pub mod disassemble {
         }
         Ok(())
     }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum CfgEdgeKind {
+        /// Execution continues into the next block
+        Fallthrough,
+        /// Conditional or unconditional branch taken
+        Branch,
+        /// Call from within the source block. Target is not part of the graph
+        Call,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct CfgEdge {
+        /// Start address of the source block
+        pub from: usize,
+        pub to: usize,
+        pub kind: CfgEdgeKind,
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    pub struct CfgGraph {
+        /// Basic blocks sorted by start address
+        pub blocks: Vec<Range<usize>>,
+        pub edges: Vec<CfgEdge>,
+    }
+
+    impl CfgGraph {
+        /// Render graph in Graphviz DOT format
+        pub fn to_dot(&self) -> String {
+            let mut dot = String::new();
+            dot.push_str("digraph cfg {\n");
+            dot.push_str("    node [shape=box fontname=monospace];\n");
+            for block in &self.blocks {
+                dot.push_str(&format!(
+                    "    \"{:x}\" [label=\"{:x}..{:x}\"];\n",
+                    block.start, block.start, block.end
+                ));
+            }
+            for edge in &self.edges {
+                let attrs = match edge.kind {
+                    CfgEdgeKind::Fallthrough => "label=fallthrough",
+                    CfgEdgeKind::Branch => "label=branch color=blue",
+                    CfgEdgeKind::Call => "label=call style=dashed",
+                };
+                dot.push_str(&format!(
+                    "    \"{:x}\" -> \"{:x}\" [{attrs}];\n",
+                    edge.from, edge.to
+                ));
+            }
+            dot.push_str("}\n");
+            dot
+        }
+    }
+
+    /// Build the control flow graph of the function starting at `address`. Calls are recorded as
+    /// edges but not followed.
+    pub fn control_flow_graph(
+        exe: &Image<'_>,
+        address: usize,
+    ) -> Result<CfgGraph, MemoryAccessError> {
+        let mut instructions = BTreeMap::new();
+        let mut leaders = BTreeSet::from([address]);
+        let mut queue = vec![address];
+
+        while let Some(start) = queue.pop() {
+            let mut decoder = Decoder::with_ip(
+                exe.bitness.bits(),
+                exe.memory.range_from(start..)?,
+                start as u64,
+                DecoderOptions::NONE,
+            );
+            for inst in decoder.iter() {
+                let ip = inst.ip() as usize;
+                if instructions.insert(ip, inst).is_some() {
+                    break;
+                }
+                match inst.flow_control() {
+                    FlowControl::ConditionalBranch => {
+                        let target = inst.near_branch_target() as usize;
+                        leaders.insert(target);
+                        leaders.insert(inst.next_ip() as usize);
+                        queue.push(target);
+                    }
+                    FlowControl::UnconditionalBranch => {
+                        let target = inst.near_branch_target() as usize;
+                        leaders.insert(target);
+                        queue.push(target);
+                        break;
+                    }
+                    FlowControl::Next | FlowControl::Call | FlowControl::IndirectCall => {}
+                    _ => break,
+                }
+            }
+        }
+
+        let mut graph = CfgGraph::default();
+        let mut block_start = None;
+        for inst in instructions.values() {
+            let start = *block_start.get_or_insert(inst.ip() as usize);
+            let next = inst.next_ip() as usize;
+            let flow = inst.flow_control();
+
+            if flow == FlowControl::Call {
+                graph.edges.push(CfgEdge {
+                    from: start,
+                    to: inst.near_branch_target() as usize,
+                    kind: CfgEdgeKind::Call,
+                });
+            }
+
+            let falls_through = matches!(
+                flow,
+                FlowControl::Next | FlowControl::Call | FlowControl::IndirectCall
+            );
+            if falls_through && !leaders.contains(&next) && instructions.contains_key(&next) {
+                continue;
+            }
+
+            graph.blocks.push(start..next);
+            block_start = None;
+
+            let mut edge = |to, kind| {
+                graph.edges.push(CfgEdge {
+                    from: start,
+                    to,
+                    kind,
+                })
+            };
+            match flow {
+                FlowControl::ConditionalBranch => {
+                    edge(inst.near_branch_target() as usize, CfgEdgeKind::Branch);
+                    edge(next, CfgEdgeKind::Fallthrough);
+                }
+                FlowControl::UnconditionalBranch => {
+                    edge(inst.near_branch_target() as usize, CfgEdgeKind::Branch);
+                }
+                _ if falls_through && instructions.contains_key(&next) => {
+                    edge(next, CfgEdgeKind::Fallthrough);
+                }
+                _ => {}
+            }
+        }
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn oob<T>() -> Result<T, MemoryAccessError> {
+        Err(MemoryAccessError::MemoryOutOfBoundsError)
+    }
+
+    #[test]
+    fn test_rebased() {
+        let res = Resolution {
+            address: 0x140123450,
+        };
+        assert_eq!(0x7ff600123450, res.rebased(0x140000000, 0x7ff600000000));
+        assert_eq!(0x123450, res.rebased(0x140000000, 0));
+        // identity
+        assert_eq!(0x140123450, res.rebased(0x140000000, 0x140000000));
+    }
+
+    #[test]
+    #[should_panic(expected = "align must be non-zero")]
+    fn test_aligned_zero() {
+        PatternConfig::new((), "zero".to_string(), None, Pattern::new("aa").unwrap()).aligned(0);
+    }
+
+    #[test]
+    fn test_memory_block_bounds() {
+        let section = MemorySection {
+            address: 0x100,
+            data: Cow::Borrowed(&[1, 2, 3, 4]),
+        };
+
+        // index
+        assert_eq!(oob(), section.index(0xff));
+        assert_eq!(oob(), section.index(0));
+        assert_eq!(Ok(1), section.index(0x100));
+        assert_eq!(Ok(4), section.index(0x103));
+        assert_eq!(oob(), section.index(0x104));
+        assert_eq!(oob(), section.index(usize::MAX));
+
+        // range
+        assert_eq!(oob(), section.range(0xff..0x102));
+        assert_eq!(oob(), section.range(0..0x1));
+        assert_eq!(Ok(&[1, 2][..]), section.range(0x100..0x102));
+        assert_eq!(Ok(&[1, 2, 3, 4][..]), section.range(0x100..0x104));
+        assert_eq!(Ok(&[][..]), section.range(0x104..0x104));
+        assert_eq!(oob(), section.range(0x102..0x105));
+        assert_eq!(oob(), section.range(0x105..0x106));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 0x103..0x101;
+        assert_eq!(oob(), section.range(reversed));
+
+        // range_from
+        assert_eq!(oob(), section.range_from(0xff..));
+        assert_eq!(Ok(&[1, 2, 3, 4][..]), section.range_from(0x100..));
+        assert_eq!(Ok(&[4][..]), section.range_from(0x103..));
+        assert_eq!(Ok(&[][..]), section.range_from(0x104..));
+        assert_eq!(oob(), section.range_from(0x105..));
+
+        // range_to
+        assert_eq!(oob(), section.range_to(..0xff));
+        assert_eq!(Ok(&[][..]), section.range_to(..0x100));
+        assert_eq!(Ok(&[1, 2, 3, 4][..]), section.range_to(..0x104));
+        assert_eq!(oob(), section.range_to(..0x105));
+
+        // accessors
+        assert_eq!(Ok(0x04030201), section.u32_le(0x100));
+        assert_eq!(Ok(0x01020304), section.u32_be(0x100));
+        assert_eq!(oob(), section.u32_le(0x101));
+        assert_eq!(oob(), section.u32_le(0xfe));
+    }
+
+    #[test]
+    fn test_write_memory() {
+        let data = [0u8; 8];
+        let mut section = MemorySection {
+            address: 0x100,
+            data: Cow::Borrowed(&data),
+        };
+
+        assert_eq!(Ok(()), section.write_bytes(0x102, &[0x90, 0x90]));
+        assert_eq!(Ok(()), section.write_u32_le(0x104, 0x12345678));
+        assert_eq!(Ok(0x9090), section.u16_le(0x102));
+        assert_eq!(Ok(0x12345678), section.u32_le(0x104));
+        assert_eq!(
+            Ok(&[0, 0, 0x90, 0x90, 0x78, 0x56, 0x34, 0x12][..]),
+            section.range(0x100..0x108)
+        );
+        // backing buffer is copied on write
+        assert_eq!([0u8; 8], data);
+
+        // out of bounds writes leave memory untouched
+        assert_eq!(oob(), section.write_bytes(0xff, &[1, 2]));
+        assert_eq!(oob(), section.write_bytes(0x107, &[1, 2]));
+        assert_eq!(oob(), section.write_u32_le(0x105, 0));
+        assert_eq!(oob(), section.write_bytes(0x109, &[]));
+        assert_eq!(oob(), section.write_bytes(usize::MAX, &[1]));
+        assert_eq!(Ok(()), section.write_bytes(0x108, &[]));
+        assert_eq!(
+            Ok(&[0, 0, 0x90, 0x90, 0x78, 0x56, 0x34, 0x12][..]),
+            section.range(0x100..0x108)
+        );
+    }
+
+    #[test]
+    fn test_read_string_max() {
+        let section = |data: &'static [u8]| MemorySection {
+            address: 0x100,
+            data: Cow::Borrowed(data),
+        };
+        let unterminated = || Err(MemoryAccessError::UnterminatedString(0x100));
+
+        // exactly at the limit
+        let s = section(b"abcd\0efgh");
+        assert_eq!(Ok("abcd".to_string()), s.read_string_max(0x100, 4));
+        assert_eq!(Ok("abcd".to_string()), s.read_string_max(0x100, 5));
+        assert_eq!(Ok("".to_string()), s.read_string_max(0x104, 0));
+        // one over
+        assert_eq!(unterminated(), s.read_string_max(0x100, 3));
+        // no terminator before end of section
+        assert_eq!(unterminated(), section(b"abcd").read_string_max(0x100, 100));
+        assert_eq!(oob(), s.read_string_max(0x10b, 4));
+
+        let s = section(b"a\0b\0c\0\0\0d\0");
+        assert_eq!(Ok("abc".to_string()), s.read_wstring_max(0x100, 3));
+        assert_eq!(Ok("abc".to_string()), s.read_wstring_max(0x100, usize::MAX));
+        assert_eq!(unterminated(), s.read_wstring_max(0x100, 2));
+        assert_eq!(
+            unterminated(),
+            section(b"a\0b\0c").read_wstring_max(0x100, 100)
+        );
+    }
+
+    #[test]
+    fn test_big_endian() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+        let section = MemorySection {
+            address: 0x100,
+            data: Cow::Borrowed(&data),
+        };
+
+        assert_eq!(Ok(0x3412), section.u16_le(0x100));
+        assert_eq!(Ok(0x1234), section.u16_be(0x100));
+        assert_eq!(Ok(0x78563412), section.u32_le(0x100));
+        assert_eq!(Ok(0x12345678), section.u32_be(0x100));
+        assert_eq!(Ok(0x78563412), section.i32_le(0x100));
+        assert_eq!(Ok(0x12345678), section.i32_be(0x100));
+        assert_eq!(Ok(-0x65432110), section.i32_be(0x104));
+        assert_eq!(Ok(0xf0debc9a78563412), section.u64_le(0x100));
+        assert_eq!(Ok(0x123456789abcdef0), section.u64_be(0x100));
+        assert_eq!(oob(), section.u64_be(0x101));
+
+        let wide = |data: &'static [u8]| MemorySection {
+            address: 0,
+            data: Cow::Borrowed(data),
+        };
+        assert_eq!(Ok("ab".to_string()), wide(b"a\0b\0\0\0").read_wstring(0));
+        assert_eq!(Ok("ab".to_string()), wide(b"\0a\0b\0\0").read_wstring_be(0));
+        // unterminated with a trailing unpaired byte
+        assert_eq!(Ok("ab".to_string()), wide(b"\0a\0b\0").read_wstring_be(0));
+        assert_eq!(Ok("".to_string()), wide(b"\0").read_wstring_be(0));
+    }
+
+    #[cfg(feature = "image-pe")]
+    impl<'data> Image<'data> {
+        /// 64-bit PE image at 0x1000 made up of `sections`
+        pub(crate) fn test_image(sections: Vec<NamedMemorySection<'data>>) -> Self {
+            use image::{pe::PEImage, Bitness, ImageType};
+
+            Image {
+                base_address: 0x1000,
+                bitness: Bitness::B64,
+                endianness: object::Endianness::Little,
+                memory: Memory { sections },
+                #[cfg(feature = "symbols")]
+                symbols: None,
+                imports: Default::default(),
+                exports: Default::default(),
+                forwarded_exports: Default::default(),
+                image_type: ImageType::PEImage(PEImage::default()),
+            }
+        }
+    }
+
+    #[cfg(feature = "image-pe")]
+    impl<'data> NamedMemorySection<'data> {
+        /// Section for use with [`Image::test_image`]
+        pub(crate) fn test_section(
+            name: &str,
+            address: usize,
+            kind: object::SectionKind,
+            data: impl Into<Cow<'data, [u8]>>,
+        ) -> Self {
+            Self::new(name.to_string(), address, kind, data)
+        }
+    }
+
+    /// 64-bit image with a single .text section at 0x1000
+    #[cfg(feature = "image-pe")]
+    fn text_image(text: &'static [u8]) -> Image<'static> {
+        Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x1000,
+            object::SectionKind::Text,
+            text,
+        )])
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_control_flow_graph() {
+        use disassemble::{control_flow_graph, CfgEdge, CfgEdgeKind};
+
+        let image = text_image(&[
+            0xe8, 0xfb, 0x0f, 0x00, 0x00, // call 0x2000
+            0xff, 0xc9, // dec ecx
+            0x75, 0xf7, // jne 0x1000
+            0xc3, // ret
+        ]);
+
+        let graph = control_flow_graph(&image, 0x1000).unwrap();
+        assert_eq!(vec![0x1000..0x1009, 0x1009..0x100a], graph.blocks);
+        let edge = |from, to, kind| CfgEdge { from, to, kind };
+        assert_eq!(
+            vec![
+                edge(0x1000, 0x2000, CfgEdgeKind::Call),
+                edge(0x1000, 0x1000, CfgEdgeKind::Branch),
+                edge(0x1000, 0x1009, CfgEdgeKind::Fallthrough),
+            ],
+            graph.edges
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"1000\" -> \"1009\" [label=fallthrough];"));
+        assert!(dot.contains("\"1009\" [label=\"1009..100a\"];"));
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_disassemble_follow_calls() {
+        use disassemble::{disassemble_with, Control, DisassembleOptions};
+
+        let image = text_image(&[
+            0xe8, 0x0b, 0x00, 0x00, 0x00, // call 0x1010
+            0xc3, // ret
+            0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, // padding
+            0x31, 0xc0, // xor eax, eax
+            0xc3, // ret
+        ]);
+        let visit = |options| {
+            let mut visited = vec![];
+            disassemble_with(&image, 0x1000, options, |inst| {
+                visited.push(inst.ip() as usize);
+                Ok(Control::Continue)
+            })
+            .unwrap();
+            visited
+        };
+
+        assert_eq!(vec![0x1000, 0x1005], visit(DisassembleOptions::default()));
+        assert_eq!(
+            vec![0x1000, 0x1005, 0x1010, 0x1012],
+            visit(DisassembleOptions {
+                follow_calls: true,
+                max_depth: 1,
+                ..Default::default()
+            })
+        );
+        // depth limit reached
+        assert_eq!(
+            vec![0x1000, 0x1005],
+            visit(DisassembleOptions {
+                follow_calls: true,
+                max_depth: 0,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_disassemble_tail_call() {
+        use disassemble::{disassemble_with, Control, DisassembleOptions};
+
+        let image = text_image(&[
+            0x85, 0xc9, // test ecx, ecx
+            0x74, 0x02, // je 0x1006
+            0xeb, 0x0a, // jmp 0x1010 (tail call)
+            0x31, 0xc0, // xor eax, eax
+            0xc3, // ret
+            0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, // padding
+            0x33, 0xc0, // xor eax, eax
+            0xc3, // ret
+        ]);
+        let visit = |options| {
+            let mut visited = vec![];
+            disassemble_with(&image, 0x1000, options, |inst| {
+                visited.push(inst.ip() as usize);
+                Ok(Control::Continue)
+            })
+            .unwrap();
+            visited.sort();
+            visited
+        };
+
+        assert_eq!(
+            vec![0x1000, 0x1002, 0x1004, 0x1006, 0x1008, 0x1010, 0x1012],
+            visit(DisassembleOptions::default())
+        );
+        assert_eq!(
+            vec![0x1000, 0x1002, 0x1004, 0x1006, 0x1008],
+            visit(DisassembleOptions {
+                follow_unconditional_branches: false,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_overlapping_sections() {
+        let text = (0..0x100).map(|i| i as u8).collect::<Vec<_>>();
+        let section = |name: &str, address, data: Vec<u8>| {
+            NamedMemorySection::new(name.to_string(), address, object::SectionKind::Text, data)
+        };
+        let memory = Memory {
+            sections: vec![
+                section(".empty", 0x1000, vec![]),
+                section(".tiny", 0x1000, vec![0; 0x10]),
+                section(".text", 0x1000, text),
+            ],
+        };
+
+        assert_eq!(
+            ".text",
+            memory.get_section_containing(0x1008).unwrap().name()
+        );
+        assert_eq!(0x08, memory[0x1008]);
+        // straddles the end of .tiny
+        assert_eq!(0x0f0e0d0c, memory.u32_le(0x100c).unwrap());
+        assert_eq!(0x1716151413121110, memory.u64_le(0x1010).unwrap());
+        assert_eq!(oob(), memory.get_section_containing(0x1100).map(|_| ()));
+    }
+
+    #[test]
+    fn test_addressable() {
+        use patternsleuth_scanner::Capture;
+        let capture = |address, data: &'static [u8]| Capture { address, data };
+
+        assert_eq!(0xf0, capture(0x1000, &[0xf0]).u8());
+        assert_eq!(0x1234, capture(0x1000, &[0x34, 0x12]).u16());
+        assert_eq!(0x12345678, capture(0x1000, &[0x78, 0x56, 0x34, 0x12]).u32());
+        assert_eq!(-0x10, capture(0x1000, &[0xf0, 0xff, 0xff, 0xff]).i32());
+        assert_eq!(
+            -2,
+            capture(0x1000, &[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).i64()
+        );
+        assert_eq!(
+            0x140000000,
+            capture(0x1000, &[0x00, 0x00, 0x00, 0x40, 0x01, 0x00, 0x00, 0x00]).ptr()
+        );
+
+        // negative displacement relative to the end of the displacement
+        let disp = capture(0x1000, &[0xf0, 0xff, 0xff, 0xff]);
+        assert_eq!(0x0ff4, disp.rip());
+        assert_eq!(0x0ff4, disp.rip_at(0));
+        // followed by an imm8
+        assert_eq!(0x0ff5, disp.rip_at(1));
+        assert_eq!(0x1110, capture(0x1000, &[0x0c, 0x01, 0x00, 0x00]).rip());
+    }
+
+    #[test]
+    fn test_from_regions() {
+        let memory = Memory::from_regions(vec![
+            (
+                0x1000,
+                vec![0x48, 0x8b, 0x05, 0xc3],
+                ".text".to_string(),
+                object::SectionKind::Text,
+            ),
+            (
+                0x2000,
+                b"hello\0world\0".to_vec(),
+                ".rdata".to_string(),
+                object::SectionKind::ReadOnlyData,
+            ),
+        ]);
+
+        let text = memory.get_section_containing(0x1002).unwrap();
+        assert_eq!(".text", text.name());
+        assert_eq!(object::SectionKind::Text, text.kind());
+        assert_eq!(
+            ".rdata",
+            memory.get_section_containing(0x2006).unwrap().name()
+        );
+        assert_eq!(oob(), memory.get_section_containing(0x1004).map(|_| ()));
+
+        assert_eq!(Ok(&[0x8b, 0x05][..]), memory.range(0x1001..0x1003));
+        assert_eq!(oob(), memory.range(0x1002..0x1006));
+        assert_eq!(Ok("hello".to_string()), memory.read_string(0x2000));
+        assert_eq!(Ok("world".to_string()), memory.read_string(0x2006));
+    }
+
+    #[test]
+    fn test_unwind_info() {
+        // sub rsp, 0x28 / push rbx / push rbp with rbp as frame register
+        let xdata = vec![
+            0x09, 0x0a, 0x03,
+            0x25, // version 1, UNW_FLAG_EHANDLER, prolog 0xa, 3 codes, rbp+0x20
+            0x0a, 0x42, // UWOP_ALLOC_SMALL 0x28
+            0x06, 0x50, // UWOP_PUSH_NONVOL rbp
+            0x05, 0x30, // UWOP_PUSH_NONVOL rbx
+            0x00, 0x00, // alignment
+        ];
+        let memory = Memory {
+            sections: vec![NamedMemorySection::new(
+                ".xdata".to_string(),
+                0x2000,
+                object::SectionKind::ReadOnlyData,
+                xdata,
+            )],
+        };
+        let function = RuntimeFunction {
+            range: 0x1000..0x1040,
+            unwind: 0x2000,
+        };
+
+        let info = function.unwind_info(&memory).unwrap();
+        assert_eq!(
+            UnwindInfo {
+                version: 1,
+                flags: 1,
+                prolog_size: 0xa,
+                frame_register: 5,
+                frame_offset: 2,
+                codes: vec![
+                    UnwindCode {
+                        offset: 0x0a,
+                        op: 2,
+                        info: 4
+                    },
+                    UnwindCode {
+                        offset: 0x06,
+                        op: 0,
+                        info: 5
+                    },
+                    UnwindCode {
+                        offset: 0x05,
+                        op: 0,
+                        info: 3
+                    },
+                ],
+            },
+            info
+        );
+        assert!(!info.has_chain_info());
+
+        // code slots run past the end of the section
+        let truncated = RuntimeFunction {
+            unwind: 0x2004,
+            ..function
+        };
+        assert_eq!(oob(), truncated.unwind_info(&memory).map(|_| ()));
+    }
+
+    #[test]
+    fn test_range_contiguous() {
+        let section = |name: &str, address, data: &'static [u8]| {
+            NamedMemorySection::new(name.to_string(), address, object::SectionKind::Data, data)
+        };
+        let memory = Memory {
+            sections: vec![
+                section(".a", 0x100, &[1, 2, 3, 4]),
+                section(".b", 0x104, &[5, 6]),
+                section(".c", 0x106, &[7, 8]),
+                section(".d", 0x200, &[9, 10]),
+            ],
+        };
+
+        // fits a single section
+        let res = memory.range_contiguous(0x101..0x103).unwrap();
+        assert!(matches!(res, Cow::Borrowed(_)));
+        assert_eq!(&[2, 3], res.as_ref());
+        assert!(matches!(
+            memory.range_contiguous(0x100..0x104).unwrap(),
+            Cow::Borrowed(&[1, 2, 3, 4])
+        ));
+
+        // straddles the seam
+        assert_eq!(oob(), memory.range(0x102..0x106));
+        let res = memory.range_contiguous(0x102..0x106).unwrap();
+        assert!(matches!(res, Cow::Owned(_)));
+        assert_eq!(&[3, 4, 5, 6], res.as_ref());
+
+        // spans three sections
+        assert_eq!(
+            &[4, 5, 6, 7],
+            memory.range_contiguous(0x103..0x107).unwrap().as_ref()
+        );
+
+        // gap between sections
+        assert_eq!(oob(), memory.range_contiguous(0x106..0x201));
+        // past the end
+        assert_eq!(oob(), memory.range_contiguous(0x200..0x203));
+        // before the start
+        assert_eq!(oob(), memory.range_contiguous(0xff..0x102));
+    }
 }
@@ -65,6 +65,29 @@ mod linux {
         bail!("no main module found")
     }
 
+    /// List running processes as (PID, executable name) pairs. The name is taken from the first
+    /// argument of the command line so WINE processes report their .exe rather than the loader
+    pub fn list_processes() -> Result<Vec<(i32, String)>> {
+        let mut processes = vec![];
+        for entry in std::fs::read_dir("/proc")? {
+            let entry = entry?;
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            // process may have exited since listing
+            let Ok(cmdline) = std::fs::read(entry.path().join("cmdline")) else {
+                continue;
+            };
+            let arg0 = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+            let arg0 = String::from_utf8_lossy(arg0);
+            let name = arg0.rsplit(['/', '\\']).next().unwrap_or_default();
+            if !name.is_empty() {
+                processes.push((pid, name.to_string()));
+            }
+        }
+        Ok(processes)
+    }
+
     pub fn read_image_from_pid<'data>(pid: i32) -> Result<Image<'data>> {
         let main_module = find_main_module(pid)?;
 
@@ -98,13 +121,17 @@ pub use macos::*;
 
 #[cfg(target_os = "macos")]
 mod macos {
-    use anyhow::Result;
+    use anyhow::{bail, Result};
 
     use crate::Image;
 
     pub fn read_image_from_pid<'data>(pid: i32) -> Result<Image<'data>> {
         todo!()
     }
+
+    pub fn list_processes() -> Result<Vec<(i32, String)>> {
+        bail!("listing processes is not supported on macOS")
+    }
 }
 
 #[cfg(windows)]
@@ -118,10 +145,10 @@ mod windows {
     use crate::image::pe::PEImage;
     use crate::{Image, Memory};
 
-    use windows::Win32::Foundation::HMODULE;
+    use windows::Win32::Foundation::{CloseHandle, HMODULE};
     use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
     use windows::Win32::System::ProcessStatus::{
-        EnumProcessModules, GetModuleInformation, MODULEINFO,
+        EnumProcessModules, EnumProcesses, GetModuleBaseNameW, GetModuleInformation, MODULEINFO,
     };
     use windows::Win32::System::Threading::{
         OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
@@ -184,4 +211,38 @@ mod windows {
 
         PEImage::read_inner_memory::<String>(base, None, false, memory, object)
     }
+
+    /// List running processes as (PID, executable name) pairs. Processes which cannot be opened
+    /// are skipped
+    pub fn list_processes() -> Result<Vec<(i32, String)>> {
+        let mut pids = vec![0u32; 4096];
+        let mut out_len = 0;
+        unsafe {
+            EnumProcesses(
+                pids.as_mut_ptr(),
+                (pids.len() * std::mem::size_of::<u32>()) as u32,
+                &mut out_len,
+            )?;
+        }
+        pids.truncate(out_len as usize / std::mem::size_of::<u32>());
+
+        let mut processes = vec![];
+        for pid in pids {
+            let name = unsafe {
+                let Ok(process) =
+                    OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, false, pid)
+                else {
+                    continue;
+                };
+                let mut name = [0u16; 260];
+                let len = GetModuleBaseNameW(process, HMODULE::default(), &mut name);
+                let _ = CloseHandle(process);
+                String::from_utf16_lossy(&name[..len as usize])
+            };
+            if !name.is_empty() {
+                processes.push((pid as i32, name));
+            }
+        }
+        Ok(processes)
+    }
 }
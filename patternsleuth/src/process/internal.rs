@@ -1,13 +1,71 @@
+#[cfg(any(target_os = "linux", windows))]
+use std::ops::Range;
+
+#[cfg(any(target_os = "linux", windows))]
+use crate::{MemoryAccessError, WritableMemoryTrait};
+
+/// Writable view of the current process' memory. Page protection is temporarily lifted for the
+/// duration of each write.
+#[cfg(any(target_os = "linux", windows))]
+pub struct WritableMemory {
+    range: Range<usize>,
+}
+
+#[cfg(any(target_os = "linux", windows))]
+impl WritableMemory {
+    /// # Safety
+    /// `range` must remain mapped for the lifetime of the returned value and writes must not race
+    /// with other threads executing or accessing the patched memory
+    pub unsafe fn new(range: Range<usize>) -> Self {
+        Self { range }
+    }
+    /// Writable view spanning all sections of `image`
+    ///
+    /// # Safety
+    /// `image` must have been read from the current process (see [`read_image`])
+    pub unsafe fn from_image(image: &crate::Image<'_>) -> Self {
+        let sections = image.memory.sections();
+        let start = sections
+            .iter()
+            .map(|s| s.address())
+            .min()
+            .unwrap_or_default();
+        let end = sections
+            .iter()
+            .map(|s| s.address() + s.len())
+            .max()
+            .unwrap_or_default();
+        Self { range: start..end }
+    }
+}
+
+#[cfg(any(target_os = "linux", windows))]
+impl WritableMemoryTrait for WritableMemory {
+    fn write_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let end = address
+            .checked_add(data.len())
+            .ok_or(MemoryAccessError::MemoryOutOfBoundsError)?;
+        if address < self.range.start || end > self.range.end {
+            return Err(MemoryAccessError::MemoryOutOfBoundsError);
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+        write_protected(address, data)
+    }
+}
+
 #[cfg(target_os = "linux")]
 pub use linux::*;
 
 #[cfg(target_os = "linux")]
 mod linux {
+    use std::ops::Range;
     use std::ptr::{null, null_mut};
 
     use anyhow::Result;
 
-    use crate::Image;
+    use crate::{Image, MemoryAccessError};
     use libc::{dl_iterate_phdr, Elf64_Addr, Elf64_Phdr, Elf64_Sxword, Elf64_Xword, PT_LOAD};
 
     #[repr(C)]
@@ -107,6 +165,61 @@ mod linux {
             Image::read(Some(base_addr), data, exe_path, false)
         }
     }
+
+    /// Find the mapping containing `address` and its protection flags from `/proc/self/maps`
+    fn get_protection(address: usize) -> Option<(Range<usize>, i32)> {
+        let maps = std::fs::read_to_string("/proc/self/maps").ok()?;
+        maps.lines().find_map(|line| {
+            let (range, rest) = line.split_once(' ')?;
+            let (start, end) = range.split_once('-')?;
+            let range =
+                usize::from_str_radix(start, 16).ok()?..usize::from_str_radix(end, 16).ok()?;
+            if !range.contains(&address) {
+                return None;
+            }
+            let prot = [
+                (b'r', libc::PROT_READ),
+                (b'w', libc::PROT_WRITE),
+                (b'x', libc::PROT_EXEC),
+            ]
+            .iter()
+            .zip(rest.bytes())
+            .filter(|((flag, _), c)| flag == c)
+            .fold(libc::PROT_NONE, |prot, ((_, p), _)| prot | p);
+            Some((range, prot))
+        })
+    }
+
+    pub(super) fn write_protected(address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let end = address + data.len();
+
+        // writes may span several mappings with differing protection
+        let mut cur = address;
+        while cur < end {
+            let (map, prot) = get_protection(cur).ok_or(MemoryAccessError::ProtectionError(cur))?;
+            let chunk_end = map.end.min(end);
+            let page = cur & !(page_size - 1);
+            let len = chunk_end - page;
+            let protect = |prot| unsafe { libc::mprotect(page as _, len, prot) } == 0;
+
+            if prot & libc::PROT_WRITE == 0 && !protect(prot | libc::PROT_WRITE) {
+                return Err(MemoryAccessError::ProtectionError(cur));
+            }
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    data[cur - address..].as_ptr(),
+                    cur as *mut u8,
+                    chunk_end - cur,
+                );
+            }
+            if prot & libc::PROT_WRITE == 0 && !protect(prot) {
+                return Err(MemoryAccessError::ProtectionError(cur));
+            }
+            cur = chunk_end;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(windows)]
@@ -117,13 +230,15 @@ mod windows {
     use anyhow::{Context, Result};
     use object::{Object, ObjectSection};
     use windows::Win32::System::{
+        Diagnostics::Debug::FlushInstructionCache,
         LibraryLoader::GetModuleHandleA,
+        Memory::{VirtualProtect, PAGE_EXECUTE_READWRITE, PAGE_PROTECTION_FLAGS},
         ProcessStatus::{GetModuleInformation, MODULEINFO},
         Threading::GetCurrentProcess,
     };
 
     use crate::image::pe::PEImage;
-    use crate::{Image, Memory};
+    use crate::{Image, Memory, MemoryAccessError};
 
     pub fn read_image<'data>() -> Result<Image<'data>> {
         let main_module =
@@ -162,4 +277,16 @@ mod windows {
 
         PEImage::read_inner_memory::<String>(image_base_address, None, false, memory, object)
     }
+    pub(super) fn write_protected(address: usize, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let ptr = address as *const std::ffi::c_void;
+        let err = |_| MemoryAccessError::ProtectionError(address);
+        let mut old = PAGE_PROTECTION_FLAGS::default();
+        unsafe {
+            VirtualProtect(ptr, data.len(), PAGE_EXECUTE_READWRITE, &mut old).map_err(err)?;
+            std::ptr::copy_nonoverlapping(data.as_ptr(), address as *mut u8, data.len());
+            VirtualProtect(ptr, data.len(), old, &mut old).map_err(err)?;
+            FlushInstructionCache(GetCurrentProcess(), Some(ptr), data.len()).map_err(err)?;
+        }
+        Ok(())
+    }
 }
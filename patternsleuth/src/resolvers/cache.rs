@@ -0,0 +1,201 @@
+//! Disk-backed cache of resolver results
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use anyhow::Result;
+
+use super::{CancelToken, NamedResolver, Resolution};
+use crate::Image;
+
+/// Serialized successful results keyed by resolver name. Errors are never cached so failing
+/// resolvers are retried on the next run.
+pub type CachedResults = BTreeMap<String, Arc<dyn Resolution>>;
+
+type Results = Vec<super::Result<Arc<dyn Resolution>>>;
+
+/// Cache of resolver results stored as one JSON file per image in `dir`, keyed by
+/// [`Image::content_hash`] and a version which should change whenever resolvers do
+pub struct ResolveCache {
+    dir: PathBuf,
+    version: String,
+}
+
+impl ResolveCache {
+    /// Cache in `dir` versioned by the patternsleuth crate version
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self::with_version(dir, env!("CARGO_PKG_VERSION"))
+    }
+    /// Cache in `dir` which only reuses results stored with the same `version`, e.g. a git
+    /// commit hash
+    pub fn with_version<P: Into<PathBuf>, V: Into<String>>(dir: P, version: V) -> Self {
+        Self {
+            dir: dir.into(),
+            version: version.into(),
+        }
+    }
+    fn path(&self, hash: u64) -> PathBuf {
+        self.dir
+            .join(&self.version)
+            .join(format!("{hash:016x}.json"))
+    }
+    /// Return cached results for image `hash` or None if missing or unreadable
+    pub fn get(&self, hash: u64) -> Option<CachedResults> {
+        let data = std::fs::read(self.path(hash)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+    /// Store `results` for image `hash`, replacing any existing entry
+    pub fn put(&self, hash: u64, results: &CachedResults) -> Result<()> {
+        let path = self.path(hash);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_vec(results)?)?;
+        Ok(())
+    }
+    /// Same as [`Image::resolve_many`] but only runs resolvers without a cached result for this
+    /// image. Successful results are merged into the cache.
+    pub fn resolve_many(
+        &self,
+        image: &Image<'_>,
+        resolvers: &[&'static NamedResolver],
+    ) -> Result<Results> {
+        Ok(self
+            .resolve_many_cancellable(image, resolvers, &CancelToken::new())?
+            .expect("token is never cancelled"))
+    }
+    /// Same as [`Self::resolve_many`] but stops early like [`Image::resolve_many_cancellable`].
+    /// Nothing is cached if cancelled.
+    pub fn resolve_many_cancellable(
+        &self,
+        image: &Image<'_>,
+        resolvers: &[&'static NamedResolver],
+        cancel: &CancelToken,
+    ) -> Result<super::Result<Results>> {
+        let hash = image.content_hash();
+        let mut cached = self.get(hash).unwrap_or_default();
+
+        let missing = resolvers
+            .iter()
+            .filter(|r| !cached.contains_key(r.name))
+            .collect::<Vec<_>>();
+
+        let mut fresh = BTreeMap::new();
+        if !missing.is_empty() {
+            let getters = missing.iter().map(|r| r.getter).collect::<Vec<_>>();
+            let results = match image.resolve_many_cancellable(&getters, cancel) {
+                Ok(results) => results,
+                Err(err) => return Ok(Err(err)),
+            };
+
+            for (resolver, res) in missing.iter().zip(results) {
+                if let Ok(res) = &res {
+                    cached.insert(resolver.name.to_string(), res.clone());
+                }
+                fresh.insert(resolver.name, res);
+            }
+            self.put(hash, &cached)?;
+        }
+
+        Ok(Ok(resolvers
+            .iter()
+            .map(|r| match fresh.get(r.name) {
+                Some(res) => res.clone(),
+                None => Ok(cached[r.name].clone()),
+            })
+            .collect()))
+    }
+}
+
+#[cfg(all(test, feature = "image-pe"))]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::resolvers::{DynResolverFactory, Singleton};
+    use crate::NamedMemorySection;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static FAILING_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct CacheTest(usize);
+    impl Singleton for CacheTest {
+        fn get(&self) -> Option<usize> {
+            Some(self.0)
+        }
+    }
+    #[typetag::serde]
+    impl Resolution for CacheTest {}
+
+    fn cache_test() -> &'static DynResolverFactory {
+        static FACTORY: DynResolverFactory = DynResolverFactory {
+            factory: |ctx| {
+                Box::pin(async {
+                    CALLS.fetch_add(1, Ordering::SeqCst);
+                    let res = ctx.scan(patternsleuth_scanner::Pattern::new("bb").unwrap());
+                    let address = super::super::ensure_one(res.await)?;
+                    Ok(Arc::new(CacheTest(address)) as Arc<dyn Resolution>)
+                })
+            },
+        };
+        &FACTORY
+    }
+    static RESOLVER: NamedResolver = NamedResolver {
+        name: "CacheTest",
+        getter: cache_test,
+    };
+
+    fn cache_test_failing() -> &'static DynResolverFactory {
+        static FACTORY: DynResolverFactory = DynResolverFactory {
+            factory: |ctx| {
+                Box::pin(async {
+                    FAILING_CALLS.fetch_add(1, Ordering::SeqCst);
+                    let res = ctx.scan(patternsleuth_scanner::Pattern::new("dd").unwrap());
+                    let address = super::super::ensure_one(res.await)?;
+                    Ok(Arc::new(CacheTest(address)) as Arc<dyn Resolution>)
+                })
+            },
+        };
+        &FACTORY
+    }
+    static FAILING_RESOLVER: NamedResolver = NamedResolver {
+        name: "CacheTestFailing",
+        getter: cache_test_failing,
+    };
+
+    #[test]
+    fn test_resolve_cache() {
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x1000,
+            object::SectionKind::Text,
+            &[0xaa, 0xbb, 0xcc][..],
+        )]);
+
+        let dir = std::env::temp_dir().join(format!("patternsleuth-cache-{}", std::process::id()));
+        let cache = ResolveCache::new(&dir);
+        // must not change between builds or existing caches are invalidated
+        assert_eq!(0x02e64d74b49142af, image.content_hash());
+        assert!(cache.get(image.content_hash()).is_none());
+
+        let resolvers = [&RESOLVER, &FAILING_RESOLVER];
+        let first = cache.resolve_many(&image, &resolvers).unwrap();
+        assert_eq!(1, CALLS.load(Ordering::SeqCst));
+        assert_eq!(1, FAILING_CALLS.load(Ordering::SeqCst));
+        assert!(first[1].is_err());
+        let cached = cache.get(image.content_hash()).unwrap();
+        assert_eq!(vec!["CacheTest"], cached.keys().collect::<Vec<_>>());
+
+        // only the failing resolver is run again
+        let second = cache.resolve_many(&image, &resolvers).unwrap();
+        assert_eq!(1, CALLS.load(Ordering::SeqCst));
+        assert_eq!(2, FAILING_CALLS.load(Ordering::SeqCst));
+        assert_eq!(
+            serde_json::to_vec(&first).unwrap(),
+            serde_json::to_vec(&second).unwrap()
+        );
+        assert_eq!(Some(0x1001), second[0].as_ref().unwrap().get());
+
+        let other = ResolveCache::with_version(&dir, "other");
+        assert!(other.get(image.content_hash()).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
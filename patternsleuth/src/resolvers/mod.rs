@@ -1,3 +1,5 @@
+#[cfg(feature = "serde-resolvers")]
+pub mod cache;
 pub mod unreal;
 
 use crate::{Image, MemoryAccessError};
@@ -10,13 +12,19 @@ use futures_scopes::{
     relay::{new_relay_scope, RelayScopeLocalSpawning},
     ScopedSpawnExt, SpawnScope,
 };
-use patternsleuth_scanner::Pattern;
+use object::SectionKind;
+use patternsleuth_scanner::{Pattern, PreparedPatterns};
 use std::{
     any::{Any, TypeId},
     borrow::Cow,
     collections::HashMap,
     error::Error,
-    sync::{Arc, Mutex},
+    num::NonZeroUsize,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
 /// Given an iterator of values, returns Ok(value) if all values are equal or Err
@@ -27,6 +35,15 @@ pub fn ensure_one<T: std::fmt::Debug + PartialEq>(data: impl IntoIterator<Item =
 /// Given an iterator of values, returns Ok(value) if all values are equal or Err
 pub fn try_ensure_one<T: std::fmt::Debug + PartialEq>(
     data: impl IntoIterator<Item = Result<T>>,
+) -> Result<T> {
+    try_ensure_one_capped(data, 4)
+}
+
+/// Given an iterator of values, returns Ok(value) if all values are equal or Err. Stops
+/// collecting unique values for the error message once `max` have been found.
+pub fn try_ensure_one_capped<T: std::fmt::Debug + PartialEq>(
+    data: impl IntoIterator<Item = Result<T>>,
+    max: usize,
 ) -> Result<T> {
     let mut reached_max = false;
 
@@ -37,21 +54,72 @@ pub fn try_ensure_one<T: std::fmt::Debug + PartialEq>(
         if !unique.contains(&value) {
             unique.push(value);
         }
-        if unique.len() >= 4 {
+        if unique.len() >= max {
             reached_max = true;
             break;
         }
     }
     match unique.len() {
-        0 => Err(ResolveError::Msg("expected at least one value".into())),
+        0 => Err(ResolveError::not_found()),
         1 => Ok(unique.swap_remove(0)),
-        len => Err(ResolveError::Msg(
-            format!(
-                "found {}{len} unique values {unique:X?}",
-                if reached_max { ">=" } else { "" }
-            )
-            .into(),
-        )),
+        count => Err(ResolveError::Ambiguous {
+            count,
+            capped: reached_max,
+            values: format!("{unique:X?}"),
+        }),
+    }
+}
+
+/// Like [`ensure_one`] but each value is paired with a label describing where it came from
+/// (e.g. the pattern which matched). On disagreement the error lists the labels of each value.
+pub fn ensure_one_labeled<T: std::fmt::Debug + PartialEq, L: std::fmt::Display>(
+    data: impl IntoIterator<Item = (T, L)>,
+) -> Result<T> {
+    try_ensure_one_labeled(data.into_iter().map(Ok))
+}
+
+/// Like [`try_ensure_one`] but each value is paired with a label, see [`ensure_one_labeled`]
+pub fn try_ensure_one_labeled<T: std::fmt::Debug + PartialEq, L: std::fmt::Display>(
+    data: impl IntoIterator<Item = Result<(T, L)>>,
+) -> Result<T> {
+    let mut unique: Vec<(T, Vec<String>)> = vec![];
+    for value in data.into_iter() {
+        let (value, label) = value?;
+        match unique.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, labels)) => labels.push(label.to_string()),
+            None => unique.push((value, vec![label.to_string()])),
+        }
+    }
+    match unique.len() {
+        0 => Err(ResolveError::not_found()),
+        1 => Ok(unique.swap_remove(0).0),
+        count => Err(ResolveError::Ambiguous {
+            count,
+            capped: false,
+            values: unique
+                .iter()
+                .map(|(value, labels)| format!("{value:X?} ({})", labels.join(", ")))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }),
+    }
+}
+
+/// Given an iterator of values, returns Ok(values) with duplicates removed or Err if there are
+/// none
+pub fn ensure_all<T: std::fmt::Debug + PartialEq>(
+    data: impl IntoIterator<Item = T>,
+) -> Result<Vec<T>> {
+    let mut unique = vec![];
+    for value in data.into_iter() {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    if unique.is_empty() {
+        Err(ResolveError::not_found())
+    } else {
+        Ok(unique)
     }
 }
 
@@ -63,13 +131,43 @@ pub type Result<T> = std::result::Result<T, ResolveError>;
 )]
 pub enum ResolveError {
     Msg(Cow<'static, str>),
+    /// No candidate values were found
+    NotFound(Cow<'static, str>),
+    /// Candidate values disagreed. `count` is only a lower bound if `capped`
+    Ambiguous {
+        count: usize,
+        capped: bool,
+        /// Formatted unique values
+        values: String,
+    },
+    /// A candidate was found but failed a sanity check
+    Validation(Cow<'static, str>),
     MemoryAccessOutOfBounds(MemoryAccessError),
+    /// Evaluation was stopped early via a [`CancelToken`]
+    Cancelled,
+}
+impl ResolveError {
+    fn not_found() -> Self {
+        Self::NotFound("expected at least one value".into())
+    }
 }
 impl std::fmt::Display for ResolveError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             ResolveError::Msg(msg) => write!(f, "{msg}"),
+            ResolveError::NotFound(msg) => write!(f, "{msg}"),
+            ResolveError::Ambiguous {
+                count,
+                capped,
+                values,
+            } => write!(
+                f,
+                "found {}{count} unique values {values}",
+                if *capped { ">=" } else { "" }
+            ),
+            ResolveError::Validation(msg) => write!(f, "{msg}"),
             ResolveError::MemoryAccessOutOfBounds(err) => err.fmt(f),
+            ResolveError::Cancelled => write!(f, "cancelled"),
         }
     }
 }
@@ -99,7 +197,7 @@ impl<T> Context<T> for Option<T> {
     fn context(self, msg: &'static str) -> Result<T> {
         match self {
             Some(value) => Ok(value),
-            None => Err(ResolveError::Msg(msg.into())),
+            None => Err(ResolveError::NotFound(msg.into())),
         }
     }
 }
@@ -195,7 +293,11 @@ macro_rules! _matcharm_generator {
                     }
                 }
             )*
-            res.unwrap()
+            res.unwrap_or_else(|| {
+                Err($crate::resolvers::ResolveError::Msg(
+                    "resolver not implemented for this image type".into(),
+                ))
+            })
         }
     }
 }
@@ -216,6 +318,11 @@ macro_rules! _cfg_image_elf { ($($args:tt)*) => { $($args)* } }
 macro_rules! _cfg_image_elf {
     ($($args:tt)*) => {};
 }
+// no Mach-O specific resolvers yet so `collect` resolvers fail on Mach-O images
+#[macro_export]
+macro_rules! _cfg_image_macho {
+    ($($args:tt)*) => {};
+}
 
 #[macro_export]
 macro_rules! _impl_resolver {
@@ -264,7 +371,7 @@ macro_rules! _impl_resolver {
 macro_rules! _impl_resolver_singleton {
     (all, $name:ident, |$ctx:ident| async $x:block ) => {
         $crate::_impl_resolver_inner!($name, |$ctx| async {
-            if let Some(a) = std::env::var(concat!("PATTERNSLEUTH_RES_", stringify!($name))).ok().and_then(|s| (s.strip_prefix("0x").map(|s| usize::from_str_radix(s, 16).ok()).unwrap_or_else(|| s.parse().ok()))) {
+            if let Some(a) = $ctx.get_override(stringify!($name)) {
                 return Ok($name(a));
             }
             $x
@@ -288,7 +395,7 @@ macro_rules! _impl_resolver_singleton {
 
     (collect, $name:ident) => {
         $crate::_impl_resolver_inner!($name, |ctx| async {
-            if let Some(a) = std::env::var(concat!("PATTERNSLEUTH_RES_", stringify!($name))).ok().and_then(|s| (s.strip_prefix("0x").map(|s| usize::from_str_radix(s, 16).ok()).unwrap_or_else(|| s.parse().ok()))) {
+            if let Some(a) = ctx.get_override(stringify!($name)) {
                 return Ok($name(a));
             }
             $crate::image::image_type_reflection!(all, impl_resolver_singleton; generate; {ctx, $name})
@@ -418,6 +525,7 @@ pub use _impl_try_collector as impl_try_collector;
 pub use _matcharm_generator as matcharm_generator;
 pub mod cfg_image {
     pub use _cfg_image_elf as ElfImage;
+    pub use _cfg_image_macho as MachOImage;
     pub use _cfg_image_pe as PEImage;
 }
 
@@ -425,6 +533,36 @@ pub trait Singleton {
     fn get(&self) -> Option<usize>;
 }
 
+/// Set of addresses for resolvers with multiple valid results
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct MultiAddress(pub Vec<usize>);
+impl Singleton for MultiAddress {
+    fn get(&self) -> Option<usize> {
+        None
+    }
+}
+#[cfg_attr(feature = "serde-resolvers", typetag::serde)]
+impl Resolution for MultiAddress {}
+
+/// Number of matches for resolvers which only report how many were found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Count(pub usize);
+impl Singleton for Count {
+    fn get(&self) -> Option<usize> {
+        None
+    }
+}
+#[cfg_attr(feature = "serde-resolvers", typetag::serde)]
+impl Resolution for Count {}
+
 type AnyValue = Result<Arc<dyn Any + Send + Sync>>;
 
 #[derive(Debug)]
@@ -438,30 +576,119 @@ struct AsyncContextInnerWrite {
     resolvers: HashMap<TypeId, AnyValue>,
     pending_resolvers: HashMap<TypeId, Vec<oneshot::Sender<AnyValue>>>,
     queue: Vec<(Pattern, oneshot::Sender<PatternMatches>)>,
+    pointer_queue: Vec<(usize, oneshot::Sender<Vec<usize>>)>,
+    call_queue: Vec<(usize, oneshot::Sender<Vec<usize>>)>,
+    range_queue: Vec<(Pattern, Range<usize>, oneshot::Sender<PatternMatches>)>,
+    first_queue: Vec<(Pattern, oneshot::Sender<Option<usize>>)>,
+    kind_queue: Vec<(Pattern, SectionKind, oneshot::Sender<PatternMatches>)>,
+    graph: Option<DependencyGraph>,
 }
 
 struct AsyncContextInnerRead<'data> {
     write: Mutex<AsyncContextInnerWrite>,
     image: &'data Image<'data>,
+    overrides: Overrides,
+}
+
+/// Hardcoded addresses for singleton resolvers keyed by resolver name. An overridden resolver
+/// returns its address without scanning. Takes precedence over `PATTERNSLEUTH_RES_<Name>`
+/// environment variables.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Overrides(HashMap<String, usize>);
+
+impl Overrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn insert(&mut self, name: impl Into<String>, address: usize) -> Option<usize> {
+        self.0.insert(name.into(), address)
+    }
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.0.get(name).copied()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.0
+            .iter()
+            .map(|(name, address)| (name.as_str(), *address))
+    }
+    /// Overrides currently set via `PATTERNSLEUTH_RES_<Name>` environment variables. Variables
+    /// whose value is not a decimal or `0x` prefixed hex address are ignored.
+    pub fn from_env() -> Self {
+        Self(
+            std::env::vars()
+                .filter_map(|(key, value)| {
+                    let name = key.strip_prefix(OVERRIDE_ENV_PREFIX)?;
+                    Some((name.to_string(), parse_override(&value)?))
+                })
+                .collect(),
+        )
+    }
+}
+
+const OVERRIDE_ENV_PREFIX: &str = "PATTERNSLEUTH_RES_";
+
+fn parse_override(value: &str) -> Option<usize> {
+    match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Resolver dependencies recorded by [`AsyncContext::record_dependencies`]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// `(dependent, dependency)` resolver type names in the order they were first requested
+    pub edges: Vec<(&'static str, &'static str)>,
+    /// Resolver type names in the order they completed and whether they succeeded
+    pub nodes: Vec<(&'static str, bool)>,
+}
+
+impl DependencyGraph {
+    /// Render graph in Graphviz DOT format. Failed resolvers are drawn in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph resolvers {\n");
+        dot.push_str("    node [shape=box];\n");
+        for (name, ok) in &self.nodes {
+            let color = if *ok { "black" } else { "red" };
+            dot.push_str(&format!("    \"{name}\" [color={color}];\n"));
+        }
+        for (from, to) in &self.edges {
+            dot.push_str(&format!("    \"{from}\" -> \"{to}\";\n"));
+        }
+        dot.push_str("}\n");
+        dot
+    }
 }
 
 #[derive(Clone)]
 pub struct AsyncContext<'data> {
     read: Arc<AsyncContextInnerRead<'data>>,
+    /// Type name of the resolver this context was handed to, if any
+    current: Option<&'static str>,
 }
 
 impl<'data> AsyncContext<'data> {
-    fn new(image: &'data Image<'data>) -> Self {
+    fn new(image: &'data Image<'data>, overrides: Overrides) -> Self {
         Self {
             read: Arc::new(AsyncContextInnerRead {
                 write: Default::default(),
                 image,
+                overrides,
             }),
+            current: None,
         }
     }
     pub fn image(&self) -> &Image<'_> {
         self.read.image
     }
+    /// Address the resolver `name` is overridden to, either by the [`Overrides`] passed to
+    /// [`eval_with_overrides`] or a `PATTERNSLEUTH_RES_<name>` environment variable
+    pub fn get_override(&self, name: &str) -> Option<usize> {
+        self.read.overrides.get(name).or_else(|| {
+            parse_override(&std::env::var(format!("{OVERRIDE_ENV_PREFIX}{name}")).ok()?)
+        })
+    }
     pub async fn scan(&self, pattern: Pattern) -> Vec<usize> {
         self.scan_tagged((), pattern).await.2
     }
@@ -482,14 +709,155 @@ impl<'data> AsyncContext<'data> {
         let PatternMatches { pattern, matches } = rx.await.unwrap();
         (tag, pattern, matches)
     }
+    /// Like [`Self::scan`] but tags each match with the name of the section containing it
+    pub async fn scan_with_section(&self, pattern: Pattern) -> Vec<(usize, String)> {
+        let matches = self.scan(pattern).await;
+        let memory = &self.image().memory;
+        matches
+            .into_iter()
+            .filter_map(|address| {
+                let section = memory.get_section_containing(address).ok()?;
+                Some((address, section.name().to_string()))
+            })
+            .collect()
+    }
+    /// Scan for `pattern` only accepting matches whose address is a multiple of `align`
+    pub async fn scan_aligned(&self, pattern: Pattern, align: NonZeroUsize) -> Vec<usize> {
+        let mut matches = self.scan(pattern).await;
+        matches.retain(|address| address % align.get() == 0);
+        matches
+    }
+    /// Scan for `pattern` only within the address `range`, e.g. a function already known to
+    /// contain the match. Avoids rescanning entire sections.
+    pub async fn scan_in(&self, pattern: Pattern, range: Range<usize>) -> Vec<usize> {
+        let (tx, rx) = oneshot::channel::<PatternMatches>();
+        {
+            let mut lock = self.read.write.lock().unwrap();
+            lock.range_queue.push((pattern, range, tx));
+        }
+        rx.await.unwrap().matches
+    }
+    /// Scan for only the lowest address matching `pattern`. Cheaper than [`Self::scan`] when
+    /// only existence or the first hit is needed.
+    pub async fn scan_first(&self, pattern: Pattern) -> Option<usize> {
+        let (tx, rx) = oneshot::channel::<Option<usize>>();
+        {
+            let mut lock = self.read.write.lock().unwrap();
+            lock.first_queue.push((pattern, tx));
+        }
+        rx.await.unwrap()
+    }
+    /// Scan for `pattern` only within the section named `name`. Returns no matches if there is
+    /// no such section.
+    pub async fn scan_in_section(&self, name: &str, pattern: Pattern) -> Vec<usize> {
+        let Some(section) = self.image().memory.get_section_by_name(name) else {
+            return vec![];
+        };
+        let range = section.address()..section.address() + section.len();
+        self.scan_in(pattern, range).await
+    }
+    /// Scan for `pattern` only within sections of the given `kind`, e.g. to avoid matching code
+    /// bytes that happen to also appear in data sections.
+    pub async fn scan_in_kind(&self, kind: SectionKind, pattern: Pattern) -> Vec<usize> {
+        let (tx, rx) = oneshot::channel::<PatternMatches>();
+        {
+            let mut lock = self.read.write.lock().unwrap();
+            lock.kind_queue.push((pattern, kind, tx));
+        }
+        rx.await.unwrap().matches
+    }
+    /// Find absolute pointers to `address`. All pointers queued in the same stage are found in
+    /// a single pass over the image.
+    pub async fn scan_pointer(&self, address: usize) -> Vec<usize> {
+        let (tx, rx) = oneshot::channel::<Vec<usize>>();
+        {
+            let mut lock = self.read.write.lock().unwrap();
+            lock.pointer_queue.push((address, tx));
+        }
+        rx.await.unwrap()
+    }
+    /// Snap `address` to the start of the root function containing it. Errors if `address` is
+    /// not inside any known function.
+    pub fn ensure_function_start(&self, address: usize) -> Result<usize> {
+        match self.image().get_root_function(address)? {
+            Some(function) => Ok(function.range.start),
+            None => Err(ResolveError::Validation(
+                format!("{address:#x} is not inside a known function").into(),
+            )),
+        }
+    }
+    /// Find `lea reg, [rip + x]` instructions referencing the string literal `string` (UTF-16 if
+    /// `wide`). Returns the address of each instruction. `string` is matched as is so include a
+    /// trailing `\0` to avoid matching prefixes of longer strings.
+    pub async fn find_string_refs(&self, string: &str, wide: bool) -> Vec<usize> {
+        let bytes = if wide {
+            string.encode_utf16().flat_map(u16::to_le_bytes).collect()
+        } else {
+            string.as_bytes().to_vec()
+        };
+        let strings = self.scan(Pattern::from_bytes(bytes).unwrap()).await;
+        let refs = join_all(strings.into_iter().flat_map(|s| {
+            [
+                self.scan(Pattern::new(format!("48 8d ?? X0x{s:X}")).unwrap()),
+                self.scan(Pattern::new(format!("4c 8d ?? X0x{s:X}")).unwrap()),
+            ]
+        }))
+        .await;
+        let mut refs = refs.into_iter().flatten().collect::<Vec<_>>();
+        refs.sort();
+        refs
+    }
+    /// Scan for `string` encoded as both UTF-8 and UTF-16 for when the encoding used by the
+    /// binary is not known. Returns the sorted addresses of either encoding.
+    pub async fn scan_string_any(&self, string: &str) -> Vec<usize> {
+        let res = join_all([
+            self.scan(Pattern::from_bytes(string.as_bytes().to_vec()).unwrap()),
+            self.scan(
+                Pattern::from_bytes(string.encode_utf16().flat_map(u16::to_le_bytes).collect())
+                    .unwrap(),
+            ),
+        ])
+        .await;
+        let mut res = res.into_iter().flatten().collect::<Vec<_>>();
+        res.sort();
+        res.dedup();
+        res
+    }
+    /// Find relative `call`/`jmp` (`e8`/`e9`) instructions to `address`, returning the address of
+    /// each call site. All call targets queued in the same stage are found in a single pass.
+    pub async fn scan_calls_to(&self, address: usize) -> Vec<usize> {
+        let (tx, rx) = oneshot::channel::<Vec<usize>>();
+        {
+            let mut lock = self.read.write.lock().unwrap();
+            lock.call_queue.push((address, tx));
+        }
+        rx.await.unwrap()
+    }
+    /// Start recording which resolvers each resolver depends on. Retrieve the result with
+    /// [`Self::dependency_graph`].
+    pub fn record_dependencies(&self) {
+        let mut lock = self.read.write.lock().unwrap();
+        lock.graph.get_or_insert_with(Default::default);
+    }
+    /// Dependencies recorded since [`Self::record_dependencies`] was called
+    pub fn dependency_graph(&self) -> Option<DependencyGraph> {
+        self.read.write.lock().unwrap().graph.clone()
+    }
     pub async fn resolve<T: Send + Sync + 'static>(
         &self,
         resolver: &ResolverFactory<T>,
     ) -> Result<Arc<T>> {
         let t = TypeId::of::<T>();
+        let name = std::any::type_name::<T>();
         let rx = {
-            // first check to see if we've already computed the resolver
             let mut lock = self.read.write.lock().unwrap();
+            if let (Some(graph), Some(current)) = (&mut lock.graph, self.current) {
+                if !graph.edges.contains(&(current, name)) {
+                    graph.edges.push((current, name));
+                }
+            }
+
+            // first check to see if we've already computed the resolver
             if let Some(res) = lock.resolvers.get(&t) {
                 return res.clone().map(|ok| ok.downcast::<T>().unwrap());
             }
@@ -515,8 +883,13 @@ impl<'data> AsyncContext<'data> {
             return rx.await.unwrap().map(|ok| ok.downcast::<T>().unwrap());
         }
 
-        // compute the resolver value
-        let resolver = (resolver.factory)(self);
+        // compute the resolver value with a context that knows which resolver is asking for
+        // dependencies
+        let ctx = AsyncContext {
+            read: self.read.clone(),
+            current: Some(name),
+        };
+        let resolver = (resolver.factory)(&ctx);
         let res = resolver.await.map(Arc::new);
 
         let cache: Result<Arc<dyn Any + Send + Sync>> = match res.as_ref() {
@@ -527,6 +900,9 @@ impl<'data> AsyncContext<'data> {
         // insert new value
         let mut lock = self.read.write.lock().unwrap();
         lock.resolvers.insert(t, cache.clone());
+        if let Some(graph) = &mut lock.graph {
+            graph.nodes.push((name, res.is_ok()));
+        }
 
         // update any other listening futures
         for tx in lock.pending_resolvers.remove(&t).unwrap() {
@@ -537,15 +913,94 @@ impl<'data> AsyncContext<'data> {
     }
 }
 
-#[tracing::instrument(level = "debug", skip_all, fields(stages))]
+/// Observer of [`eval_with_progress`] progress
+pub trait ProgressSink {
+    /// Called after each stage has run until stalled with the number of resolvers currently
+    /// pending and resolved
+    fn on_stage(&mut self, _stage: usize, _pending: usize, _done: usize) {}
+    /// Called before scanning a batch of `count` queued scans
+    fn on_pattern_batch(&mut self, _count: usize) {}
+    /// Called after each section has been scanned for a batch with the time spent scanning it
+    fn on_section_scanned(&mut self, _section: &str, _elapsed: std::time::Duration) {}
+}
+impl ProgressSink for () {}
+
+/// Shared flag used to stop [`eval_cancellable`] between stages, e.g. from a Ctrl-C handler
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
 pub fn eval<F, T: Send + Sync>(image: &Image<'_>, f: F) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    eval_with_progress(image, &mut (), f)
+}
+
+pub fn eval_with_progress<F, T: Send + Sync>(
+    image: &Image<'_>,
+    progress: &mut dyn ProgressSink,
+    f: F,
+) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    match eval_cancellable(image, progress, &CancelToken::new(), f) {
+        Ok(res) => res,
+        Err(_) => unreachable!("token is never cancelled"),
+    }
+}
+
+/// Like [`eval`] but resolvers overridden in `overrides` return their hardcoded address
+pub fn eval_with_overrides<F, T: Send + Sync>(image: &Image<'_>, overrides: &Overrides, f: F) -> T
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    match eval_impl(image, &mut (), &CancelToken::new(), overrides.clone(), f) {
+        Ok(res) => res,
+        Err(_) => unreachable!("token is never cancelled"),
+    }
+}
+
+/// Like [`eval_with_progress`] but checks `cancel` before each stage, returning
+/// [`ResolveError::Cancelled`] as soon as it is set
+pub fn eval_cancellable<F, T: Send + Sync>(
+    image: &Image<'_>,
+    progress: &mut dyn ProgressSink,
+    cancel: &CancelToken,
+    f: F,
+) -> Result<T>
+where
+    F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
+{
+    eval_impl(image, progress, cancel, Default::default(), f)
+}
+
+#[tracing::instrument(level = "debug", skip_all, fields(stages))]
+fn eval_impl<F, T: Send + Sync>(
+    image: &Image<'_>,
+    progress: &mut dyn ProgressSink,
+    cancel: &CancelToken,
+    overrides: Overrides,
+    f: F,
+) -> Result<T>
 where
     F: for<'ctx> FnOnce(&'ctx AsyncContext<'_>) -> BoxFuture<'ctx, T> + Send + Sync,
 {
     {
         tracing::debug!("starting eval");
 
-        let ctx = AsyncContext::new(image);
+        let ctx = AsyncContext::new(image, overrides);
         let (rx, tx) = std::sync::mpsc::channel();
 
         let scope = new_relay_scope!();
@@ -565,41 +1020,112 @@ where
         let mut i = 0;
 
         loop {
+            if cancel.is_cancelled() {
+                tracing::debug!("cancelled");
+                break Err(ResolveError::Cancelled);
+            }
+
             i += 1;
 
             tracing::debug_span!("resolvers", stage = i).in_scope(|| {
                 pool.run_until_stalled();
             });
 
+            {
+                let lock = ctx.read.write.lock().unwrap();
+                progress.on_stage(i, lock.pending_resolvers.len(), lock.resolvers.len());
+            }
+
             if let Ok(res) = tx.try_recv() {
                 tracing::Span::current().record("stages", i);
-                break res;
+                break Ok(res);
             } else {
-                let queue: Vec<_> = std::mem::take(&mut ctx.read.write.lock().unwrap().queue);
+                let (queue, pointer_queue, call_queue, range_queue, first_queue, kind_queue) = {
+                    let mut lock = ctx.read.write.lock().unwrap();
+                    (
+                        std::mem::take(&mut lock.queue),
+                        std::mem::take(&mut lock.pointer_queue),
+                        std::mem::take(&mut lock.call_queue),
+                        std::mem::take(&mut lock.range_queue),
+                        std::mem::take(&mut lock.first_queue),
+                        std::mem::take(&mut lock.kind_queue),
+                    )
+                };
+                progress.on_pattern_batch(
+                    queue.len()
+                        + pointer_queue.len()
+                        + call_queue.len()
+                        + range_queue.len()
+                        + first_queue.len()
+                        + kind_queue.len(),
+                );
+
                 let (patterns, rx): (Vec<_>, Vec<_>) = queue.into_iter().unzip();
                 let setup = patterns.iter().collect::<Vec<_>>();
-
-                let span = tracing::debug_span!("patterns", patterns = setup.len()).entered();
+                let (first_patterns, first_rx): (Vec<_>, Vec<_>) = first_queue.into_iter().unzip();
+                let first_setup = first_patterns.iter().collect::<Vec<_>>();
+                let (pointers, pointer_rx): (Vec<_>, Vec<_>) = pointer_queue
+                    .into_iter()
+                    .map(|(address, rx)| (address as u64, rx))
+                    .unzip();
+                let (calls, call_rx): (Vec<_>, Vec<_>) = call_queue.into_iter().unzip();
+
+                let span = tracing::debug_span!(
+                    "patterns",
+                    patterns = setup.len(),
+                    first_patterns = first_setup.len(),
+                    pointers = pointers.len(),
+                    calls = calls.len()
+                )
+                .entered();
                 for p in &setup {
                     tracing::debug!("pattern = {p:?}");
                 }
 
+                // group and bin patterns once rather than once per section
+                let prepared = PreparedPatterns::new(&setup);
+                let first_prepared = PreparedPatterns::new(&first_setup);
+
                 let mut all_results = rx.into_iter().map(|rx| (rx, vec![])).collect::<Vec<_>>();
+                let mut first_results = first_rx
+                    .into_iter()
+                    .map(|rx| (rx, None::<usize>))
+                    .collect::<Vec<_>>();
+                let mut pointer_results = pointer_rx
+                    .into_iter()
+                    .map(|rx| (rx, vec![]))
+                    .collect::<Vec<_>>();
+                let mut call_results = call_rx
+                    .into_iter()
+                    .map(|rx| (rx, vec![]))
+                    .collect::<Vec<_>>();
+                let (kind_patterns, kind_rx): (Vec<_>, Vec<_>) = kind_queue
+                    .into_iter()
+                    .map(|(pattern, kind, rx)| ((pattern, kind), rx))
+                    .unzip();
+                let mut kind_results = kind_rx
+                    .into_iter()
+                    .map(|rx| (rx, vec![]))
+                    .collect::<Vec<_>>();
+                // kind restricted patterns prepared once per section kind
+                let mut kind_prepared: HashMap<SectionKind, (Vec<usize>, PreparedPatterns)> =
+                    Default::default();
 
                 for section in image.memory.sections() {
                     let span = tracing::debug_span!(
                         "section",
                         section = section.name(),
                         kind = format!("{:?}", section.kind()),
-                        results = tracing::field::Empty
+                        results = tracing::field::Empty,
+                        elapsed_us = tracing::field::Empty
                     )
                     .entered();
+                    let start = std::time::Instant::now();
 
                     let base_address = section.address();
                     let data = section.data();
 
-                    let scan_results =
-                        patternsleuth_scanner::scan_pattern(&setup, base_address, data);
+                    let scan_results = prepared.scan(base_address, data);
 
                     let mut total = 0;
 
@@ -608,14 +1134,98 @@ where
                         all_results[i].1.extend(res)
                     }
 
+                    if !first_setup.is_empty() {
+                        let first_scan_results = first_prepared.scan_first(base_address, data);
+                        for (i, res) in first_scan_results.into_iter().enumerate() {
+                            total += res.is_some() as usize;
+                            let first = &mut first_results[i].1;
+                            *first = match (*first, res) {
+                                (Some(a), Some(b)) => Some(a.min(b)),
+                                (a, b) => a.or(b),
+                            };
+                        }
+                    }
+
+                    if !pointers.is_empty() {
+                        let pointer_scan_results =
+                            patternsleuth_scanner::scan_absolute(&pointers, base_address, data);
+                        for (i, res) in pointer_scan_results.iter().enumerate() {
+                            total += res.len();
+                            pointer_results[i].1.extend(res)
+                        }
+                    }
+
+                    if !calls.is_empty() {
+                        let call_scan_results =
+                            patternsleuth_scanner::scan_relative_calls(&calls, base_address, data);
+                        for (i, res) in call_scan_results.iter().enumerate() {
+                            total += res.len();
+                            call_results[i].1.extend(res)
+                        }
+                    }
+
+                    // only scan kind restricted patterns in sections of a matching kind
+                    let (kind_indexes, kind_setup) =
+                        kind_prepared.entry(section.kind()).or_insert_with(|| {
+                            let (kind_indexes, kind_setup): (Vec<_>, Vec<_>) = kind_patterns
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, (_, kind))| *kind == section.kind())
+                                .map(|(i, (pattern, _))| (i, pattern))
+                                .unzip();
+                            (kind_indexes, PreparedPatterns::new(&kind_setup))
+                        });
+                    if !kind_indexes.is_empty() {
+                        let kind_scan_results = kind_setup.scan(base_address, data);
+                        for (i, res) in kind_indexes.iter().zip(kind_scan_results) {
+                            total += res.len();
+                            kind_results[*i].1.extend(res)
+                        }
+                    }
+
+                    let elapsed = start.elapsed();
                     span.record("results", total);
+                    span.record("elapsed_us", elapsed.as_micros() as u64);
+                    progress.on_section_scanned(section.name(), elapsed);
                 }
 
                 drop(span);
+                drop((prepared, first_prepared, kind_prepared));
 
                 for ((rx, matches), pattern) in all_results.into_iter().zip(patterns) {
                     rx.send(PatternMatches { pattern, matches }).unwrap();
                 }
+                for (rx, first) in first_results {
+                    rx.send(first).unwrap();
+                }
+                for (rx, matches) in pointer_results {
+                    rx.send(matches).unwrap();
+                }
+                for (rx, matches) in call_results {
+                    rx.send(matches).unwrap();
+                }
+                for ((rx, matches), (pattern, _)) in kind_results.into_iter().zip(kind_patterns) {
+                    rx.send(PatternMatches { pattern, matches }).unwrap();
+                }
+
+                for (pattern, range, rx) in range_queue {
+                    let mut matches = vec![];
+                    for section in image.memory.sections() {
+                        let section_range = section.address()..section.address() + section.len();
+                        if range.start < section_range.end && section_range.start < range.end {
+                            matches.extend(
+                                patternsleuth_scanner::scan_pattern_in(
+                                    &[&pattern],
+                                    section.address(),
+                                    section.data(),
+                                    range.clone(),
+                                )
+                                .swap_remove(0),
+                            );
+                        }
+                    }
+                    rx.send(PatternMatches { pattern, matches }).unwrap();
+                }
             }
         }
     }
@@ -629,12 +1239,621 @@ pub fn resolve<T: Send + Sync>(
         .map(|ok| Arc::<T>::into_inner(ok).unwrap())
 }
 
+/// Like [`resolve`] but resolvers overridden in `overrides` return their hardcoded address
+pub fn resolve_with_overrides<T: Send + Sync>(
+    image: &Image<'_>,
+    resolver: &'static ResolverFactory<T>,
+    overrides: &Overrides,
+) -> Result<T> {
+    eval_with_overrides(image, overrides, |ctx| {
+        Box::pin(async { ctx.resolve(resolver).await })
+    })
+    .map(|ok| Arc::<T>::into_inner(ok).unwrap())
+}
+
+/// Results align 1:1 with `resolvers`, so duplicated resolvers yield duplicated results
 pub fn resolve_many(
     image: &Image<'_>,
     resolvers: &[fn() -> &'static DynResolverFactory],
+) -> Vec<Result<Arc<dyn Resolution>>> {
+    resolve_many_with_progress(image, resolvers, &mut ())
+}
+
+pub fn resolve_many_with_progress(
+    image: &Image<'_>,
+    resolvers: &[fn() -> &'static DynResolverFactory],
+    progress: &mut dyn ProgressSink,
 ) -> Vec<Result<Arc<dyn Resolution>>> {
     let fns = resolvers.iter().map(|r| r().factory).collect::<Vec<_>>();
-    eval(image, |ctx| {
+    eval_with_progress(image, progress, |ctx| {
         Box::pin(async { join_all(fns.into_iter().map(|f| f(ctx))).await })
     })
 }
+
+/// Like [`resolve_many_with_progress`] but stops early with [`ResolveError::Cancelled`] once
+/// `cancel` is set
+pub fn resolve_many_cancellable(
+    image: &Image<'_>,
+    resolvers: &[fn() -> &'static DynResolverFactory],
+    progress: &mut dyn ProgressSink,
+    cancel: &CancelToken,
+) -> Result<Vec<Result<Arc<dyn Resolution>>>> {
+    let fns = resolvers.iter().map(|r| r().factory).collect::<Vec<_>>();
+    eval_cancellable(image, progress, cancel, |ctx| {
+        Box::pin(async { join_all(fns.into_iter().map(|f| f(ctx))).await })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockSink {
+        stages: Vec<(usize, usize, usize)>,
+        batches: Vec<usize>,
+        sections: Vec<(String, std::time::Duration)>,
+    }
+    impl ProgressSink for MockSink {
+        fn on_stage(&mut self, stage: usize, pending: usize, done: usize) {
+            self.stages.push((stage, pending, done));
+        }
+        fn on_pattern_batch(&mut self, count: usize) {
+            self.batches.push(count);
+        }
+        fn on_section_scanned(&mut self, section: &str, elapsed: std::time::Duration) {
+            self.sections.push((section.to_string(), elapsed));
+        }
+    }
+
+    // resolvers are keyed by type so each needs its own
+    #[derive(Debug, PartialEq)]
+    struct First(usize);
+    #[derive(Debug, PartialEq)]
+    struct Second(usize);
+
+    static FIRST: ResolverFactory<First> = ResolverFactory {
+        factory: |ctx| {
+            Box::pin(async {
+                let matches = ctx.scan(Pattern::new("aa bb").unwrap()).await;
+                Ok(First(ensure_one(matches)?))
+            })
+        },
+    };
+    static SECOND: ResolverFactory<Second> = ResolverFactory {
+        factory: |ctx| {
+            Box::pin(async {
+                let first = ctx.resolve(&FIRST).await?.0;
+                let matches = ctx
+                    .scan_in(Pattern::new("cc").unwrap(), first..first + 4)
+                    .await;
+                Ok(Second(ensure_one(matches)?))
+            })
+        },
+    };
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_eval_with_progress() {
+        use crate::NamedMemorySection;
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x1000,
+            SectionKind::Text,
+            &[0xaa, 0xbb, 0x00, 0xcc, 0xcc][..],
+        )]);
+
+        let mut sink = MockSink::default();
+        let res = eval_with_progress(&image, &mut sink, |ctx| {
+            Box::pin(async { ctx.resolve(&SECOND).await })
+        });
+        assert_eq!(Ok(Second(0x1003)), res.map(|r| Arc::into_inner(r).unwrap()));
+
+        // stage 1: both resolvers pending on the first scan
+        // stage 2: first resolved, second pending on its ranged scan
+        // stage 3: both resolved
+        assert_eq!(vec![(1, 2, 0), (2, 1, 1), (3, 0, 2)], sink.stages);
+        assert_eq!(vec![1, 1], sink.batches);
+        // one timing per section per batch
+        assert_eq!(
+            vec![".text", ".text"],
+            sink.sections
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_eval_cancelled() {
+        use crate::NamedMemorySection;
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x1000,
+            SectionKind::Text,
+            &[0xaa, 0xbb, 0x00, 0xcc, 0xcc][..],
+        )]);
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+
+        let mut sink = MockSink::default();
+        let res = eval_cancellable(&image, &mut sink, &cancel, |ctx| {
+            Box::pin(async { ctx.resolve(&SECOND).await })
+        });
+        assert_eq!(Err(ResolveError::Cancelled), res.map(|_| ()));
+        assert!(sink.stages.is_empty());
+        assert!(sink.batches.is_empty());
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_dependency_graph() {
+        use crate::NamedMemorySection;
+
+        #[derive(Debug, PartialEq)]
+        struct Third;
+        static THIRD: ResolverFactory<Third> = ResolverFactory {
+            factory: |ctx| {
+                Box::pin(async {
+                    ctx.resolve(&SECOND).await?;
+                    ctx.resolve(&FIRST).await?;
+                    Ok(Third)
+                })
+            },
+        };
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x1000,
+            SectionKind::Text,
+            &[0xaa, 0xbb, 0x00, 0xcc, 0xcc][..],
+        )]);
+
+        let (res, graph) = eval(&image, |ctx| {
+            Box::pin(async {
+                ctx.record_dependencies();
+                let res = ctx.resolve(&THIRD).await;
+                (res, ctx.dependency_graph().unwrap())
+            })
+        });
+        assert!(res.is_ok());
+
+        let first = std::any::type_name::<First>();
+        let second = std::any::type_name::<Second>();
+        let third = std::any::type_name::<Third>();
+        assert_eq!(
+            vec![(third, second), (second, first), (third, first)],
+            graph.edges
+        );
+        assert_eq!(
+            vec![(first, true), (second, true), (third, true)],
+            graph.nodes
+        );
+        assert!(graph
+            .to_dot()
+            .contains(&format!("\"{third}\" -> \"{second}\";")));
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_overrides() {
+        use crate::NamedMemorySection;
+        use unreal::ConsoleManagerSingleton;
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x1000,
+            SectionKind::Text,
+            &[0x00; 0x10][..],
+        )]);
+
+        // nothing to find so the factory fails unless short-circuited
+        assert!(image.resolve(ConsoleManagerSingleton::resolver()).is_err());
+
+        let mut overrides = Overrides::new();
+        overrides.insert("ConsoleManagerSingleton", 0x1234);
+        let res = image.resolve_with_overrides(ConsoleManagerSingleton::resolver(), &overrides);
+        assert_eq!(Some(0x1234), res.unwrap().get());
+
+        std::env::set_var("PATTERNSLEUTH_RES_TestOverridesEnv", "0x10");
+        std::env::set_var("PATTERNSLEUTH_RES_TestOverridesInvalid", "nope");
+        let env = Overrides::from_env();
+        assert_eq!(Some(0x10), env.get("TestOverridesEnv"));
+        assert_eq!(None, env.get("TestOverridesInvalid"));
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_in_section() {
+        use crate::NamedMemorySection;
+
+        let image = Image::test_image(vec![
+            NamedMemorySection::test_section(
+                ".text",
+                0x1000,
+                object::SectionKind::Text,
+                &[0xc3, 0xaa, 0xbb],
+            ),
+            NamedMemorySection::test_section(
+                ".data",
+                0x2000,
+                object::SectionKind::Data,
+                &[0x11, 0x22, 0x33],
+            ),
+        ]);
+
+        assert_eq!(
+            Some(0x2000),
+            image
+                .memory
+                .get_section_by_name(".data")
+                .map(|s| s.address())
+        );
+        assert!(image.memory.get_section_by_name(".rdata").is_none());
+
+        let res = eval(&image, |ctx| {
+            Box::pin(async {
+                let pattern = || Pattern::new("22 33").unwrap();
+                (
+                    ctx.scan_in_section(".data", pattern()).await,
+                    ctx.scan_in_section(".text", pattern()).await,
+                    ctx.scan_in_section(".rdata", pattern()).await,
+                )
+            })
+        });
+        assert_eq!((vec![0x2001], vec![], vec![]), res);
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_in_kind() {
+        use crate::NamedMemorySection;
+
+        let image = Image::test_image(vec![
+            NamedMemorySection::test_section(
+                ".text",
+                0x1000,
+                object::SectionKind::Text,
+                &[0xc3, 0xaa, 0xbb],
+            ),
+            NamedMemorySection::test_section(
+                ".data",
+                0x2000,
+                object::SectionKind::Data,
+                &[0x11, 0xaa, 0xbb],
+            ),
+        ]);
+
+        let res = eval(&image, |ctx| {
+            Box::pin(async {
+                let pattern = || Pattern::new("aa bb").unwrap();
+                (
+                    ctx.scan(pattern()).await,
+                    ctx.scan_in_kind(object::SectionKind::Text, pattern()).await,
+                    ctx.scan_in_kind(object::SectionKind::ReadOnlyData, pattern())
+                        .await,
+                )
+            })
+        });
+        assert_eq!((vec![0x1001, 0x2001], vec![0x1001], vec![]), res);
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_aligned() {
+        use crate::NamedMemorySection;
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".data",
+            0x2000,
+            object::SectionKind::Data,
+            &[0xaa, 0xbb, 0xaa, 0xbb, 0x00, 0xaa, 0xbb, 0x00, 0xaa, 0xbb][..],
+        )]);
+
+        let pattern = || Pattern::new("aa bb").unwrap();
+        let res = eval(&image, |ctx| {
+            Box::pin(async move {
+                (
+                    ctx.scan(pattern()).await,
+                    ctx.scan_aligned(pattern(), NonZeroUsize::new(4).unwrap())
+                        .await,
+                )
+            })
+        });
+        assert_eq!(
+            (vec![0x2000, 0x2002, 0x2005, 0x2008], vec![0x2000, 0x2008]),
+            res
+        );
+
+        let config = [
+            crate::PatternConfig::new((), "unaligned".to_string(), None, pattern()),
+            crate::PatternConfig::new((), "aligned".to_string(), None, pattern()).aligned(2),
+        ];
+        let scan = image.scan(&config).unwrap();
+        let addresses = |name: &str| {
+            scan.results
+                .iter()
+                .filter(|(config, _)| config.name == name)
+                .map(|(_, res)| res.address)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![0x2000, 0x2002, 0x2005, 0x2008], addresses("unaligned"));
+        assert_eq!(vec![0x2000, 0x2002, 0x2008], addresses("aligned"));
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_calls_to() {
+        use crate::NamedMemorySection;
+
+        // call sites at 0x2000, 0x2008 and 0x2010 (jmp) to 0x2100 and one to 0x2200
+        let rel = |from: usize, to: usize| ((to as i32) - (from as i32 + 5)).to_le_bytes();
+        let mut text = vec![0x90; 0x20];
+        for (at, opcode, to) in [
+            (0x00, 0xe8, 0x2100),
+            (0x08, 0xe8, 0x2100),
+            (0x10, 0xe9, 0x2100),
+            (0x18, 0xe8, 0x2200),
+        ] {
+            text[at] = opcode;
+            text[at + 1..at + 5].copy_from_slice(&rel(0x2000 + at, to));
+        }
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".text",
+            0x2000,
+            object::SectionKind::Text,
+            text,
+        )]);
+
+        let res = eval(&image, |ctx| {
+            Box::pin(async {
+                (
+                    ctx.scan_calls_to(0x2100).await,
+                    ctx.scan_calls_to(0x2200).await,
+                    ctx.scan_calls_to(0x2300).await,
+                )
+            })
+        });
+        assert_eq!((vec![0x2000, 0x2008, 0x2010], vec![0x2018], vec![]), res);
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_find_string_refs() {
+        use crate::NamedMemorySection;
+
+        let mut rdata = b"narrow\0".to_vec();
+        rdata.extend("wide\0".encode_utf16().flat_map(u16::to_le_bytes));
+        let rel = |target: usize, end: usize| ((target as i64 - end as i64) as i32).to_le_bytes();
+        let mut text = vec![0x90; 0x10];
+        // lea rdx, [rip + "wide"]
+        text[0..3].copy_from_slice(&[0x48, 0x8d, 0x15]);
+        text[3..7].copy_from_slice(&rel(0x3007, 0x2007));
+        // lea r8, [rip + "narrow"]
+        text[8..11].copy_from_slice(&[0x4c, 0x8d, 0x05]);
+        text[11..15].copy_from_slice(&rel(0x3000, 0x200f));
+
+        let image = Image::test_image(vec![
+            NamedMemorySection::test_section(".text", 0x2000, object::SectionKind::Text, text),
+            NamedMemorySection::test_section(
+                ".rdata",
+                0x3000,
+                object::SectionKind::ReadOnlyData,
+                rdata,
+            ),
+        ]);
+
+        let res = eval(&image, |ctx| {
+            Box::pin(async {
+                (
+                    ctx.find_string_refs("wide\0", true).await,
+                    ctx.find_string_refs("narrow\0", false).await,
+                    ctx.find_string_refs("wide\0", false).await,
+                )
+            })
+        });
+        assert_eq!((vec![0x2000], vec![0x2008], vec![]), res);
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_string_any() {
+        use crate::NamedMemorySection;
+
+        let mut rdata = b"r.DumpingMovie\0".to_vec();
+        rdata.extend(
+            "vr.pixeldensity\0"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".rdata",
+            0x3000,
+            object::SectionKind::ReadOnlyData,
+            rdata,
+        )]);
+
+        let res = eval(&image, |ctx| {
+            Box::pin(async {
+                futures::future::join_all(
+                    ["r.DumpingMovie", "vr.pixeldensity", "r.Missing"]
+                        .map(|s| ctx.scan_string_any(s)),
+                )
+                .await
+            })
+        });
+        assert_eq!(vec![vec![0x3000], vec![0x300f], vec![]], res);
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_scan_with_section() {
+        use crate::NamedMemorySection;
+
+        let image = Image::test_image(vec![
+            NamedMemorySection::test_section(
+                ".text",
+                0x1000,
+                object::SectionKind::Text,
+                vec![0x90, 0x12, 0x34, 0x56, 0x90, 0x12, 0x34, 0x56],
+            ),
+            NamedMemorySection::test_section(
+                ".rdata",
+                0x2000,
+                object::SectionKind::ReadOnlyData,
+                vec![0x00, 0x00, 0x12, 0x34, 0x56],
+            ),
+        ]);
+
+        let res = eval(&image, |ctx| {
+            Box::pin(async {
+                let mut res = ctx
+                    .scan_with_section(Pattern::new("12 34 56").unwrap())
+                    .await;
+                res.sort();
+                res
+            })
+        });
+        assert_eq!(
+            vec![
+                (0x1001, ".text".to_string()),
+                (0x1005, ".text".to_string()),
+                (0x2002, ".rdata".to_string()),
+            ],
+            res
+        );
+    }
+
+    #[test]
+    fn test_try_ensure_one_capped() {
+        let msg = |res: Result<usize>| match res {
+            Err(err @ ResolveError::Ambiguous { .. }) => err.to_string(),
+            other => panic!("unexpected {other:?}"),
+        };
+        let values = |n: usize| (0..n).map(Ok);
+
+        assert_eq!(Ok(1), try_ensure_one_capped([Ok(1), Ok(1)], 2));
+        // below cap
+        assert_eq!(
+            "found 2 unique values [0, 1]",
+            msg(try_ensure_one_capped(values(2), 3))
+        );
+        // exactly at cap
+        assert_eq!(
+            "found >=3 unique values [0, 1, 2]",
+            msg(try_ensure_one_capped(values(3), 3))
+        );
+        // past cap
+        assert_eq!(
+            "found >=3 unique values [0, 1, 2]",
+            msg(try_ensure_one_capped(values(10), 3))
+        );
+
+        // default cap of 4
+        assert_eq!("found 3 unique values [0, 1, 2]", msg(ensure_one(0..3)));
+        assert_eq!(
+            "found >=4 unique values [0, 1, 2, 3]",
+            msg(try_ensure_one(values(5)))
+        );
+    }
+
+    #[test]
+    fn test_ensure_one_labeled() {
+        assert_eq!(Ok(0x10), ensure_one_labeled([(0x10, "a"), (0x10, "b")]));
+        assert_eq!(
+            Err(ResolveError::Ambiguous {
+                count: 2,
+                capped: false,
+                values: "10 (pattern a, pattern c), 20 (pattern b)".into()
+            }),
+            ensure_one_labeled([
+                (0x10, "pattern a"),
+                (0x20, "pattern b"),
+                (0x10, "pattern c"),
+            ])
+        );
+        assert_eq!(
+            Err(ResolveError::not_found()),
+            ensure_one_labeled(Vec::<(usize, &str)>::new())
+        );
+        assert_eq!(
+            Err(ResolveError::Cancelled),
+            try_ensure_one_labeled([Ok((1, "a")), Err(ResolveError::Cancelled)])
+        );
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        assert_eq!(
+            Err(ResolveError::not_found()),
+            ensure_one(Vec::<usize>::new())
+        );
+        assert_eq!(
+            Err(ResolveError::not_found()),
+            ensure_all(Vec::<usize>::new())
+        );
+        assert_eq!(
+            Err(ResolveError::Ambiguous {
+                count: 2,
+                capped: false,
+                values: "[1, 2]".into()
+            }),
+            ensure_one([1, 2, 1])
+        );
+        assert_eq!(
+            Err(ResolveError::NotFound("no GWorld".into())),
+            None::<usize>.context("no GWorld")
+        );
+
+        // messages are unchanged
+        assert_eq!(
+            "expected at least one value",
+            ensure_one(Vec::<usize>::new()).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "found 2 unique values [1, 2]",
+            ensure_one([1, 2]).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_ensure_all() {
+        assert_eq!(Ok(vec![3, 1, 2]), ensure_all([3, 1, 3, 2, 1]));
+        assert_eq!(Ok(vec![1]), ensure_all([1, 1]));
+        assert!(ensure_all(Vec::<usize>::new()).is_err());
+    }
+
+    #[cfg(feature = "serde-resolvers")]
+    #[test]
+    fn test_multi_address_serde() {
+        let res: Box<dyn Resolution> = Box::new(MultiAddress(vec![0x1000, 0x2000]));
+        let json = serde_json::to_string(&res).unwrap();
+        let de: Box<dyn Resolution> = serde_json::from_str(&json).unwrap();
+        assert!(*res == *de);
+
+        let other: Box<dyn Resolution> = Box::new(MultiAddress(vec![0x1000]));
+        assert!(*res != *other);
+    }
+
+    #[cfg(feature = "serde-resolvers")]
+    #[test]
+    fn test_count_serde() {
+        let res: Box<dyn Resolution> = Box::new(Count(3));
+        let json = serde_json::to_string(&res).unwrap();
+        let de: Box<dyn Resolution> = serde_json::from_str(&json).unwrap();
+        assert!(*res == *de);
+        assert_eq!(None, de.get());
+
+        let other: Box<dyn Resolution> = Box::new(Count(4));
+        assert!(*res != *other);
+        // same value but different resolution type
+        let multi: Box<dyn Resolution> = Box::new(MultiAddress(vec![3]));
+        assert!(*res != *multi);
+    }
+}
@@ -6,7 +6,7 @@ use itertools::Itertools;
 use patternsleuth_scanner::Pattern;
 
 use crate::{
-    resolvers::{bail_out, impl_resolver, try_ensure_one},
+    resolvers::{bail_out, ensure_one, impl_resolver, try_ensure_one, unreal::util},
     MemoryAccessorTrait,
 };
 
@@ -54,7 +54,7 @@ impl_resolver!(all, EngineVersion, |ctx| async {
 
     let res = join_all(patterns.iter().map(|p| ctx.scan(Pattern::new(p).unwrap()))).await;
 
-    try_ensure_one(
+    let primary = try_ensure_one(
         res.iter()
             .flatten()
             .map(|a| {
@@ -63,15 +63,51 @@ impl_resolver!(all, EngineVersion, |ctx| async {
                     minor: ctx.image().memory.u16_le(a + 2)?,
                 })
             })
-            .filter_ok(|ver| match ver.major {
-                // TODO 4.0 can false positive so ignore it. need to harden if this is to work on 4.0 games
-                4 if (1..=27).contains(&ver.minor) => true,
-                5 if (0..).contains(&ver.minor) => true,
-                _ => false,
-            }),
+            .filter_ok(EngineVersion::is_plausible),
+    );
+    if primary.is_ok() {
+        return primary;
+    }
+
+    // fall back to branch name strings for builds where none of the patterns match
+    let strings =
+        join_all(["++UE4+Release-", "++UE5+Release-"].map(|s| ctx.scan(util::utf16_pattern(s))))
+            .await;
+
+    ensure_one(
+        strings
+            .into_iter()
+            .flatten()
+            .filter_map(|a| ctx.image().memory.read_wstring_max(a, 64).ok())
+            .filter_map(|s| EngineVersion::from_branch_name(&s))
+            .filter(EngineVersion::is_plausible),
     )
+    .or(primary)
 });
 
+impl EngineVersion {
+    fn is_plausible(&self) -> bool {
+        match self.major {
+            // TODO 4.0 can false positive so ignore it. need to harden if this is to work on 4.0 games
+            4 if (1..=27).contains(&self.minor) => true,
+            5 if (0..).contains(&self.minor) => true,
+            _ => false,
+        }
+    }
+    /// Parse version from a branch name such as "++UE4+Release-4.27"
+    fn from_branch_name(branch: &str) -> Option<Self> {
+        let (_, version) = branch.split_once("+Release-")?;
+        let (major, rest) = version.split_once('.')?;
+        let minor = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(rest, |end| &rest[..end]);
+        Some(EngineVersion {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+}
+
 /// currently seems to be 4.22+
 #[derive(Debug, PartialEq)]
 #[cfg_attr(
@@ -86,8 +122,6 @@ pub struct EngineVersionStrings {
 impl_resolver!(collect, EngineVersionStrings);
 // "++UE5+Release-{}.{}"
 impl_resolver!(ElfImage, EngineVersionStrings, |ctx| async {
-    use crate::resolvers::{ensure_one, unreal::util};
-
     let pattern_name = util::utf16_pattern("++UE5+Release-");
     let name_scan = ctx.scan(pattern_name).await;
 
@@ -181,3 +215,48 @@ impl_resolver!(PEImage, EngineVersionStrings, |ctx| async {
 
     bail_out!("not found");
 });
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_branch_name() {
+        let version = |major, minor| Some(EngineVersion { major, minor });
+        assert_eq!(
+            version(4, 27),
+            EngineVersion::from_branch_name("++UE4+Release-4.27")
+        );
+        assert_eq!(
+            version(5, 1),
+            EngineVersion::from_branch_name("++UE5+Release-5.1")
+        );
+        assert_eq!(
+            version(4, 26),
+            EngineVersion::from_branch_name("++UE4+Release-4.26-CL-0")
+        );
+        assert_eq!(None, EngineVersion::from_branch_name("++UE4+Release-"));
+        assert_eq!(None, EngineVersion::from_branch_name("++UE4+Main"));
+    }
+
+    #[cfg(feature = "image-pe")]
+    #[test]
+    fn test_branch_name_fallback() {
+        use crate::image::Image;
+        use crate::NamedMemorySection;
+
+        let mut data = vec![0; 0x10];
+        data.extend(util::utf16("++UE4+Release-4.27\0"));
+        data.extend([0; 0x10]);
+
+        let image = Image::test_image(vec![NamedMemorySection::test_section(
+            ".rdata",
+            0x1000,
+            object::SectionKind::ReadOnlyData,
+            data,
+        )]);
+
+        let version = image.resolve(EngineVersion::resolver()).unwrap();
+        assert_eq!((4, 27), (version.major, version.minor));
+    }
+}
@@ -345,7 +345,8 @@ impl_resolver_singleton!(all, FNameToStringFString, |ctx| async {
     )?))
 });
 
-/// FNamePool
+/// FNamePool, the global name pool introduced in UE 4.23. Earlier engine versions store names in
+/// a `TNameEntryArray` (GNames) instead so this is not expected to resolve for them.
 #[derive(Debug, PartialEq)]
 #[cfg_attr(
     feature = "serde-resolvers",
@@ -361,7 +362,19 @@ impl_resolver_singleton!(all, FNamePool, |ctx| async {
 
     let res = join_all(patterns.iter().map(|p| ctx.scan(Pattern::new(p).unwrap()))).await;
 
-    Ok(Self(try_ensure_one(res.iter().flatten().map(
-        |a| -> Result<usize> { Ok(ctx.image().memory.rip4(*a)?) },
-    ))?))
+    // the pool is a static object so it can't live in code. its name entries are only allocated
+    // at runtime so the contents can't be validated in an image read from disk
+    let in_code = |address| {
+        ctx.image()
+            .memory
+            .get_section_containing(address)
+            .is_ok_and(|section| section.kind() == object::SectionKind::Text)
+    };
+
+    Ok(Self(try_ensure_one(
+        res.iter()
+            .flatten()
+            .map(|a| -> Result<usize> { Ok(ctx.image().memory.rip4(*a)?) })
+            .filter(|pool| !pool.as_ref().is_ok_and(|pool| in_code(*pool))),
+    )?))
 });
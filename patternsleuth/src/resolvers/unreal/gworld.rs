@@ -0,0 +1,66 @@
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+
+use iced_x86::Code;
+
+use crate::{
+    disassemble::{disassemble, Control},
+    resolvers::{ensure_one, impl_resolver_singleton, unreal::game_loop::UGameEngineTick},
+};
+
+/// UWorldProxy GWorld
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct GWorld(pub usize);
+impl_resolver_singleton!(collect, GWorld);
+// UGameEngine::Tick saves GWorld, reassigns it for each world context and then restores it so
+// look for a global that is both loaded and stored within the function
+impl_resolver_singleton!(PEImage, GWorld, |ctx| async {
+    let tick = ctx.resolve(UGameEngineTick::resolver()).await?.0;
+    let img = ctx.image();
+
+    let mut loads = BTreeSet::new();
+    let mut stores = BTreeSet::new();
+
+    disassemble(img, tick, |inst| {
+        let cur = inst.ip() as usize;
+        if Some(tick) != img.get_root_function(cur)?.map(|f| f.range.start) {
+            return Ok(Control::Break);
+        }
+        if inst.is_ip_rel_memory_operand() {
+            let address = inst.ip_rel_memory_address() as usize;
+            match inst.code() {
+                Code::Mov_r64_rm64 => {
+                    loads.insert(address);
+                }
+                Code::Mov_rm64_r64 => {
+                    stores.insert(address);
+                }
+                _ => {}
+            }
+        }
+        Ok(Control::Continue)
+    })?;
+
+    let is_writable_data = |address: usize| {
+        img.memory.get_section_containing(address).is_ok_and(|s| {
+            matches!(
+                s.kind(),
+                object::SectionKind::Data | object::SectionKind::UninitializedData
+            )
+        })
+    };
+
+    Ok(GWorld(ensure_one(
+        loads
+            .intersection(&stores)
+            .copied()
+            .filter(|a| is_writable_data(*a)),
+    )?))
+});
+impl_resolver_singleton!(ElfImage, GWorld, |_ctx| async {
+    super::bail_out!("ElfImage unimplemented");
+});
@@ -8,7 +8,7 @@ use crate::{
     resolvers::{
         bail_out, ensure_one, impl_resolver, impl_resolver_singleton, try_ensure_one, Result,
     },
-    Addressable, Matchable, MemoryTrait,
+    Addressable, Image, Matchable, MemoryTrait,
 };
 
 /// public: void __cdecl UObject::SkipFunction(struct FFrame &, void *const, class UFunction *)
@@ -31,6 +31,37 @@ impl_resolver_singleton!(all, UObjectSkipFunction, |ctx| async {
     Ok(UObjectSkipFunction(ensure_one(res.into_iter().flatten())?))
 });
 
+/// Check that a GNatives candidate lies in a data section and that any populated entries point
+/// into code. The table is filled by static initializers so entries are only present in images
+/// read from a running process.
+fn validate_gnatives(image: &Image<'_>, address: usize) -> Result<()> {
+    use object::SectionKind;
+
+    let section = image.memory.get_section_containing(address)?;
+    if !matches!(
+        section.kind(),
+        SectionKind::Data | SectionKind::UninitializedData
+    ) {
+        bail_out!("GNatives not in a data section");
+    }
+    let pointer_size = image.bitness.pointer_size();
+    // one native per EExprToken
+    for i in 0..0x100 {
+        let Ok(ptr) = image.ptr(address + i * pointer_size) else {
+            break;
+        };
+        if ptr != 0
+            && !image
+                .memory
+                .get_section_containing(ptr)
+                .is_ok_and(|s| s.kind() == SectionKind::Text)
+        {
+            bail_out!("GNatives entry does not point to code");
+        }
+    }
+    Ok(())
+}
+
 // GNatives
 #[derive(Debug, PartialEq)]
 #[cfg_attr(
@@ -57,7 +88,9 @@ impl_resolver_singleton!(PEImage, GNatives, |ctx| async {
     while decoder.can_decode() {
         decoder.decode_out(&mut instruction);
         if instruction.code() == Code::Lea_r64_m && instruction.memory_base() == Register::RIP {
-            return Ok(GNatives(instruction.memory_displacement64() as usize));
+            let address = instruction.memory_displacement64() as usize;
+            validate_gnatives(ctx.image(), address)?;
+            return Ok(GNatives(address));
         }
     }
 
@@ -80,7 +113,9 @@ impl_resolver_singleton!(ElfImage, GNatives, |ctx| async {
     while decoder.can_decode() {
         decoder.decode_out(&mut instruction);
         if instruction.is_call_near_indirect() && instruction.memory_index_scale() == 8 {
-            return Ok(GNatives(instruction.memory_displacement32() as usize));
+            let address = instruction.memory_displacement32() as usize;
+            validate_gnatives(ctx.image(), address)?;
+            return Ok(GNatives(address));
         }
     }
 
@@ -8,11 +8,14 @@ pub mod game_loop;
 pub mod gengine;
 pub mod gmalloc;
 pub mod guobject_array;
+pub mod gworld;
 pub mod kismet;
 pub mod pak;
+pub mod process_event;
 pub mod save_game;
 pub mod static_construct_object;
 pub mod static_find_object;
+pub mod ustruct_link;
 
 use std::{
     collections::{HashMap, HashSet},
@@ -58,12 +61,7 @@ mod util {
         ctx: &AsyncContext<'_>,
         addresses: impl IntoIterator<Item = &usize> + Copy,
     ) -> Vec<usize> {
-        let refs_indirect = join_all(
-            addresses
-                .into_iter()
-                .map(|s| ctx.scan(Pattern::from_bytes(usize::to_le_bytes(*s).into()).unwrap())),
-        )
-        .await;
+        let refs_indirect = join_all(addresses.into_iter().map(|s| ctx.scan_pointer(*s))).await;
 
         let refs = join_all(
             addresses
@@ -111,13 +109,7 @@ mod util {
                 .into_iter()
                 .copied()
                 .chain(refs_indirect.into_iter().flatten())
-                .flat_map(|s| {
-                    [
-                        //ctx.scan(Pattern::new(format!("10111??? 0x{s:X}")).unwrap()), // mov reg, imm32
-                        ctx.scan(Pattern::new(format!("e8 X0x{s:X}")).unwrap()),
-                        ctx.scan(Pattern::new(format!("e9 X0x{s:X}")).unwrap()),
-                    ]
-                }),
+                .map(|s| ctx.scan_calls_to(s)),
         )
         .await;
 
@@ -307,24 +299,8 @@ pub struct ConsoleManagerSingleton(usize);
 
 impl_resolver_singleton!(all, ConsoleManagerSingleton, |ctx| async {
     let strings = join_all([
-        ctx.scan(
-            Pattern::from_bytes(
-                "r.DumpingMovie"
-                    .encode_utf16()
-                    .flat_map(u16::to_le_bytes)
-                    .collect(),
-            )
-            .unwrap(),
-        ),
-        ctx.scan(
-            Pattern::from_bytes(
-                "vr.pixeldensity"
-                    .encode_utf16()
-                    .flat_map(u16::to_le_bytes)
-                    .collect(),
-            )
-            .unwrap(),
-        ),
+        ctx.scan_string_any("r.DumpingMovie"),
+        ctx.scan_string_any("vr.pixeldensity"),
     ])
     .await;
 
@@ -0,0 +1,31 @@
+use futures::future::join_all;
+
+use patternsleuth_scanner::Pattern;
+
+use crate::resolvers::{impl_resolver_singleton, try_ensure_one};
+
+/// ```text
+/// void __cdecl UObject::ProcessEvent(class UFunction *, void *)
+/// ```
+///
+/// Patterns are from MSVC x64 builds. Both match the same sequence near the start of the
+/// function, the first also including the prologue for builds where the body alone is ambiguous.
+/// Matches are snapped to the start of their function.
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UObjectProcessEvent(pub usize);
+impl_resolver_singleton!(all, UObjectProcessEvent, |ctx| async {
+    let patterns = [
+        "40 55 56 57 41 54 41 55 41 56 41 57 48 81 EC ?? ?? ?? ?? 48 8D 6C 24 ?? 48 89 9D ?? ?? ?? ?? 48 8B 05 ?? ?? ?? ?? 48 33 C5 48 89 85 ?? ?? ?? ?? 8B 41 0C 45 33 F6 3B 05 ?? ?? ?? ?? 4D 8B F8 48 8B F2 4C 8B E1 41 B8 FF FF FF FF 7D",
+        "8B 41 0C 45 33 F6 3B 05 ?? ?? ?? ?? 4D 8B F8 48 8B F2 4C 8B E1 41 B8 FF FF FF FF 7D",
+    ];
+
+    let res = join_all(patterns.iter().map(|p| ctx.scan(Pattern::new(p).unwrap()))).await;
+
+    Ok(Self(try_ensure_one(
+        res.iter().flatten().map(|a| ctx.ensure_function_start(*a)),
+    )?))
+});
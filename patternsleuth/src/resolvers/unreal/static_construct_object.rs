@@ -5,7 +5,10 @@ use patternsleuth_scanner::Pattern;
 
 use crate::{
     disassemble::{disassemble, Control},
-    resolvers::{ensure_one, impl_resolver_singleton, try_ensure_one, unreal::util, Result},
+    resolvers::{
+        ensure_all, ensure_one, impl_resolver, impl_resolver_singleton, try_ensure_one,
+        unreal::util, MultiAddress, Result,
+    },
     MemoryAccessorTrait,
 };
 
@@ -120,11 +123,10 @@ impl_resolver_singleton!(ElfImage, StaticConstructObjectInternalString, |ctx| as
 });
 
 impl_resolver_singleton!(PEImage, StaticConstructObjectInternalString, |ctx| async {
-    use iced_x86::{Code, FlowControl, OpKind, Register};
+    use iced_x86::{Code, OpKind, Register};
     use itertools::Itertools;
 
     use crate::{
-        disassemble::disassemble_single,
         resolvers::{bail_out, Context, Result},
         Image, MemoryTrait,
     };
@@ -217,13 +219,8 @@ impl_resolver_singleton!(PEImage, StaticConstructObjectInternalString, |ctx| asy
         } else {
             // sometimes can be a call deep so check all outgoing calls as well
             for call in util::find_calls(img, f)? {
-                let mut f = call.callee;
                 // sometimes there's a jmp stub between
-                if let Some(inst) = disassemble_single(img, f)? {
-                    if inst.flow_control() == FlowControl::UnconditionalBranch {
-                        f = inst.near_branch_target() as usize;
-                    }
-                }
+                let f = img.resolve_thunk(call.callee).unwrap_or(call.callee);
 
                 if check(f)? {
                     return Ok(true);
@@ -317,12 +314,10 @@ impl_resolver_singleton!(PEImage, StaticConstructObjectInternalString, |ctx| asy
         if !checked.contains(&call.callee) {
             checked.insert(call.callee);
 
-            let mut f = call.callee;
-            if let Some(inst) = disassemble_single(ctx.image(), f)? {
-                if inst.flow_control() == FlowControl::UnconditionalBranch {
-                    f = inst.near_branch_target() as usize;
-                }
-            }
+            let f = ctx
+                .image()
+                .resolve_thunk(call.callee)
+                .unwrap_or(call.callee);
 
             if check_is_static_construct(ctx.image(), f)? {
                 return Ok(Self(call.callee));
@@ -343,3 +338,18 @@ impl_resolver_singleton!(PEImage, StaticConstructObjectInternalString, |ctx| asy
 
     bail_out!("could not find StaticConstructObject_Internal call");
 });
+
+/// All call sites of StaticConstructObject_Internal
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct StaticConstructObjectInternalCalls(pub MultiAddress);
+impl_resolver!(all, StaticConstructObjectInternalCalls, |ctx| async {
+    let f = ctx
+        .resolve(StaticConstructObjectInternal::resolver())
+        .await?;
+    let calls = util::scan_xcalls(ctx, &[f.0]).await;
+    Ok(Self(MultiAddress(ensure_all(calls)?)))
+});
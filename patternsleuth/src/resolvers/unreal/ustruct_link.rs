@@ -0,0 +1,24 @@
+use std::fmt::Debug;
+
+use crate::resolvers::{ensure_one, impl_resolver_singleton, unreal::util};
+
+/// public: virtual void __cdecl UStruct::Link(class FArchive &, bool)
+#[derive(Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde-resolvers",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UStructLink(pub usize);
+impl_resolver_singleton!(all, UStructLink, |ctx| async {
+    // ensureMsgf in the ChildProperties loop. Only present in 4.25+ (FField properties) and only
+    // in builds with ensures enabled, so older engine versions and most shipping builds are not
+    // covered.
+    let strings = ctx
+        .scan(util::utf16_pattern(
+            "Linking '%s'. Property '%s' has outer '%s'\0",
+        ))
+        .await;
+    let refs = util::scan_xrefs(ctx, &strings).await;
+    let fns = util::root_functions(ctx, &refs)?;
+    Ok(Self(ensure_one(fns)?))
+});
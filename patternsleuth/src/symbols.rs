@@ -94,3 +94,167 @@ pub fn dump_pdb_symbols<P: AsRef<Path>>(
     }
     Ok(symbols)
 }
+
+/// Parse symbols from an MSVC or lld linker map file. Addresses in the map are relative to the
+/// preferred `image_base` and are rebased to `base_address`.
+pub fn parse_map_symbols(
+    map: &str,
+    image_base: usize,
+    base_address: usize,
+) -> HashMap<usize, Symbol> {
+    let rebase = |address: &str| -> Option<usize> {
+        let address = usize::from_str_radix(address, 16).ok()?;
+        Some(base_address + address.checked_sub(image_base)?)
+    };
+
+    let mut symbols = HashMap::new();
+
+    // lld maps start with a column header e.g. "Address Size Align Out In Symbol"
+    let lld_header = map
+        .lines()
+        .find(|line| line.contains(" Out ") && line.trim_end().ends_with("Symbol"));
+
+    if let Some(header) = lld_header {
+        let symbol_column = header.find("Symbol").unwrap();
+        let numeric_columns = header
+            .split_whitespace()
+            .take_while(|c| *c != "Out")
+            .count();
+
+        for line in map.lines() {
+            let mut rest = line;
+            let mut address = None;
+            for _ in 0..numeric_columns {
+                let column = rest.trim_start();
+                let end = column.find(char::is_whitespace).unwrap_or(column.len());
+                address = address.or(Some(&column[..end]));
+                rest = &column[end..];
+            }
+            let name = rest.trim();
+            // section and input file rows are indented less than symbol rows
+            if name.is_empty() || line.len() - rest.trim_start().len() < symbol_column {
+                continue;
+            }
+            if let Some(address) = address.and_then(rebase) {
+                symbols.insert(
+                    address,
+                    Symbol {
+                        name: name.to_string(),
+                    },
+                );
+            }
+        }
+    } else {
+        // MSVC: "<section>:<offset> <name> <rva+base> [f] [i] <object>"
+        for line in map.lines() {
+            let mut columns = line.split_whitespace();
+            let (Some(section_offset), Some(name), Some(address)) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                continue;
+            };
+            let Some((section, offset)) = section_offset.split_once(':') else {
+                continue;
+            };
+            let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+            // section 0 contains absolute symbols
+            if !is_hex(section) || !is_hex(offset) || section.chars().all(|c| c == '0') {
+                continue;
+            }
+            if let Some(address) = rebase(address) {
+                symbols.insert(
+                    address,
+                    Symbol {
+                        name: name.to_string(),
+                    },
+                );
+            }
+        }
+    }
+    symbols
+}
+
+pub fn dump_map_symbols<P: AsRef<Path>>(
+    filename: P,
+    image_base: usize,
+    base_address: usize,
+) -> Result<HashMap<usize, Symbol>> {
+    Ok(parse_map_symbols(
+        &std::fs::read_to_string(filename)?,
+        image_base,
+        base_address,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(symbols: &HashMap<usize, Symbol>) -> Vec<(usize, &str)> {
+        let mut names = symbols
+            .iter()
+            .map(|(address, symbol)| (*address, symbol.name.as_str()))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_parse_msvc_map() {
+        let map = r#" FSD-Win64-Shipping
+
+ Timestamp is 5f5a8d3e (Thu Sep 10 22:23:26 2020)
+
+ Preferred load address is 0000000140000000
+
+ Start         Length     Name                   Class
+ 0001:00000000 00001234H .text$mn                CODE
+ 0002:00000000 00000100H .rdata                  DATA
+
+  Address         Publics by Value              Rva+Base               Lib:Object
+
+ 0000:00000000       __guard_flags              0000000000000000     <absolute>
+ 0001:00000000       main                       0000000140001000 f   main.obj
+ 0001:00000020       ?Tick@UWorld@@QEAAXM@Z     0000000140001020 f   World.obj
+ 0002:00000010       ?GWorld@@3PEAVUWorld@@EA   0000000140002010     World.obj
+
+ entry point at        0001:00000000
+
+ Static symbols
+
+ 0001:00000040       static_helper              0000000140001040 f   main.obj
+"#;
+        let symbols = parse_map_symbols(map, 0x140000000, 0x7ff600000000);
+        assert_eq!(
+            vec![
+                (0x7ff600001000, "main"),
+                (0x7ff600001020, "?Tick@UWorld@@QEAAXM@Z"),
+                (0x7ff600001040, "static_helper"),
+                (0x7ff600002010, "?GWorld@@3PEAVUWorld@@EA"),
+            ],
+            names(&symbols)
+        );
+    }
+
+    #[test]
+    fn test_parse_lld_map() {
+        let map = r#"         Address Size     Align Out     In      Symbol
+       140001000 00000045  4096 .text
+       140001000 00000025    16         main.obj:(.text)
+       140001000 00000000     0                 main
+       140001010 00000000     0                 ?Tick@UWorld@@QEAAXM@Z
+       140002000 00000010  4096 .data
+       140002000 00000010     8         World.obj:(.data)
+       140002008 00000000     0                 ?GWorld@@3PEAVUWorld@@EA
+"#;
+        let symbols = parse_map_symbols(map, 0x140000000, 0x140000000);
+        assert_eq!(
+            vec![
+                (0x140001000, "main"),
+                (0x140001010, "?Tick@UWorld@@QEAAXM@Z"),
+                (0x140002008, "?GWorld@@3PEAVUWorld@@EA"),
+            ],
+            names(&symbols)
+        );
+    }
+}
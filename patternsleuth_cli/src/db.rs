@@ -12,7 +12,8 @@ use rayon::prelude::*;
 use rusqlite::{Connection, OptionalExtension};
 
 use crate::{
-    disassemble, get_games, CommandAutoGen, CommandBuildIndex, CommandViewSymbol, GameFileEntry,
+    disassemble, get_games, CommandAutoGen, CommandBuildIndex, CommandViewSymbol,
+    DisassemblyFormat, GameFileEntry,
 };
 
 fn generate_patterns_for_symbol(symbol: &str) -> Result<Vec<Pattern>> {
@@ -236,6 +237,8 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
         game: String,
         address: usize,
         data: Vec<u8>,
+        /// Address and bytes of child functions when `--with-children` is passed
+        children: Vec<(usize, Vec<u8>)>,
     }
 
     struct IndexedFunction {
@@ -251,6 +254,7 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
                 game: row.get(0)?,
                 address: row.get(1)?,
                 data: row.get(2)?,
+                children: vec![],
             })
         })? {
             functions.push(row?)
@@ -259,11 +263,20 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
 
     for function in command.function {
         let data = fs::read(&function.path)?;
-        let img = Image::builder().build(&data).unwrap();
+        let img = Image::builder()
+            .functions(command.with_children)
+            .build(&data)
+            .unwrap();
+        let children = if command.with_children {
+            disassemble::child_functions(&img, function.start)?
+        } else {
+            vec![]
+        };
         functions.push(Function {
             game: function.path,
             address: function.start,
             data: img.memory[function.start..function.end].to_vec(),
+            children,
         });
     }
 
@@ -286,7 +299,10 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
 
             let exe = {
                 let bin_data = bin_data.as_ref().unwrap();
-                match Image::builder().functions(false).build(bin_data) {
+                match Image::builder()
+                    .functions(command.with_children)
+                    .build(bin_data)
+                {
                     Ok(exe) => exe,
                     Err(err) => {
                         println!("err reading {}: {err}", exe_path.display());
@@ -302,10 +318,16 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
             for res in resolution.into_iter().flatten() {
                 let start = res.get().unwrap();
                 let bounds = patternsleuth::disassemble::function_range(&exe, start)?;
+                let children = if command.with_children {
+                    disassemble::child_functions(&exe, start)?
+                } else {
+                    vec![]
+                };
                 functions.push(Function {
                     game: exe_path.to_string_lossy().to_string(),
                     address: start,
                     data: exe.memory[bounds].to_vec(),
+                    children,
                 });
             }
         }
@@ -436,28 +458,52 @@ pub(crate) fn view(command: CommandViewSymbol) -> Result<()> {
         }
 
         for (group, pattern) in groups.iter().zip(patterns) {
+            if command.format == DisassemblyFormat::Json {
+                for f in group {
+                    println!(
+                        "{}",
+                        serde_json::to_string(&serde_json::json!({
+                            "game": f.function.game,
+                            "address": f.function.address,
+                            "pattern": pattern,
+                            "instructions": disassemble::disassemble_bytes_json(
+                                f.function.address,
+                                &f.function.data,
+                            ),
+                        }))?
+                    );
+                }
+                continue;
+            }
             let mut table = Table::new();
             table.set_titles(group.iter().map(|f| &f.function.game).collect());
             table.add_row(Row::new(
                 group
                     .iter()
                     .map(|f| {
-                        Cell::new(&disassemble::disassemble_bytes_with_symbols(
-                            f.function.address,
-                            &f.function.data,
-                            Some(&Pattern::new(&pattern).unwrap()),
-                            |address| -> Option<String> {
-                                command.show_symbols.then(||
-                                conn
-                                    .query_row_and_then(
-                                        "SELECT symbol FROM symbols WHERE game = ?1 AND address = ?2",
-                                        (&f.function.game, address),
-                                        |row| row.get(0).optional(),
-                                    )
-                                    .ok()
-                                    .flatten()).flatten()
+                        let symbols = |address: usize| -> Option<String> {
+                            if !command.show_symbols {
+                                return None;
                             }
-                        ))
+                            conn.query_row_and_then(
+                                "SELECT symbol FROM symbols WHERE game = ?1 AND address = ?2",
+                                (&f.function.game, address),
+                                |row| row.get(0).optional(),
+                            )
+                            .ok()
+                            .flatten()
+                        };
+                        Cell::new(
+                            &(disassemble::disassemble_bytes_with_symbols(
+                                f.function.address,
+                                &f.function.data,
+                                Some(&Pattern::new(&pattern).unwrap()),
+                                symbols,
+                            ) + &disassemble::disassemble_children(
+                                &f.function.children,
+                                symbols,
+                            )),
+                        )
                     })
                     .collect(),
             ));
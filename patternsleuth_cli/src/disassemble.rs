@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::ops::Range;
 
 use colored::{ColoredString, Colorize};
 use iced_x86::{
-    Decoder, DecoderOptions, Formatter, FormatterOutput, FormatterTextKind, IntelFormatter, OpKind,
+    Decoder, DecoderOptions, Formatter, FormatterOutput, FormatterTextKind, Instruction,
+    IntelFormatter, OpKind, SymbolResolver, SymbolResult,
 };
-use patternsleuth::{image::Image, scanner::Pattern, MemoryTrait};
+use itertools::Itertools;
+use patternsleuth::{image::Image, scanner::Pattern, MemoryTrait, NamedMemorySection};
+use serde::Serialize;
 
 #[derive(Default)]
 struct Output {
@@ -18,10 +22,217 @@ impl FormatterOutput for Output {
     }
 }
 
-pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>) -> String {
-    let context = 20; // number of instructions before and after
+/// Names of known addresses (imports, exports, resolvers, ...) shown in place of the raw address
+/// of branch targets and memory operands
+struct Annotations(HashMap<u64, String>);
+
+impl SymbolResolver for Annotations {
+    fn symbol(
+        &mut self,
+        _instruction: &Instruction,
+        _operand: u32,
+        _instruction_operand: Option<u32>,
+        address: u64,
+        _address_size: u32,
+    ) -> Option<SymbolResult<'_>> {
+        self.0
+            .get(&address)
+            .map(|name| SymbolResult::with_str(address, name))
+    }
+}
+
+fn formatter(annotations: Option<&HashMap<u64, String>>) -> IntelFormatter {
+    let mut formatter = match annotations {
+        Some(annotations) => {
+            IntelFormatter::with_options(Some(Box::new(Annotations(annotations.clone()))), None)
+        }
+        None => IntelFormatter::new(),
+    };
+    formatter.options_mut().set_first_operand_char_index(8);
+    formatter
+}
+
+/// Build annotations from the imports and exports of `exe`
+pub(crate) fn image_annotations(exe: &Image) -> HashMap<u64, String> {
+    let mut annotations = HashMap::new();
+    for (lib, imports) in &exe.imports {
+        for (name, address) in imports {
+            annotations.insert(*address as u64, format!("{lib}!{name}"));
+        }
+    }
+    for (name, address) in &exe.exports {
+        annotations.insert(*address as u64, name.clone());
+    }
+    annotations
+}
+
+/// Instructions surrounding an address
+struct DecodedContext<'a> {
+    /// Range of the containing function if any
+    function: Option<Range<usize>>,
+    data: &'a [u8],
+    start_address: u64,
+    instructions: Vec<Instruction>,
+}
+
+/// Decode the whole function containing `address` or `context` instructions before and after
+/// `address` if it is not part of a known function
+fn decode_context<'a>(
+    exe: &Image,
+    section: &'a NamedMemorySection,
+    address: usize,
+    context: usize,
+) -> DecodedContext<'a> {
     let max_inst = 16; // max size of x86 instruction in bytes
 
+    let (function, data, start_address) = if let Ok(Some(f)) = exe.get_root_function(address) {
+        let fns = exe.get_child_functions(f.range.start).unwrap();
+        let min = fns.iter().map(|f| f.range.start).min().unwrap();
+        let max = fns.iter().map(|f| f.range.end).max().unwrap();
+        let range = min..max;
+
+        let start_address = range.start as u64;
+        let data = section.range(range.clone()).unwrap();
+        (Some(range), data, start_address)
+    } else {
+        let data = &section.data()[(address - context * max_inst).saturating_sub(section.address())
+            ..(address + context * max_inst).saturating_sub(section.address())];
+        let start_address = (address - context * max_inst) as u64;
+        (None, data, start_address)
+    };
+
+    let mut decoder = Decoder::with_ip(
+        exe.bitness.bits(),
+        data,
+        start_address,
+        DecoderOptions::NONE,
+    );
+
+    let instructions = decoder.iter().collect::<Vec<_>>();
+    let instructions = if let Some((middle, _)) = function
+        .is_none()
+        .then(|| {
+            instructions
+                .iter()
+                .enumerate()
+                .find(|(_, inst)| inst.ip() >= address as u64)
+        })
+        .flatten()
+    {
+        instructions
+            .into_iter()
+            .skip(middle - context)
+            .take(context * 2 + 1)
+            .collect::<Vec<_>>()
+    } else {
+        instructions
+    };
+
+    DecodedContext {
+        function,
+        data,
+        start_address,
+        instructions,
+    }
+}
+
+/// Machine readable form of a single decoded instruction
+#[derive(Debug, Serialize)]
+pub(crate) struct JsonInstruction {
+    pub ip: u64,
+    /// Instruction bytes as space separated hex
+    pub bytes: String,
+    pub mnemonic: String,
+    pub operands: String,
+    pub flow_control: String,
+    pub branch_target: Option<u64>,
+}
+
+fn json_instructions(
+    data: &[u8],
+    start_address: u64,
+    instructions: impl IntoIterator<Item = Instruction>,
+) -> Vec<JsonInstruction> {
+    let mut formatter = IntelFormatter::new();
+    instructions
+        .into_iter()
+        .map(|instruction| {
+            let index = (instruction.ip() - start_address) as usize;
+            let bytes = data[index..index + instruction.len()]
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let mut mnemonic = String::new();
+            formatter.format_mnemonic(&instruction, &mut mnemonic);
+            let mut operands = String::new();
+            formatter.format_all_operands(&instruction, &mut operands);
+
+            let branch_target = instruction
+                .op_kinds()
+                .any(|op| {
+                    matches!(
+                        op,
+                        OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64
+                    )
+                })
+                .then(|| instruction.near_branch_target());
+
+            JsonInstruction {
+                ip: instruction.ip(),
+                bytes,
+                mnemonic,
+                operands,
+                flow_control: format!("{:?}", instruction.flow_control()),
+                branch_target,
+            }
+        })
+        .collect()
+}
+
+/// Same instructions shown by [`disassemble`] with `window` instructions of context
+pub(crate) fn disassemble_json(exe: &Image, address: usize, window: usize) -> Vec<JsonInstruction> {
+    let Ok(section) = exe.memory.get_section_containing(address) else {
+        return vec![];
+    };
+    let DecodedContext {
+        data,
+        start_address,
+        instructions,
+        ..
+    } = decode_context(exe, section, address, window);
+    json_instructions(data, start_address, instructions)
+}
+
+/// Same instructions shown by [`disassemble_bytes_with_symbols`]
+pub(crate) fn disassemble_bytes_json(address: usize, data: &[u8]) -> Vec<JsonInstruction> {
+    json_instructions(
+        data,
+        address as u64,
+        Decoder::with_ip(64, data, address as u64, DecoderOptions::NONE),
+    )
+}
+
+/// Whether the byte at `byte_address` is a literal (fully masked) byte of `pattern` given a match
+/// of `pattern` at `match_address`. Scan results already include the pattern's custom offset so
+/// the match itself begins `custom_offset` bytes earlier.
+fn is_literal(pattern: &Pattern, match_address: usize, byte_address: usize) -> bool {
+    match_address
+        .checked_sub(pattern.custom_offset)
+        .and_then(|start| byte_address.checked_sub(start))
+        .and_then(|offset| pattern.simple.mask.get(offset))
+        .is_some_and(|mask| *mask == 0xff)
+}
+
+pub(crate) fn disassemble(
+    exe: &Image,
+    address: usize,
+    pattern: Option<&Pattern>,
+    annotations: Option<&HashMap<u64, String>>,
+) -> String {
+    let context = 20; // number of instructions before and after
+
     let mut output = Output::default();
 
     if let Ok(section) = exe.memory.get_section_containing(address) {
@@ -33,12 +244,14 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
             section.name(),
         ));
 
-        let (is_fn, data, start_address) = if let Ok(Some(f)) = exe.get_root_function(address) {
-            let fns = exe.get_child_functions(f.range.start).unwrap();
-            let min = fns.iter().map(|f| f.range.start).min().unwrap();
-            let max = fns.iter().map(|f| f.range.end).max().unwrap();
-            let range = min..max;
+        let DecodedContext {
+            function,
+            data,
+            start_address,
+            instructions,
+        } = decode_context(exe, section, address, context);
 
+        if let Some(range) = function {
             output.buffer.push_str(&format!(
                 "{:016x} - {:016x} = function\n",
                 range.start, range.end
@@ -53,44 +266,13 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
                     output.buffer.push('\n');
                 }
             }
-            let start_address = range.start as u64;
-            let data = section.range(range).unwrap();
-            (true, data, start_address)
         } else {
             output.buffer.push_str("no function");
-
-            let data = &section.data()[(address - context * max_inst)
-                .saturating_sub(section.address())
-                ..(address + context * max_inst).saturating_sub(section.address())];
-            let start_address = (address - context * max_inst) as u64;
-            (false, data, start_address)
-        };
+        }
 
         output.buffer.push('\n');
 
-        let mut decoder = Decoder::with_ip(64, data, start_address, DecoderOptions::NONE);
-
-        let instructions = decoder.iter().collect::<Vec<_>>();
-        let instructions = if let Some((middle, _)) = (!is_fn)
-            .then(|| {
-                instructions
-                    .iter()
-                    .enumerate()
-                    .find(|(_, inst)| inst.ip() >= address as u64)
-            })
-            .flatten()
-        {
-            instructions
-                .into_iter()
-                .skip(middle - context)
-                .take(context * 2 + 1)
-                .collect::<Vec<_>>()
-        } else {
-            instructions
-        };
-
-        let mut formatter = IntelFormatter::new();
-        formatter.options_mut().set_first_operand_char_index(8);
+        let mut formatter = formatter(annotations);
         for instruction in instructions {
             let ip = format!("{:016x}", instruction.ip());
             if (instruction.ip()..instruction.ip() + instruction.len() as u64)
@@ -105,12 +287,8 @@ pub(crate) fn disassemble(exe: &Image, address: usize, pattern: Option<&Pattern>
 
             let index = (instruction.ip() - start_address) as usize;
             for (i, b) in data[index..index + instruction.len()].iter().enumerate() {
-                let highlight = pattern
-                    .and_then(|p| -> Option<bool> {
-                        let offset = (instruction.ip() as usize) - address + i + p.custom_offset;
-                        Some(*p.simple.mask.get(offset)? != 0)
-                    })
-                    .unwrap_or_default();
+                let highlight =
+                    pattern.is_some_and(|p| is_literal(p, address, instruction.ip() as usize + i));
                 let s = format!("{:02x}", b);
                 let mut colored = if highlight {
                     s.bright_white()
@@ -179,7 +357,12 @@ pub(crate) fn disassemble_range(exe: &Image, range: Range<usize>) -> String {
 
         output.buffer.push('\n');
 
-        let mut decoder = Decoder::with_ip(64, data, address as u64, DecoderOptions::NONE);
+        let mut decoder = Decoder::with_ip(
+            exe.bitness.bits(),
+            data,
+            address as u64,
+            DecoderOptions::NONE,
+        );
 
         let instructions = decoder.iter().collect::<Vec<_>>();
 
@@ -213,6 +396,35 @@ pub(crate) fn disassemble_range(exe: &Image, range: Range<usize>) -> String {
     output.buffer
 }
 
+/// Address and bytes of every child function of the root function starting at `address`,
+/// excluding the root itself
+pub(crate) fn child_functions(
+    exe: &Image,
+    address: usize,
+) -> anyhow::Result<Vec<(usize, Vec<u8>)>> {
+    Ok(exe
+        .get_child_functions(address)?
+        .into_iter()
+        .filter(|f| f.range.start != address)
+        .sorted_by_key(|f| f.range.start)
+        .map(|f| (f.range.start, exe.memory[f.range].to_vec()))
+        .collect())
+}
+
+pub(crate) fn disassemble_children<F>(children: &[(usize, Vec<u8>)], symbols: F) -> String
+where
+    F: Fn(usize) -> Option<String>,
+{
+    let mut buffer = String::new();
+    for (address, data) in children {
+        buffer.push_str("\nchild function ");
+        buffer.push_str(&disassemble_bytes_with_symbols(
+            *address, data, None, &symbols,
+        ));
+    }
+    buffer
+}
+
 pub(crate) fn disassemble_bytes_with_symbols<F>(
     address: usize,
     data: &[u8],
@@ -248,12 +460,8 @@ where
 
         let index = instruction.ip() as usize - address;
         for (i, b) in data[index..index + instruction.len()].iter().enumerate() {
-            let highlight = pattern
-                .and_then(|p| -> Option<bool> {
-                    let offset = (instruction.ip() as usize) - address + i + p.custom_offset;
-                    Some(*p.simple.mask.get(offset)? != 0)
-                })
-                .unwrap_or_default();
+            let highlight =
+                pattern.is_some_and(|p| is_literal(p, address, instruction.ip() as usize + i));
 
             let s = format!("{:02x}", b);
             let mut colored = if highlight {
@@ -313,6 +521,111 @@ fn get_color(s: &str, kind: FormatterTextKind) -> ColoredString {
         FormatterTextKind::Prefix | FormatterTextKind::Mnemonic => s.bright_red(),
         FormatterTextKind::Register => s.bright_blue(),
         FormatterTextKind::Number => s.bright_cyan(),
+        FormatterTextKind::Label | FormatterTextKind::Function => s.bright_yellow(),
         _ => s.white(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_annotations() {
+        // call 0x2000; call qword ptr [0x3000]
+        let data = [
+            0xe8, 0xfb, 0x0f, 0x00, 0x00, 0xff, 0x15, 0xf5, 0x1f, 0x00, 0x00,
+        ];
+        let annotations = HashMap::from([
+            (0x2000, "GMalloc".to_string()),
+            (0x3000, "kernel32.dll!GetProcAddress".to_string()),
+        ]);
+
+        let format = |annotations| {
+            let mut formatter = formatter(annotations);
+            Decoder::with_ip(64, &data, 0x1000, DecoderOptions::NONE)
+                .into_iter()
+                .map(|instruction| {
+                    let mut output = String::new();
+                    formatter.format(&instruction, &mut output);
+                    output
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let annotated = format(Some(&annotations));
+        assert!(annotated[0].ends_with("GMalloc"), "{}", annotated[0]);
+        assert!(
+            annotated[1].contains("[kernel32.dll!GetProcAddress]"),
+            "{}",
+            annotated[1]
+        );
+
+        let plain = format(None);
+        assert!(plain[0].ends_with("2000h"), "{}", plain[0]);
+    }
+
+    #[test]
+    fn test_highlight_custom_offset() {
+        let pattern = Pattern::new("48 ?? 8b | ?? 05 ?? ?? ?? ?? c3").unwrap();
+        // scan results point at the custom offset, not at the start of the match
+        let highlighted = (0x1000..0x1010)
+            .filter(|byte| is_literal(&pattern, 0x1003, *byte))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0x1000, 0x1002, 0x1004, 0x1009], highlighted);
+
+        let pattern = Pattern::new("48 8b ?? 05").unwrap();
+        let highlighted = (0xff0..0x1010)
+            .filter(|byte| is_literal(&pattern, 0x1000, *byte))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0x1000, 0x1001, 0x1003], highlighted);
+    }
+
+    #[test]
+    fn test_disassemble_children() {
+        // root function jumping to a separated cold block
+        let root = [
+            0x31, 0xc0, // xor eax, eax
+            0x74, 0x7c, // je 0x1080
+        ];
+        let children = vec![(0x1080, vec![0x31, 0xc0, 0xc3])]; // xor eax, eax; ret
+
+        let output = disassemble_bytes_with_symbols(0x1000, &root, None, |_| None)
+            + &disassemble_children(&children, |_| None);
+        assert!(
+            output.contains("0000000000001000 - 0000000000001004"),
+            "{output}"
+        );
+        assert!(
+            output.contains("child function 0000000000001080 - 0000000000001083"),
+            "{output}"
+        );
+        assert!(output.contains("0000000000001082:"), "{output}");
+    }
+
+    #[test]
+    fn test_disassemble_json() {
+        let data = [
+            0x31, 0xc0, // xor eax, eax
+            0x74, 0x01, // je 0x1005
+            0xc3, // ret
+            0xe8, 0xf6, 0xff, 0xff, 0xff, // call 0x1000
+        ];
+        let json = disassemble_bytes_json(0x1000, &data);
+
+        assert_eq!(4, json.len());
+        assert_eq!("xor", json[0].mnemonic);
+        assert_eq!("eax,eax", json[0].operands);
+        assert_eq!("31 c0", json[0].bytes);
+        assert_eq!(None, json[0].branch_target);
+
+        assert_eq!(0x1002, json[1].ip);
+        assert_eq!("je", json[1].mnemonic);
+        assert_eq!("ConditionalBranch", json[1].flow_control);
+        assert_eq!(Some(0x1005), json[1].branch_target);
+
+        assert_eq!("ret", json[2].mnemonic);
+        assert_eq!("call", json[3].mnemonic);
+        assert_eq!(Some(0x1000), json[3].branch_target);
+    }
+}
@@ -2,7 +2,7 @@ mod db;
 mod disassemble;
 
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -27,10 +27,12 @@ enum Commands {
     Scan(CommandScan),
     Report(CommandReport),
     DiffReport(CommandDiffReport),
+    ExportGhidra(CommandExportGhidra),
     Symbols(CommandSymbols),
     BuildIndex(CommandBuildIndex),
     ViewSymbol(CommandViewSymbol),
     AutoGen(CommandAutoGen),
+    Explain(CommandExplain),
 }
 
 fn parse_maybe_hex(s: &str) -> Result<usize> {
@@ -51,7 +53,7 @@ fn resolver_parser() -> impl IntoResettable<ValueParser> {
     PossibleValuesParser::new(possible_resolvers()).map(|v| parse_resolver(&v).unwrap())
 }
 
-#[derive(Parser)]
+#[derive(Parser, Clone)]
 struct CommandScan {
     /// A game to scan (can be specified multiple times). Scans everything if omitted. Supports
     /// globs
@@ -62,6 +64,14 @@ struct CommandScan {
     #[arg(long)]
     pid: Option<i32>,
 
+    /// A game process name to attach to and scan. Supports globs
+    #[arg(long, conflicts_with = "pid")]
+    process_name: Option<String>,
+
+    /// Scan every process matching --process-name instead of failing if more than one matches
+    #[arg(long, requires = "process_name")]
+    all: bool,
+
     /// A resolver to scan for (can be specified multiple times)
     #[arg(short, long, value_parser(resolver_parser()))]
     resolver: Vec<&'static NamedResolver>,
@@ -75,6 +85,10 @@ struct CommandScan {
     #[arg(long)]
     disassemble_merged: bool,
 
+    /// Output format of --disassemble
+    #[arg(long, value_enum, default_value_t)]
+    format: DisassemblyFormat,
+
     /// A pattern to scan for (can be specified multiple times)
     #[arg(short, long, value_parser(|s: &_| Pattern::new(s)))]
     patterns: Vec<Pattern>,
@@ -83,6 +97,15 @@ struct CommandScan {
     #[arg(long)]
     pattern_config: Option<PathBuf>,
 
+    /// A path to a file containing one pattern per line or `-` to read from stdin. Blank lines and
+    /// lines starting with `#` are ignored
+    #[arg(long)]
+    patterns_file: Option<PathBuf>,
+
+    /// Rescan whenever the --pattern-config file is modified
+    #[arg(long, requires = "pattern_config")]
+    watch: bool,
+
     /// An xref to scan for (can be specified multiple times)
     #[arg(short, long, value_parser(|s: &str| parse_maybe_hex(s).map(Xref)))]
     xref: Vec<Xref>,
@@ -91,10 +114,36 @@ struct CommandScan {
     #[arg(long)]
     symbols: bool,
 
+    /// Warn about resolved addresses whose PDB symbol does not resemble the resolver name
+    #[arg(long, requires = "symbols")]
+    verify_symbols: bool,
+
     /// Skip parsing of exception table
     #[arg(long)]
     skip_exceptions: bool,
 
+    /// Memory map game executables instead of reading them into memory
+    #[arg(long)]
+    mmap: bool,
+
+    /// Dump exported symbols of each scanned image
+    #[arg(long)]
+    exports: bool,
+
+    /// Dump imported symbols of each scanned image matching a glob (`__imp_` prefixes are
+    /// ignored)
+    #[arg(long)]
+    imports: Option<String>,
+
+    /// Print addresses rebased from the image base to this base, e.g. to match a debugger
+    /// attached to a game loaded at a different address
+    #[arg(long, value_parser(parse_maybe_hex))]
+    rebase: Option<usize>,
+
+    /// Write an IDA Python script naming every resolved address (requires a single game)
+    #[arg(long)]
+    export_ida: Option<PathBuf>,
+
     /// Show scan summary
     #[arg(long)]
     summary: bool,
@@ -102,6 +151,11 @@ struct CommandScan {
     /// Show scan progress
     #[arg(long)]
     progress: bool,
+
+    /// Number of games to scan in parallel. Output of each game is printed once all games are
+    /// done
+    #[arg(short, long)]
+    jobs: Option<usize>,
 }
 
 #[derive(Parser)]
@@ -114,6 +168,29 @@ struct CommandReport {
     /// A resolver to scan for (can be specified multiple times)
     #[arg(short, long, value_parser(resolver_parser()))]
     resolver: Vec<&'static NamedResolver>,
+
+    /// Also scan for every resolver whose name matches this regex
+    #[arg(long)]
+    resolver_filter: Option<regex::Regex>,
+
+    /// Number of games to take per unique name prefix when ordering games so that differently
+    /// named games are resolved first
+    #[arg(long, default_value_t = SAMPLE_PREFIX_SIZE)]
+    sample: usize,
+
+    /// Directory to cache resolver results in. Resolvers which already succeeded for a game with
+    /// the same build are skipped
+    #[arg(long)]
+    cache: Option<PathBuf>,
+
+    /// Memory map game executables instead of reading them into memory
+    #[arg(long)]
+    mmap: bool,
+
+    /// Directory to write per-game partial results to. Games with partial results already
+    /// present are skipped so an interrupted report can be resumed
+    #[arg(long)]
+    resume: Option<PathBuf>,
 }
 
 #[derive(Parser)]
@@ -123,6 +200,28 @@ struct CommandDiffReport {
 
     /// Path to second report
     b: PathBuf,
+
+    /// Also write per-resolver success rates to a CSV file
+    #[arg(long)]
+    csv: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+struct CommandExportGhidra {
+    /// Game to resolve
+    #[arg(short, long)]
+    game: String,
+
+    /// A resolver to export (can be specified multiple times). Exports all resolvers if omitted
+    #[arg(short, long, value_parser(resolver_parser()))]
+    resolver: Vec<&'static NamedResolver>,
+
+    /// Image base of the Ghidra program. Defaults to the program's image base at script runtime
+    #[arg(long, value_parser(parse_maybe_hex))]
+    image_base: Option<usize>,
+
+    /// Path to write the Ghidra Python script to
+    output: PathBuf,
 }
 
 #[derive(Parser)]
@@ -134,6 +233,15 @@ struct CommandSymbols {
 
     #[arg(short, long)]
     symbol: Vec<regex::Regex>,
+
+    /// Write symbols and their function ranges to a JSON file keyed by game. Includes all
+    /// symbols if --symbol is omitted
+    #[arg(long)]
+    json: Option<PathBuf>,
+
+    /// Memory map game executables instead of reading them into memory
+    #[arg(long)]
+    mmap: bool,
 }
 
 #[derive(Parser)]
@@ -192,11 +300,45 @@ struct CommandViewSymbol {
     /// Whether to show symbols in function disassembly
     #[arg(long)]
     show_symbols: bool,
+
+    /// Also disassemble child functions (funclets, chained unwind ranges) of functions read
+    /// from an image
+    #[arg(long)]
+    with_children: bool,
+
+    /// Output format of function disassembly
+    #[arg(long, value_enum, default_value_t)]
+    format: DisassemblyFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum DisassemblyFormat {
+    /// Colored text
+    #[default]
+    Text,
+    /// One JSON object per disassembled address
+    Json,
 }
 
 #[derive(Parser)]
 struct CommandAutoGen {}
 
+#[derive(Parser)]
+struct CommandExplain {
+    /// Game to read
+    #[arg(short, long)]
+    game: String,
+
+    /// Pattern to compare
+    #[arg(value_parser(|s: &_| Pattern::new(s)))]
+    pattern: Pattern,
+
+    /// Address the pattern is expected to match as reported by `scan` (i.e. including the `|`
+    /// offset)
+    #[arg(value_parser(parse_maybe_hex))]
+    address: usize,
+}
+
 fn find_ext<P: AsRef<Path>, E: AsRef<str>>(dir: P, ext: &[E]) -> Result<Option<PathBuf>> {
     for f in fs::read_dir(dir)? {
         let f = f?.path();
@@ -227,10 +369,12 @@ fn main() -> Result<()> {
         Commands::Scan(command) => scan(command),
         Commands::Report(command) => report(command),
         Commands::DiffReport(command) => diff_report(command),
+        Commands::ExportGhidra(command) => export_ghidra(command),
         Commands::Symbols(command) => symbols(command),
         Commands::BuildIndex(command) => db::build(command),
         Commands::ViewSymbol(command) => db::view(command),
         Commands::AutoGen(command) => db::auto_gen(command),
+        Commands::Explain(command) => explain(command),
     }
 }
 
@@ -243,8 +387,96 @@ impl std::fmt::Display for Sig {
     }
 }
 
+/// Parse a JSON pattern config mapping symbol names to lists of patterns
+fn parse_pattern_config(json: &str) -> Result<Vec<PatternConfig<Sig>>> {
+    let config: BTreeMap<String, Vec<String>> = serde_json::from_str(json)?;
+
+    let mut patterns = vec![];
+    for (symbol, symbol_patterns) in config {
+        for (i, p) in symbol_patterns.into_iter().enumerate() {
+            patterns.push(PatternConfig::new(
+                Sig(format!("file {symbol}")),
+                format!("#{i} {symbol}"),
+                None,
+                Pattern::new(&p).with_context(|| format!("bad pattern #{i} for {symbol}: {p}"))?,
+            ));
+        }
+    }
+    Ok(patterns)
+}
+
+/// Parse newline delimited patterns, skipping blank lines and `#` comments. Patterns are named
+/// after their line number.
+fn parse_patterns_file(text: &str) -> Result<Vec<PatternConfig<Sig>>> {
+    let mut patterns = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_number = i + 1;
+        patterns.push(PatternConfig::new(
+            Sig("patterns file".to_string()),
+            format!("line {line_number}"),
+            None,
+            Pattern::new(line).with_context(|| format!("bad pattern on line {line_number}"))?,
+        ));
+    }
+    Ok(patterns)
+}
+
 fn scan(command: CommandScan) -> Result<()> {
-    let include_default = command.patterns.is_empty() && command.xref.is_empty();
+    if command.watch {
+        scan_watch(command)
+    } else {
+        scan_once(command)
+    }
+}
+
+/// Rescan each time the modification time of --pattern-config changes
+fn scan_watch(command: CommandScan) -> Result<()> {
+    let path = command
+        .pattern_config
+        .clone()
+        .context("--watch requires --pattern-config")?;
+    let modified = || fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+    loop {
+        let last = modified();
+
+        // clear screen
+        print!("\x1b[2J\x1b[H");
+        if let Err(err) = scan_once(command.clone()) {
+            println!("{err:#}");
+        }
+        println!("watching {} for changes", path.display());
+
+        while modified() == last {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    }
+}
+
+fn scan_once(command: CommandScan) -> Result<()> {
+    let include_default =
+        command.patterns.is_empty() && command.xref.is_empty() && command.patterns_file.is_none();
+    let config_patterns = match &command.pattern_config {
+        Some(path) => parse_pattern_config(&fs::read_to_string(path)?)
+            .with_context(|| format!("failed to read pattern config {}", path.display()))?,
+        None => vec![],
+    };
+    let file_patterns = match &command.patterns_file {
+        Some(path) => {
+            let text = if path.as_os_str() == "-" {
+                std::io::read_to_string(std::io::stdin())?
+            } else {
+                fs::read_to_string(path)?
+            };
+            parse_patterns_file(&text)
+                .with_context(|| format!("failed to read patterns file {}", path.display()))?
+        }
+        None => vec![],
+    };
     // TODO warn if empty?
     let patterns = command
         .patterns
@@ -254,27 +486,25 @@ fn scan(command: CommandScan) -> Result<()> {
         .chain(command.xref.into_iter().enumerate().map(|(i, p)| {
             PatternConfig::xref(Sig("arg".to_string()), format!("xref {i}"), None, p)
         }))
-        .chain(command.pattern_config.into_iter().flat_map(|path| {
-            let file = std::fs::read_to_string(path).unwrap();
-            let config: HashMap<String, Vec<String>> = serde_json::from_str(&file).unwrap();
-
-            config.into_iter().flat_map(|(symbol, patterns)| {
-                patterns.into_iter().enumerate().map(move |(i, p)| {
-                    PatternConfig::new(
-                        Sig(format!("file {symbol}")),
-                        format!("#{i} {symbol}"),
-                        None,
-                        Pattern::new(p).unwrap(),
-                    )
-                })
-            })
-        }))
+        .chain(config_patterns)
+        .chain(file_patterns)
         .collect_vec();
+    for config in &patterns {
+        if let Some(pattern) = config.scan.scan_type.get_pattern() {
+            for warning in pattern.validate() {
+                eprintln!(
+                    "warning: {:?}({}) {pattern}: {warning}",
+                    config.sig, config.name
+                );
+            }
+        }
+    }
 
+    // results are indexed by resolver so duplicates must be removed before resolving
     let resolvers = if command.resolver.is_empty() && include_default {
         resolvers().collect::<Vec<_>>()
     } else {
-        command.resolver
+        select_resolvers(&command.resolver, None)
     };
     let dyn_resolvers = resolvers.iter().map(|res| res.getter).collect::<Vec<_>>();
 
@@ -286,7 +516,7 @@ fn scan(command: CommandScan) -> Result<()> {
     let mut games: HashSet<String> = Default::default();
 
     let mut all: HashMap<(String, (&Sig, &String)), Vec<Resolution>> = HashMap::new();
-    let mut all_resolutions: HashMap<String, _> = Default::default();
+    let mut all_resolutions: HashMap<String, Vec<DynResolution>> = Default::default();
 
     use colored::Colorize;
     use indicatif::ProgressIterator;
@@ -296,6 +526,8 @@ fn scan(command: CommandScan) -> Result<()> {
     enum Output {
         Stdout,
         Progress(ProgressBar),
+        /// Lines collected to be printed later
+        Buffer(std::sync::Mutex<Vec<String>>),
     }
 
     impl Output {
@@ -303,29 +535,69 @@ fn scan(command: CommandScan) -> Result<()> {
             match self {
                 Output::Stdout => println!("{}", msg.as_ref()),
                 Output::Progress(progress) => progress.println(msg),
+                Output::Buffer(lines) => lines.lock().unwrap().push(msg.as_ref().to_string()),
             }
         }
     }
 
+    /// Show resolver stages in the progress bar message
+    struct ResolverProgress<'a>(&'a ProgressBar);
+
+    impl patternsleuth::resolvers::ProgressSink for ResolverProgress<'_> {
+        fn on_stage(&mut self, stage: usize, pending: usize, done: usize) {
+            self.0
+                .set_message(format!("stage {stage}: {done} resolved, {pending} pending"));
+        }
+    }
+
     let mut games_vec = vec![];
 
     if let Some(pid) = command.pid {
         games_vec.push(GameEntry::Process(GameProcessEntry { pid }));
+    } else if let Some(process_name) = &command.process_name {
+        let processes = patternsleuth::process::external::list_processes()?;
+        games_vec.extend(
+            match_processes(&processes, process_name, command.all)?
+                .into_iter()
+                .map(|pid| GameEntry::Process(GameProcessEntry { pid })),
+        );
     } else {
         games_vec.extend(get_games(command.game)?.into_iter().map(GameEntry::File));
     }
 
-    let (output, iter): (_, Box<dyn Iterator<Item = _>>) = if command.progress {
-        let progress = ProgressBar::new(games_vec.len() as u64);
-        (
-            Output::Progress(progress.clone()),
-            Box::new(games_vec.iter().progress_with(progress)),
-        )
+    if command.export_ida.is_some() && games_vec.len() != 1 {
+        bail!(
+            "--export-ida requires exactly one game, found {}",
+            games_vec.len()
+        );
+    }
+
+    let imports_glob = command
+        .imports
+        .as_deref()
+        .map(|g| {
+            globset::GlobBuilder::new(g.strip_prefix("__imp_").unwrap_or(g))
+                .case_insensitive(true)
+                .build()
+                .map(|g| g.compile_matcher())
+        })
+        .transpose()?;
+
+    let output = if command.progress {
+        Output::Progress(ProgressBar::new(games_vec.len() as u64).with_style(
+            indicatif::ProgressStyle::with_template("{wide_bar} {pos}/{len} {msg}").unwrap(),
+        ))
     } else {
-        (Output::Stdout, Box::new(games_vec.iter()))
+        Output::Stdout
     };
 
-    for game in iter {
+    struct GameScan<'p> {
+        name: String,
+        scan: patternsleuth::ScanResult<'p, Sig>,
+        resolution: Vec<DynResolution>,
+    }
+
+    let scan_game = |game: &GameEntry, output: &Output| -> Result<Option<GameScan<'_>>> {
         #[allow(unused_assignments)]
         let mut bin_data = None;
 
@@ -333,7 +605,7 @@ fn scan(command: CommandScan) -> Result<()> {
             GameEntry::File(GameFileEntry { name, exe_path }) => {
                 output.println(format!("{:?} {:?}", name, exe_path.display()));
 
-                bin_data = Some(fs::read(exe_path)?);
+                bin_data = Some(FileData::read(exe_path, command.mmap)?);
 
                 (Cow::Borrowed(name), {
                     let bin_data = bin_data.as_ref().unwrap();
@@ -347,7 +619,7 @@ fn scan(command: CommandScan) -> Result<()> {
                         Ok(exe) => exe,
                         Err(err) => {
                             output.println(format!("err reading {}: {}", exe_path.display(), err));
-                            continue;
+                            return Ok(None);
                         }
                     }
                 })
@@ -362,7 +634,24 @@ fn scan(command: CommandScan) -> Result<()> {
             }
         };
 
-        games.insert(name.to_string());
+        if command.exports {
+            for (name, address) in exe.exports.iter().sorted_by_key(|e| e.1) {
+                output.println(format!("{address:016x} {name}"));
+            }
+            for (name, target) in exe.forwarded_exports.iter().sorted() {
+                output.println(format!("{:>16} {name} -> {target}", "forwarded"));
+            }
+        }
+
+        if let Some(glob) = &imports_glob {
+            for (lib, name, address) in exe
+                .imports_iter()
+                .filter(|(_, name, _)| glob.is_match(name.strip_prefix("__imp_").unwrap_or(name)))
+                .sorted_by_key(|i| i.2)
+            {
+                output.println(format!("{address:016x} {lib}!{name}"));
+            }
+        }
 
         let scan = exe.scan(&patterns)?;
 
@@ -376,15 +665,59 @@ fn scan(command: CommandScan) -> Result<()> {
                 map
             });
 
+        let game_name = match game {
+            GameEntry::File(GameFileEntry { name, .. }) => name.clone(),
+            GameEntry::Process(GameProcessEntry { pid }) => format!("pid={pid}"),
+        };
+
+        let resolution = tracing::info_span!("scan", game = game_name).in_scope(|| match &output {
+            Output::Progress(progress) => {
+                exe.resolve_many_with_progress(&dyn_resolvers, &mut ResolverProgress(progress))
+            }
+            _ => exe.resolve_many(&dyn_resolvers),
+        });
+
+        // name imports, exports and resolved addresses in disassembly
+        let annotations = (command.disassemble || command.disassemble_merged).then(|| {
+            let mut annotations = disassemble::image_annotations(&exe);
+            for resolved in collect_resolved(resolvers.iter().map(|r| r.name).zip(&resolution)) {
+                if let ResolvedName::Address(name, address) = resolved {
+                    annotations.insert(address as u64, name.to_string());
+                }
+            }
+            annotations
+        });
+
+        let rebase = |address: usize| match command.rebase {
+            Some(new_base) => Resolution { address }.rebased(exe.base_address, new_base),
+            None => address,
+        };
+
         let mut table = Table::new();
-        table.set_titles(row!["sig", "offline scan"]);
+        match command.rebase {
+            Some(new_base) => table.set_titles(row![
+                "sig",
+                format!("offline scan (rebased to {new_base:#x})")
+            ]),
+            None => table.set_titles(row!["sig", "offline scan"]),
+        }
 
         for sig in &sigs {
             let mut cells = vec![];
             cells.push(Cell::new(&sig.to_string()));
 
             if let Some(sig_scans) = folded_scans.get(&sig) {
-                if command.disassemble {
+                if command.disassemble && command.format == DisassemblyFormat::Json {
+                    for m in sig_scans.iter() {
+                        output.println(serde_json::to_string(&serde_json::json!({
+                            "game": name,
+                            "name": m.0.name,
+                            "address": m.1.address,
+                            "instructions": disassemble::disassemble_json(&exe, m.1.address, 20),
+                        }))?);
+                    }
+                    cells.push(Cell::new(&format!("{} matches", sig_scans.len())));
+                } else if command.disassemble {
                     let mut table = Table::new();
                     table.set_format(*format::consts::FORMAT_NO_BORDER);
                     for m in sig_scans.iter() {
@@ -395,7 +728,8 @@ fn scan(command: CommandScan) -> Result<()> {
                             disassemble::disassemble(
                                 &exe,
                                 m.1.address,
-                                m.0.scan.scan_type.get_pattern()
+                                m.0.scan.scan_type.get_pattern(),
+                                annotations.as_ref(),
                             )
                         )));
                         table.add_row(Row::new(cells));
@@ -416,7 +750,12 @@ fn scan(command: CommandScan) -> Result<()> {
                             // sort by pattern name, then match address
                             .sorted_by_key(|&data| data.0)
                             .map(|(m, counts)| {
-                                let dis = disassemble::disassemble(&exe, m.address, None);
+                                let dis = disassemble::disassemble(
+                                    &exe,
+                                    m.address,
+                                    None,
+                                    annotations.as_ref(),
+                                );
 
                                 let mut lines = vec![];
                                 for (name, count) in counts.iter().sorted_by_key(|e| e.0) {
@@ -465,7 +804,7 @@ fn scan(command: CommandScan) -> Result<()> {
                                 };
 
                                 (
-                                    format!("{:016x} {:?}{}", m.1.address, m.0, count)
+                                    format!("{:016x} {:?}{}", rebase(m.1.address), m.0, count)
                                         .normal()
                                         .to_string(),
                                     exe.symbols
@@ -495,24 +834,29 @@ fn scan(command: CommandScan) -> Result<()> {
             table.add_row(Row::new(cells));
         }
 
-        let game_name = match game {
-            GameEntry::File(GameFileEntry { name, .. }) => name.clone(),
-            GameEntry::Process(GameProcessEntry { pid }) => format!("pid={pid}"),
-        };
-
-        let resolution = tracing::info_span!("scan", game = game_name)
-            .in_scope(|| exe.resolve_many(&dyn_resolvers));
-
         for (resolver, resolution) in resolvers.iter().zip(&resolution) {
             table.add_row(Row::new(
                 [
                     Cell::new(resolver.name),
                     match resolution {
-                        Ok(res) => Cell::new(&format!("{:#x?}", res)),
-                        Err(err) =>
-                        {
-                            #[allow(clippy::unnecessary_to_owned)]
-                            Cell::new(&format!("{:x?}", err).red().to_string())
+                        Ok(res) => match (command.rebase, res.get()) {
+                            (Some(_), Some(address)) => {
+                                Cell::new(&format!("{:#x} (rebased)", rebase(address)))
+                            }
+                            _ => Cell::new(&format!("{:#x?}", res)),
+                        },
+                        Err(err) => {
+                            let text = format!("{:x?}", err);
+                            // ambiguous results are usually a pattern away from resolving
+                            let text = if matches!(
+                                err,
+                                patternsleuth::resolvers::ResolveError::Ambiguous { .. }
+                            ) {
+                                text.yellow()
+                            } else {
+                                text.red()
+                            };
+                            Cell::new(&text.to_string())
                         }
                     },
                 ]
@@ -520,21 +864,98 @@ fn scan(command: CommandScan) -> Result<()> {
             ));
         }
 
-        if !resolution.is_empty() {
-            all_resolutions.insert(name.to_string(), resolution);
+        if command.verify_symbols {
+            if let Some(symbols) = &exe.symbols {
+                let resolved = collect_resolved(resolvers.iter().map(|r| r.name).zip(&resolution));
+                for (name, address, symbol) in symbol_mismatches(symbols, &resolved) {
+                    output.println(
+                        format!("{name} resolved to {address:016x} which is {symbol}")
+                            .yellow()
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        if let Some(path) = &command.export_ida {
+            let script = ida_script(
+                exe.base_address,
+                resolvers.iter().map(|r| r.name).zip(&resolution),
+            );
+            fs::write(path, script)?;
         }
 
         output.println(table.to_string());
 
+        Ok(Some(GameScan {
+            name: name.to_string(),
+            scan,
+            resolution,
+        }))
+    };
+
+    /// Fold a finished game scan into the summary maps
+    fn add_game<'p>(
+        game_scan: GameScan<'p>,
+        games: &mut HashSet<String>,
+        all_resolutions: &mut HashMap<String, Vec<DynResolution>>,
+        all: &mut HashMap<(String, (&'p Sig, &'p String)), Vec<Resolution>>,
+    ) {
+        let GameScan {
+            name,
+            scan,
+            resolution,
+        } = game_scan;
+
+        games.insert(name.clone());
+
+        if !resolution.is_empty() {
+            all_resolutions.insert(name.clone(), resolution);
+        }
+
         // fold current game scans into summary scans
-        scan.results.into_iter().fold(&mut all, |map, m| {
-            map.entry((name.to_string(), (&m.0.sig, &m.0.name)))
+        scan.results.into_iter().fold(all, |map, m| {
+            map.entry((name.clone(), (&m.0.sig, &m.0.name)))
                 .or_default()
                 .push(m.1);
             map
         });
     }
 
+    if let Some(jobs) = command.jobs {
+        // buffer output of each game so it is printed in order once all games are done
+        let results = par_map_ordered(&games_vec, jobs, |game| {
+            let buffer = Output::Buffer(Default::default());
+            let result = scan_game(game, &buffer);
+            if let Output::Progress(progress) = &output {
+                progress.inc(1);
+            }
+            (buffer, result)
+        })?;
+        for (buffer, result) in results {
+            if let Output::Buffer(lines) = buffer {
+                for line in lines.into_inner().unwrap() {
+                    output.println(line);
+                }
+            }
+            if let Some(game_scan) = result? {
+                add_game(game_scan, &mut games, &mut all_resolutions, &mut all);
+            }
+        }
+    } else {
+        let iter: Box<dyn Iterator<Item = _>> = match &output {
+            Output::Progress(progress) => {
+                Box::new(games_vec.iter().progress_with(progress.clone()))
+            }
+            _ => Box::new(games_vec.iter()),
+        };
+        for game in iter {
+            if let Some(game_scan) = scan_game(game, &output)? {
+                add_game(game_scan, &mut games, &mut all_resolutions, &mut all);
+            }
+        }
+    }
+
     // force any progress output to be dropped
     let output = Output::Stdout;
 
@@ -656,18 +1077,211 @@ fn scan(command: CommandScan) -> Result<()> {
     Ok(())
 }
 
+type DynResolution =
+    patternsleuth::resolvers::Result<std::sync::Arc<dyn patternsleuth::resolvers::Resolution>>;
+
+/// A successfully resolved resolver
+#[derive(Debug, PartialEq)]
+enum ResolvedName<'a> {
+    Address(&'a str, usize),
+    /// Resolutions which are not an address, e.g. strings or versions
+    Other(&'a str, String),
+}
+
+/// Normalize a resolver or demangled symbol name for fuzzy comparison, e.g.
+/// `public: virtual void __cdecl UObject::ProcessEvent(class UFunction *, void *)` becomes
+/// `uobjectprocessevent`
+fn normalize_symbol_name(name: &str) -> String {
+    let name = name.split('(').next().unwrap_or_default();
+    let name = name.split_whitespace().last().unwrap_or_default();
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Whether the symbol at a resolved address plausibly belongs to the resolver
+fn symbol_matches_resolver(resolver: &str, symbol: &Symbol) -> bool {
+    let resolver = normalize_symbol_name(resolver);
+    let symbol = normalize_symbol_name(&symbol.demangle());
+    !resolver.is_empty()
+        && !symbol.is_empty()
+        && (resolver.contains(&symbol) || symbol.contains(&resolver))
+}
+
+/// Find resolved addresses with a symbol unrelated to the resolver name. Addresses without a
+/// symbol are not reported.
+fn symbol_mismatches<'a>(
+    symbols: &HashMap<usize, Symbol>,
+    resolved: &[ResolvedName<'a>],
+) -> Vec<(&'a str, usize, String)> {
+    resolved
+        .iter()
+        .filter_map(|resolved| match resolved {
+            ResolvedName::Address(name, address) => {
+                let symbol = symbols.get(address)?;
+                (!symbol_matches_resolver(name, symbol))
+                    .then(|| (*name, *address, symbol.demangle()))
+            }
+            ResolvedName::Other(..) => None,
+        })
+        .collect()
+}
+
+/// Collect successful resolutions, dropping errors
+fn collect_resolved<'a>(
+    resolutions: impl IntoIterator<Item = (&'a str, &'a DynResolution)>,
+) -> Vec<ResolvedName<'a>> {
+    resolutions
+        .into_iter()
+        .filter_map(|(name, res)| {
+            let res = res.as_ref().ok()?;
+            Some(match res.get() {
+                Some(address) => ResolvedName::Address(name, address),
+                None => ResolvedName::Other(name, format!("{res:x?}").replace('\n', " ")),
+            })
+        })
+        .collect()
+}
+
+/// Generate an IDA Python script which names resolved addresses, rebased from `base_address` to
+/// the IDB's image base. Resolutions that are not addresses are emitted as comments.
+fn ida_script<'a>(
+    base_address: usize,
+    resolutions: impl IntoIterator<Item = (&'a str, &'a DynResolution)>,
+) -> String {
+    let mut lines = vec![
+        "import idaapi".to_string(),
+        "import idc".to_string(),
+        "".to_string(),
+        format!("base = {base_address:#x}"),
+        "imagebase = idaapi.get_imagebase()".to_string(),
+        "".to_string(),
+    ];
+    for resolved in collect_resolved(resolutions) {
+        lines.push(match resolved {
+            ResolvedName::Address(name, address) => {
+                format!("idc.set_name({address:#x} - base + imagebase, {name:?})")
+            }
+            ResolvedName::Other(name, value) => format!("# {name}: {value}"),
+        });
+    }
+    lines.push("".to_string());
+    lines.join("\n")
+}
+
+/// Generate a Ghidra Python script which labels resolved addresses, rebased from `base_address`
+/// to `image_base` or the program's image base if omitted. Resolutions that are not addresses
+/// are emitted as comments.
+fn ghidra_script<'a>(
+    base_address: usize,
+    image_base: Option<usize>,
+    resolutions: impl IntoIterator<Item = (&'a str, &'a DynResolution)>,
+) -> String {
+    let mut lines = vec![
+        "# @category patternsleuth".to_string(),
+        "".to_string(),
+        format!("base = {base_address:#x}"),
+        match image_base {
+            Some(image_base) => format!("image_base = {image_base:#x}"),
+            None => "image_base = currentProgram.getImageBase().getOffset()".to_string(),
+        },
+        "".to_string(),
+    ];
+    for resolved in collect_resolved(resolutions) {
+        lines.push(match resolved {
+            ResolvedName::Address(name, address) => {
+                format!("createLabel(toAddr({address:#x} - base + image_base), {name:?}, True)")
+            }
+            ResolvedName::Other(name, value) => format!("# {name}: {value}"),
+        });
+    }
+    lines.push("".to_string());
+    lines.join("\n")
+}
+
+fn export_ghidra(command: CommandExportGhidra) -> Result<()> {
+    let games = get_games([command.game])?;
+    let [game] = games.as_slice() else {
+        bail!("expected exactly one game, found {}", games.len());
+    };
+
+    let resolvers = if command.resolver.is_empty() {
+        resolvers().collect_vec()
+    } else {
+        select_resolvers(&command.resolver, None)
+    };
+
+    let data = fs::read(&game.exe_path)?;
+    let exe = Image::builder().build(&data)?;
+    let resolution = exe.resolve_many(&resolvers.iter().map(|r| r.getter).collect_vec());
+
+    let script = ghidra_script(
+        exe.base_address,
+        command.image_base,
+        resolvers.iter().map(|r| r.name).zip(&resolution),
+    );
+    fs::write(command.output, script)?;
+
+    Ok(())
+}
+
+fn explain(command: CommandExplain) -> Result<()> {
+    use colored::Colorize;
+
+    let games = get_games([command.game])?;
+    let [game] = games.as_slice() else {
+        bail!("expected exactly one game, found {}", games.len());
+    };
+
+    let data = fs::read(&game.exe_path)?;
+    let exe = Image::builder().build(&data)?;
+
+    let pattern = &command.pattern;
+    let start = command
+        .address
+        .checked_sub(pattern.custom_offset)
+        .context("address is before pattern offset")?;
+    let section = exe.memory.get_section_containing(start)?;
+    let explanation = pattern.explain(section.data(), start - section.address());
+
+    for byte in &explanation {
+        let line = format!("{:016x} {byte}", start + byte.offset);
+        if byte.is_match() {
+            println!("{}", line.green());
+        } else {
+            println!("{}", line.red());
+        }
+    }
+    let matched = explanation.iter().filter(|b| b.is_match()).count();
+    println!("{matched}/{} bytes matched", explanation.len());
+    if matched == explanation.len()
+        && !pattern.is_match(section.data(), section.address(), start - section.address())
+    {
+        println!("byte ranges or xrefs did not match");
+    }
+    println!();
+    println!(
+        "{}",
+        disassemble::disassemble(&exe, start, Some(pattern), None)
+    );
+
+    Ok(())
+}
+
 fn report(command: CommandReport) -> Result<()> {
     use rayon::prelude::*;
 
-    fn load_game(path: impl AsRef<Path>, data: &mut Vec<u8>) -> Result<Image<'_>> {
-        use std::io::Read;
-        data.clear();
-        fs::File::open(path)?.read_to_end(data)?;
-        Image::builder().build(data)
+    fn load_game(
+        path: impl AsRef<Path>,
+        data: &mut Option<FileData>,
+        mmap: bool,
+    ) -> Result<Image<'_>> {
+        Image::builder().build(data.insert(FileData::read(path, mmap)?))
     }
 
-    let resolvers = command
-        .resolver
+    let named_resolvers = select_resolvers(&command.resolver, command.resolver_filter.as_ref());
+    let resolvers = named_resolvers
         .iter()
         .map(|res| res.getter)
         .collect::<Vec<_>>();
@@ -676,16 +1290,39 @@ fn report(command: CommandReport) -> Result<()> {
         "[year]-[month]-[day]_[hour]-[minute]-[second]"
     ))?;
 
-    let games = get_games(command.game)?;
+    let mut games = get_games_sampled(command.game, command.sample)?;
+
+    // results are only reused by builds of the same commit
+    let cache = command.cache.as_ref().map(|dir| {
+        use patternsleuth::resolvers::cache::ResolveCache;
+        match option_env!("GIT_HASH") {
+            Some(hash) => ResolveCache::with_version(dir, hash),
+            None => ResolveCache::new(dir),
+        }
+    });
+
+    let partials = if let Some(dir) = &command.resume {
+        let partials = resume_report(dir, &mut games)?;
+        println!("resuming: skipping {} completed games", partials.len());
+        partials
+    } else {
+        Default::default()
+    };
+    let results = std::sync::Arc::new(std::sync::Mutex::new(partials));
 
-    let results = std::sync::Arc::new(std::sync::Mutex::new(BTreeMap::new()));
+    // stop resolving on Ctrl-C but still write out the games completed so far
+    let cancel = cancel_on_ctrlc();
 
     let progress = ProgressBar::new(games.len() as u64);
     games.into_par_iter().try_for_each(|game| -> Result<()> {
+        if cancel.is_cancelled() {
+            return Ok(());
+        }
+
         progress.println(format!("{:?} {:?}", game.name, game.exe_path.display()));
 
-        let mut data = vec![];
-        let exe = match load_game(&game.exe_path, &mut data) {
+        let mut data = None;
+        let exe = match load_game(&game.exe_path, &mut data, command.mmap) {
             Ok(exe) => exe,
             Err(err) => {
                 progress.println(format!("err reading {}: {}", game.exe_path.display(), err));
@@ -694,14 +1331,25 @@ fn report(command: CommandReport) -> Result<()> {
             }
         };
 
-        let resolution = exe.resolve_many(&resolvers);
+        let resolution = if let Some(cache) = &cache {
+            cache.resolve_many_cancellable(&exe, &named_resolvers, &cancel)?
+        } else {
+            exe.resolve_many_cancellable(&resolvers, &cancel)
+        };
+        let Ok(resolution) = resolution else {
+            return Ok(());
+        };
 
-        let map = command
-            .resolver
-            .iter()
-            .zip(resolution)
-            .map(|(resolver, resolution)| (resolver.name, resolution))
-            .collect::<BTreeMap<_, _>>();
+        let map = serde_json::to_value(
+            named_resolvers
+                .iter()
+                .zip(resolution)
+                .map(|(resolver, resolution)| (resolver.name, resolution))
+                .collect::<BTreeMap<_, _>>(),
+        )?;
+        if let Some(dir) = &command.resume {
+            write_partial_report(dir, &game.name, &map)?;
+        }
         results.lock().unwrap().insert(game.name, map);
 
         progress.inc(1);
@@ -709,6 +1357,10 @@ fn report(command: CommandReport) -> Result<()> {
         Ok(())
     })?;
 
+    if cancel.is_cancelled() {
+        progress.println("cancelled, writing partial report");
+    }
+
     fs::create_dir_all("reports")?;
     fs::write(
         format!(
@@ -732,34 +1384,133 @@ fn report(command: CommandReport) -> Result<()> {
 
     Ok(())
 }
-fn diff_report(command: CommandDiffReport) -> Result<()> {
-    use colored::Colorize;
-    use patternsleuth::resolvers::{Resolution, ResolveError};
-    use prettytable::{Cell, Row, Table};
-    type Report = BTreeMap<String, BTreeMap<String, Result<Box<dyn Resolution>, ResolveError>>>;
 
-    let a: Report = serde_json::from_slice(&fs::read(command.a)?)?;
-    let b: Report = serde_json::from_slice(&fs::read(command.b)?)?;
+/// Load partial results written to `dir` by a previous `report --resume` and remove the games
+/// they cover from `games`
+fn resume_report(
+    dir: &Path,
+    games: &mut Vec<GameFileEntry>,
+) -> Result<BTreeMap<String, serde_json::Value>> {
+    fs::create_dir_all(dir)?;
+    let mut partials = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let partial: BTreeMap<String, serde_json::Value> =
+                serde_json::from_slice(&fs::read(&path)?)
+                    .with_context(|| format!("bad partial report {}", path.display()))?;
+            partials.extend(partial);
+        }
+    }
+    games.retain(|game| !partials.contains_key(&game.name));
+    Ok(partials)
+}
 
-    let mut games_only_in_a = vec![];
-    let mut games_only_in_b = vec![];
+/// Write the results of a single game to `dir`. The file is written under a temporary name and
+/// then renamed so an interrupted run never leaves a truncated partial behind.
+fn write_partial_report(dir: &Path, game: &str, results: &serde_json::Value) -> Result<()> {
+    let name = game
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    let path = dir.join(format!("{name}.json"));
+    let tmp = dir.join(format!("{name}.json.tmp"));
+    fs::write(
+        &tmp,
+        serde_json::to_vec(&BTreeMap::from([(game, results)]))?,
+    )?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
 
-    type Res<'r> = Result<&'r Box<dyn Resolution + 'static>, &'r ResolveError>;
-    let mut diffs: BTreeMap<&str, BTreeMap<&str, (Res, Res)>> = Default::default();
+/// Explicitly requested resolvers followed by any other resolvers whose name matches `filter`.
+/// Resolvers are deduplicated by name, keeping the first occurrence, so results of
+/// `resolve_many` align 1:1 with the returned list.
+fn select_resolvers(
+    explicit: &[&'static NamedResolver],
+    filter: Option<&regex::Regex>,
+) -> Vec<&'static NamedResolver> {
+    let mut selected = explicit.iter().copied().unique_by(|r| r.name).collect_vec();
+    if let Some(filter) = filter {
+        for resolver in resolvers()
+            .filter(|r| filter.is_match(r.name))
+            .sorted_by_key(|r| r.name)
+        {
+            if !selected.iter().any(|s| s.name == resolver.name) {
+                selected.push(resolver);
+            }
+        }
+    }
+    selected
+}
+
+/// Install a Ctrl-C handler returning a token which is cancelled on the first Ctrl-C. The default
+/// handler is restored so a second Ctrl-C exits immediately.
+fn cancel_on_ctrlc() -> patternsleuth::resolvers::CancelToken {
+    static CANCEL: std::sync::OnceLock<patternsleuth::resolvers::CancelToken> =
+        std::sync::OnceLock::new();
+
+    extern "C" fn handler(_: libc::c_int) {
+        if let Some(cancel) = CANCEL.get() {
+            cancel.cancel();
+        }
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+
+    let cancel = CANCEL.get_or_init(Default::default).clone();
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            handler as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+    cancel
+}
+
+type Report = BTreeMap<
+    String,
+    BTreeMap<
+        String,
+        Result<
+            Box<dyn patternsleuth::resolvers::Resolution>,
+            patternsleuth::resolvers::ResolveError,
+        >,
+    >,
+>;
+type ReportRes<'r> = Result<
+    &'r Box<dyn patternsleuth::resolvers::Resolution + 'static>,
+    &'r patternsleuth::resolvers::ResolveError,
+>;
+
+/// Resolutions of games present in both reports, keyed by resolver then game
+#[derive(Default)]
+struct ReportDiff<'r> {
+    games_only_in_a: Vec<&'r String>,
+    games_only_in_b: Vec<&'r String>,
+    diffs: BTreeMap<&'r str, BTreeMap<&'r str, (ReportRes<'r>, ReportRes<'r>)>>,
+}
+
+fn diff_reports<'r>(a: &'r Report, b: &'r Report) -> ReportDiff<'r> {
+    let mut diff = ReportDiff::default();
 
     for game in a.keys().chain(b.keys()).unique() {
         let game_a = a.get(game);
         let game_b = b.get(game);
         if game_a.is_none() {
-            games_only_in_b.push(game);
+            diff.games_only_in_b.push(game);
         }
         if game_b.is_none() {
-            games_only_in_a.push(game);
+            diff.games_only_in_a.push(game);
         }
         if let (Some(game_a), Some(game_b)) = (game_a, game_b) {
             for res in game_a.keys().chain(game_b.keys()).unique() {
                 if let (Some(res_a), Some(res_b)) = (game_a.get(res), game_b.get(res)) {
-                    diffs
+                    diff.diffs
                         .entry(res)
                         .or_default()
                         .insert(game, (res_a.as_ref(), res_b.as_ref()));
@@ -770,6 +1521,92 @@ fn diff_report(command: CommandDiffReport) -> Result<()> {
         }
     }
 
+    diff
+}
+
+/// Success rates of a single resolver across both reports
+#[derive(Debug, PartialEq)]
+struct ResEntry {
+    total: usize,
+    ok_a: usize,
+    ok_b: usize,
+    /// Number of games which resolved successfully in both reports but to different values
+    ok_diff: usize,
+    /// Number of games which failed in the first report but resolved in the second
+    newly_ok: usize,
+    /// Number of games which resolved in the first report but failed in the second
+    newly_failed: usize,
+    percent_a: f32,
+    percent_b: f32,
+    percent_diff: f32,
+}
+
+impl ResEntry {
+    fn new(entries: &BTreeMap<&str, (ReportRes, ReportRes)>) -> Self {
+        let total = entries.len();
+        let ok_a = entries.values().filter(|res| res.0.is_ok()).count();
+        let ok_b = entries.values().filter(|res| res.1.is_ok()).count();
+        let ok_diff = entries
+            .values()
+            .filter(|pair| matches!(pair, (Ok(a), Ok(b)) if a != b))
+            .count();
+        let newly_ok = entries
+            .values()
+            .filter(|pair| matches!(pair, (Err(_), Ok(_))))
+            .count();
+        let newly_failed = entries
+            .values()
+            .filter(|pair| matches!(pair, (Ok(_), Err(_))))
+            .count();
+
+        let percent_a = ok_a as f32 / total as f32 * 100.;
+        let percent_b = ok_b as f32 / total as f32 * 100.;
+        Self {
+            total,
+            ok_a,
+            ok_b,
+            ok_diff,
+            newly_ok,
+            newly_failed,
+            percent_a,
+            percent_b,
+            percent_diff: percent_b - percent_a,
+        }
+    }
+}
+
+fn write_diff_csv<'a>(
+    mut w: impl std::io::Write,
+    results: impl IntoIterator<Item = (&'a str, &'a ResEntry)>,
+) -> std::io::Result<()> {
+    writeln!(
+        w,
+        "resolver,ok_a,total_a,percent_a,ok_b,total_b,percent_b,changed"
+    )?;
+    for (res, e) in results {
+        writeln!(
+            w,
+            "{res},{},{},{:.2},{},{},{:.2},{}",
+            e.ok_a, e.total, e.percent_a, e.ok_b, e.total, e.percent_b, e.ok_diff
+        )?;
+    }
+    Ok(())
+}
+
+fn diff_report(command: CommandDiffReport) -> Result<()> {
+    use colored::Colorize;
+    use patternsleuth::resolvers::{Resolution, ResolveError};
+    use prettytable::{Cell, Row, Table};
+
+    let a: Report = serde_json::from_slice(&fs::read(command.a)?)?;
+    let b: Report = serde_json::from_slice(&fs::read(command.b)?)?;
+
+    let ReportDiff {
+        games_only_in_a,
+        games_only_in_b,
+        diffs,
+    } = diff_reports(&a, &b);
+
     dbg!(games_only_in_a);
     dbg!(games_only_in_b);
 
@@ -797,54 +1634,43 @@ fn diff_report(command: CommandDiffReport) -> Result<()> {
         .to_string()
     }
 
-    struct ResEntry {
-        ok_diff: usize,
-        percent_a: f32,
-        percent_b: f32,
-        percent_diff: f32,
-    }
-
     let mut results = vec![];
 
     for (res, entries) in diffs {
         let mut table = Table::new();
 
-        let total = entries.len();
-        let ok_a = entries.values().filter(|res| res.0.is_ok()).count();
-        let ok_b = entries.values().filter(|res| res.1.is_ok()).count();
+        let entry = ResEntry::new(&entries);
         let diff = entries
             .iter()
             .filter(|(_, (a, b))| a.ok() != b.ok())
             .collect::<Vec<_>>();
-        let ok_diff = diff
-            .iter()
-            .filter(|(_, pair)| matches!(pair, (Ok(a), Ok(b)) if a != b))
-            .count();
-
-        let percent_a = ok_a as f32 / total as f32 * 100.;
-        let percent_b = ok_b as f32 / total as f32 * 100.;
-        let percent_diff = percent_b - percent_a;
-
-        results.push((
-            res,
-            ResEntry {
-                ok_diff,
-                percent_a,
-                percent_b,
-                percent_diff,
-            },
-        ));
+        let ResEntry {
+            total,
+            ok_a,
+            ok_b,
+            ok_diff,
+            newly_ok,
+            newly_failed,
+            percent_a,
+            percent_b,
+            percent_diff,
+        } = entry;
+        results.push((res, entry));
 
         if diff.is_empty() {
             continue;
         }
 
         let score = format_percent_diff(percent_diff);
-        let changed = if ok_diff == 0 {
-            "".to_string()
-        } else {
-            format!("{ok_diff} changed").yellow().bold().to_string()
-        };
+        let changed = [
+            (newly_ok, "newly ok", colored::Color::Green),
+            (newly_failed, "newly failed", colored::Color::Red),
+            (ok_diff, "changed", colored::Color::Yellow),
+        ]
+        .into_iter()
+        .filter(|(count, _, _)| *count != 0)
+        .map(|(count, label, color)| format!("{count} {label}").color(color).bold().to_string())
+        .join(" ");
         let title = format!(
             "{res} - {ok_a}/{total} ({percent_a:.2}%) => {ok_b}/{total} ({percent_b:.2}%): {score} {changed}"
         );
@@ -867,14 +1693,32 @@ fn diff_report(command: CommandDiffReport) -> Result<()> {
         Cell::new("a"),
         Cell::new("b"),
         Cell::new("increase"),
+        Cell::new("newly ok"),
+        Cell::new("newly failed"),
         Cell::new("changed"),
     ]));
-    for (res, entry) in results {
+    for (res, entry) in &results {
         table.add_row(Row::new(vec![
             Cell::new(res),
             Cell::new(&format!("{:.2}%", entry.percent_a)),
             Cell::new(&format!("{:.2}%", entry.percent_b)),
             Cell::new(&format_percent_diff(entry.percent_diff)),
+            Cell::new(
+                &local(format!("{}", entry.newly_ok), |s| match entry.newly_ok {
+                    0 => s.normal(),
+                    _ => s.green().bold(),
+                })
+                .to_string(),
+            ),
+            Cell::new(
+                &local(format!("{}", entry.newly_failed), |s| {
+                    match entry.newly_failed {
+                        0 => s.normal(),
+                        _ => s.red().bold(),
+                    }
+                })
+                .to_string(),
+            ),
             Cell::new(
                 &local(format!("{}", entry.ok_diff), |s| match entry.ok_diff {
                     0 => s.normal(),
@@ -886,9 +1730,45 @@ fn diff_report(command: CommandDiffReport) -> Result<()> {
     }
     table.printstd();
 
+    if let Some(csv) = command.csv {
+        write_diff_csv(
+            std::io::BufWriter::new(fs::File::create(csv)?),
+            results.iter().map(|(res, entry)| (*res, entry)),
+        )?;
+    }
+
     Ok(())
 }
 
+/// Symbol and the range of its root function if known
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct SymbolEntry<'a> {
+    address: usize,
+    name: &'a str,
+    range_start: Option<usize>,
+    range_end: Option<usize>,
+}
+
+/// Collect `symbols` sorted by address, looking up function ranges with `get_range`
+fn symbol_entries<'a>(
+    symbols: impl IntoIterator<Item = (&'a usize, &'a Symbol)>,
+    get_range: impl Fn(usize) -> Option<std::ops::Range<usize>>,
+) -> Vec<SymbolEntry<'a>> {
+    symbols
+        .into_iter()
+        .map(|(address, symbol)| {
+            let range = get_range(*address);
+            SymbolEntry {
+                address: *address,
+                name: &symbol.name,
+                range_start: range.as_ref().map(|r| r.start),
+                range_end: range.as_ref().map(|r| r.end),
+            }
+        })
+        .sorted_by_key(|e| e.address)
+        .collect()
+}
+
 fn symbols(command: CommandSymbols) -> Result<()> {
     let re = &command.symbol;
     let filter = |sym: &Symbol| re.iter().any(|re| re.is_match(&sym.name));
@@ -896,14 +1776,18 @@ fn symbols(command: CommandSymbols) -> Result<()> {
     use prettytable::{Cell, Row, Table};
 
     let mut cells = vec![];
+    let mut json = BTreeMap::new();
 
     for GameFileEntry { name, exe_path } in get_games(command.game)? {
-        if !exe_path.with_extension("pdb").exists() && !exe_path.with_extension("sym").exists() {
+        if !["pdb", "sym", "map"]
+            .iter()
+            .any(|ext| exe_path.with_extension(ext).exists())
+        {
             continue;
         }
 
         println!("{:?} {:?}", name, exe_path.display());
-        let bin_data = fs::read(&exe_path)?;
+        let bin_data = FileData::read(&exe_path, command.mmap)?;
         let exe = match Image::builder()
             .functions(true)
             .symbols(&exe_path)
@@ -916,6 +1800,18 @@ fn symbols(command: CommandSymbols) -> Result<()> {
             }
         };
 
+        if command.json.is_some() {
+            let entries = symbol_entries(
+                exe.symbols
+                    .iter()
+                    .flatten()
+                    .filter(|(_, sym)| re.is_empty() || filter(sym)),
+                |address| exe.get_root_function_range(address).ok().flatten(),
+            );
+            json.insert(name, serde_json::to_value(entries)?);
+            continue;
+        }
+
         for (address, sym) in exe.symbols.as_ref().unwrap() {
             if filter(sym) {
                 if let Ok(Some(full_range)) = exe.get_root_function_range(*address) {
@@ -930,6 +1826,11 @@ fn symbols(command: CommandSymbols) -> Result<()> {
         }
     }
 
+    if let Some(path) = command.json {
+        fs::write(path, serde_json::to_vec(&json)?)?;
+        return Ok(());
+    }
+
     let mut table = Table::new();
     table.set_titles(cells.iter().map(|c| c.0.name.clone()).collect());
     table.add_row(Row::new(
@@ -940,6 +1841,45 @@ fn symbols(command: CommandSymbols) -> Result<()> {
     Ok(())
 }
 
+/// Map `f` over `items` on a pool of `jobs` threads, returning results in input order
+fn par_map_ordered<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&T) -> R + Sync + Send,
+) -> Result<Vec<R>> {
+    use rayon::prelude::*;
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
+    Ok(pool.install(|| items.par_iter().map(f).collect()))
+}
+
+/// Contents of a game executable, either read into memory or memory mapped
+enum FileData {
+    Read(Vec<u8>),
+    Mapped(patternsleuth::image::Mmap),
+}
+
+impl FileData {
+    fn read(path: impl AsRef<Path>, mmap: bool) -> Result<Self> {
+        Ok(if mmap {
+            Self::Mapped(patternsleuth::image::mmap(path)?)
+        } else {
+            Self::Read(fs::read(path)?)
+        })
+    }
+}
+
+impl std::ops::Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Read(data) => data,
+            Self::Mapped(data) => data,
+        }
+    }
+}
+
 enum GameEntry {
     File(GameFileEntry),
     Process(GameProcessEntry),
@@ -954,7 +1894,43 @@ struct GameProcessEntry {
     pid: i32,
 }
 
+/// Find PIDs of `processes` whose name matches `filter`. Fails if nothing matches or if more
+/// than one process matches and `all` is not set
+fn match_processes(processes: &[(i32, String)], filter: &str, all: bool) -> Result<Vec<i32>> {
+    let glob = globset::GlobBuilder::new(filter)
+        .case_insensitive(true)
+        .build()?
+        .compile_matcher();
+    let matches = processes
+        .iter()
+        .filter(|(_, name)| glob.is_match(name))
+        .collect_vec();
+    match matches.as_slice() {
+        [] => bail!("no process found matching {filter:?}"),
+        [_, _, ..] if !all => bail!(
+            "{} processes match {filter:?}, use --all to scan all of them: {}",
+            matches.len(),
+            matches
+                .iter()
+                .map(|(pid, name)| format!("{name} (PID={pid})"))
+                .join(", ")
+        ),
+        _ => Ok(matches.iter().map(|(pid, _)| *pid).collect()),
+    }
+}
+
+/// Number of games taken per unique name prefix by [`sample_order`] in [`get_games`]
+const SAMPLE_PREFIX_SIZE: usize = 3;
+
 fn get_games(filter: impl AsRef<[String]>) -> Result<Vec<GameFileEntry>> {
+    get_games_sampled(filter, SAMPLE_PREFIX_SIZE)
+}
+
+/// Like [`get_games`] but with a custom `prefix_size` passed to [`sample_order`]
+fn get_games_sampled(
+    filter: impl AsRef<[String]>,
+    prefix_size: usize,
+) -> Result<Vec<GameFileEntry>> {
     let games_filter = filter
         .as_ref()
         .iter()
@@ -992,7 +1968,7 @@ fn get_games(filter: impl AsRef<[String]>) -> Result<Vec<GameFileEntry>> {
         .filter_map(|r| r.transpose())
         .collect::<Result<Vec<(String, _)>>>()
         .map(|entries| {
-            sample_order(entries, 3)
+            sample_order(entries, prefix_size)
                 .into_iter()
                 .map(|(name, exe_path)| GameFileEntry { name, exe_path })
                 .collect::<Vec<GameFileEntry>>()
@@ -1002,18 +1978,20 @@ fn get_games(filter: impl AsRef<[String]>) -> Result<Vec<GameFileEntry>> {
 /// Distribute pairs such that unique prefixes are encountered early
 /// e.g.
 /// 7_a 8_a 9_a 7_b 7_c 7_d 8_b 8_c 9_b
+///
+/// The order only depends on the set of keys, not the order of `entries`.
 fn sample_order<V>(entries: Vec<(String, V)>, prefix_size: usize) -> Vec<(String, V)> {
     let mut trie = StringPatriciaMap::from_iter(entries);
     let mut len = 1;
     let mut result = vec![];
     while !trie.is_empty() {
-        let mut prefixes = HashSet::new();
+        let mut prefixes = BTreeSet::new();
         for (k, _v) in trie.iter() {
             if k.chars().count() >= len {
                 prefixes.insert(k.chars().take(len).collect::<String>());
             }
         }
-        for p in prefixes.iter().sorted() {
+        for p in &prefixes {
             let take = trie
                 .iter_prefix(p)
                 .take(prefix_size)
@@ -1033,6 +2011,270 @@ fn sample_order<V>(entries: Vec<(String, V)>, prefix_size: usize) -> Vec<(String
 mod test {
     use super::*;
 
+    fn sample_resolutions() -> Vec<DynResolution> {
+        use patternsleuth::resolvers::unreal::{engine_version::EngineVersion, gworld::GWorld};
+        use patternsleuth::resolvers::ResolveError;
+        use std::sync::Arc;
+
+        vec![
+            Ok(Arc::new(GWorld(0x140123450))),
+            Ok(Arc::new(EngineVersion {
+                major: 4,
+                minor: 27,
+            })),
+            Err(ResolveError::Msg("not found".into())),
+        ]
+    }
+    const SAMPLE_NAMES: [&str; 3] = ["GWorld", "EngineVersion", "GEngine"];
+
+    #[test]
+    fn test_collect_resolved() {
+        let resolutions = sample_resolutions();
+        assert_eq!(
+            vec![
+                ResolvedName::Address("GWorld", 0x140123450),
+                ResolvedName::Other("EngineVersion", "EngineVersion(4.27)".to_string()),
+            ],
+            collect_resolved(SAMPLE_NAMES.into_iter().zip(&resolutions))
+        );
+    }
+
+    #[test]
+    fn test_symbol_mismatches() {
+        let symbol = |name: &str| Symbol {
+            name: name.to_string(),
+        };
+        let symbols = HashMap::from([
+            (0x1000, symbol("UObject::ProcessEvent")),
+            (0x2000, symbol("FMemory::Free")),
+            (0x3000, symbol("class FMalloc * GMalloc")),
+        ]);
+        let resolved = vec![
+            ResolvedName::Address("UObjectProcessEvent", 0x1000),
+            ResolvedName::Address("FNameToString", 0x2000),
+            ResolvedName::Address("GMalloc", 0x3000),
+            ResolvedName::Address("GWorld", 0x4000),
+            ResolvedName::Other("EngineVersion", "EngineVersion(4.27)".to_string()),
+        ];
+        assert_eq!(
+            vec![("FNameToString", 0x2000, "FMemory::Free".to_string())],
+            symbol_mismatches(&symbols, &resolved)
+        );
+    }
+
+    #[test]
+    fn test_ida_script() {
+        let resolutions = sample_resolutions();
+        let script = ida_script(0x140000000, SAMPLE_NAMES.into_iter().zip(&resolutions));
+
+        let lines = script.lines().collect_vec();
+        assert!(lines.contains(&"base = 0x140000000"));
+        assert!(lines.contains(&r#"idc.set_name(0x140123450 - base + imagebase, "GWorld")"#));
+        assert!(lines.contains(&"# EngineVersion: EngineVersion(4.27)"));
+        assert!(!script.contains("GEngine"));
+    }
+
+    #[test]
+    fn test_ghidra_script() {
+        let resolutions = sample_resolutions();
+        let names = || SAMPLE_NAMES.into_iter().zip(&resolutions);
+
+        let script = ghidra_script(0x140000000, Some(0x10000000), names());
+        let lines = script.lines().collect_vec();
+        assert!(lines.contains(&"base = 0x140000000"));
+        assert!(lines.contains(&"image_base = 0x10000000"));
+        assert!(lines
+            .contains(&r#"createLabel(toAddr(0x140123450 - base + image_base), "GWorld", True)"#));
+        assert!(lines.contains(&"# EngineVersion: EngineVersion(4.27)"));
+        assert!(!script.contains("GEngine"));
+
+        let script = ghidra_script(0x140000000, None, names());
+        assert!(script
+            .lines()
+            .any(|l| l == "image_base = currentProgram.getImageBase().getOffset()"));
+    }
+
+    #[test]
+    fn test_diff_csv() {
+        use patternsleuth::resolvers::unreal::gworld::GWorld;
+        use patternsleuth::resolvers::ResolveError;
+
+        let err = || Err(ResolveError::Msg("not found".into()));
+        let game = |res: Vec<(
+            &str,
+            Result<Box<dyn patternsleuth::resolvers::Resolution>, _>,
+        )>| {
+            res.into_iter()
+                .map(|(name, r)| (name.to_string(), r))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let a: Report = [
+            (
+                "game1".to_string(),
+                game(vec![("GWorld", Ok(Box::new(GWorld(1))))]),
+            ),
+            ("game2".to_string(), game(vec![("GWorld", err())])),
+            ("game3".to_string(), game(vec![("GWorld", err())])),
+            ("game4".to_string(), game(vec![("GWorld", err())])),
+        ]
+        .into();
+        let b: Report = [
+            (
+                "game1".to_string(),
+                game(vec![("GWorld", Ok(Box::new(GWorld(2))))]),
+            ),
+            (
+                "game2".to_string(),
+                game(vec![("GWorld", Ok(Box::new(GWorld(3))))]),
+            ),
+            ("game3".to_string(), game(vec![("GWorld", err())])),
+            ("game4".to_string(), game(vec![("GWorld", err())])),
+        ]
+        .into();
+
+        let diff = diff_reports(&a, &b);
+        let results = diff
+            .diffs
+            .iter()
+            .map(|(res, entries)| (*res, ResEntry::new(entries)))
+            .collect_vec();
+
+        let mut csv = vec![];
+        write_diff_csv(&mut csv, results.iter().map(|(res, e)| (*res, e))).unwrap();
+        assert_eq!(
+            "resolver,ok_a,total_a,percent_a,ok_b,total_b,percent_b,changed\n\
+             GWorld,1,4,25.00,2,4,50.00,1\n",
+            String::from_utf8(csv).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_res_entry_regressions() {
+        use patternsleuth::resolvers::unreal::gworld::GWorld;
+        use patternsleuth::resolvers::ResolveError;
+
+        let ok = |v| -> Result<Box<dyn patternsleuth::resolvers::Resolution>, _> {
+            Ok(Box::new(GWorld(v)))
+        };
+        let err = || Err(ResolveError::Msg("not found".into()));
+        let report = |res: Vec<Result<_, _>>| -> Report {
+            res.into_iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    (
+                        format!("game{i}"),
+                        [("GWorld".to_string(), r)].into_iter().collect(),
+                    )
+                })
+                .collect()
+        };
+
+        let a = report(vec![err(), err(), ok(1), ok(2), ok(3), ok(4), err()]);
+        let b = report(vec![ok(1), ok(2), err(), ok(2), ok(5), ok(6), err()]);
+
+        let diff = diff_reports(&a, &b);
+        let entry = ResEntry::new(&diff.diffs["GWorld"]);
+        assert_eq!(7, entry.total);
+        assert_eq!(2, entry.newly_ok);
+        assert_eq!(1, entry.newly_failed);
+        assert_eq!(2, entry.ok_diff);
+    }
+
+    #[test]
+    fn test_resume_report() {
+        let dir = std::env::temp_dir().join(format!("patternsleuth-resume-{}", std::process::id()));
+        let game = |name: &str| GameFileEntry {
+            name: name.to_string(),
+            exe_path: PathBuf::from(format!("games/{name}/game.exe")),
+        };
+        let mut games = vec![game("game1"), game("dir/game2"), game("game3")];
+
+        // nothing to resume
+        assert!(resume_report(&dir, &mut games).unwrap().is_empty());
+        assert_eq!(3, games.len());
+
+        let game1 = serde_json::json!({ "GWorld": { "Ok": { "GWorld": 4096 } } });
+        let game2 = serde_json::json!({ "GWorld": { "Err": { "Msg": "not found" } } });
+        write_partial_report(&dir, "game1", &game1).unwrap();
+        write_partial_report(&dir, "dir/game2", &game2).unwrap();
+
+        let partials = resume_report(&dir, &mut games).unwrap();
+        assert_eq!(
+            vec!["game3"],
+            games.iter().map(|g| g.name.as_str()).collect_vec()
+        );
+        assert_eq!(
+            BTreeMap::from([
+                ("dir/game2".to_string(), game2),
+                ("game1".to_string(), game1)
+            ]),
+            partials
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_match_processes() {
+        let processes = [
+            (1, "systemd".to_string()),
+            (100, "FSD-Win64-Shipping.exe".to_string()),
+            (200, "HogwartsLegacy.exe".to_string()),
+            (300, "FSD-Win64-Shipping.exe".to_string()),
+        ];
+
+        assert_eq!(
+            vec![200],
+            match_processes(&processes, "hogwarts*", false).unwrap()
+        );
+        assert_eq!(
+            vec![100, 300],
+            match_processes(&processes, "FSD-*", true).unwrap()
+        );
+        assert!(match_processes(&processes, "FSD-*", false).is_err());
+        assert!(match_processes(&processes, "*.elf", true).is_err());
+    }
+
+    #[test]
+    fn test_symbol_entries() {
+        let symbol = |name: &str| Symbol {
+            name: name.to_string(),
+        };
+        let symbols = HashMap::from([
+            (0x1020, symbol("UWorld::Tick")),
+            (0x1000, symbol("main")),
+            (0x2000, symbol("GWorld")),
+        ]);
+        let ranges = HashMap::from([(0x1000, 0x1000..0x1010), (0x1020, 0x1020..0x1080)]);
+
+        let entries = symbol_entries(&symbols, |address| ranges.get(&address).cloned());
+        assert_eq!(
+            serde_json::json!([
+                { "address": 0x1000, "name": "main", "range_start": 0x1000, "range_end": 0x1010 },
+                { "address": 0x1020, "name": "UWorld::Tick", "range_start": 0x1020, "range_end": 0x1080 },
+                { "address": 0x2000, "name": "GWorld", "range_start": null, "range_end": null },
+            ]),
+            serde_json::to_value(entries).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_par_map_ordered() {
+        let games = (0..64).map(|i| format!("game{i}")).collect_vec();
+        let scan = |game: &String| {
+            // finish out of order
+            std::thread::sleep(std::time::Duration::from_micros(
+                (64 - game[4..].parse::<u64>().unwrap()) * 50,
+            ));
+            format!("scanned {game}")
+        };
+
+        let serial = games.iter().map(scan).collect_vec();
+        assert_eq!(serial, par_map_ordered(&games, 8, scan).unwrap());
+        assert_eq!(serial, par_map_ordered(&games, 1, scan).unwrap());
+    }
+
     #[test]
     fn test_sample_cont() {
         let entries = ["aa", "ba", "ca", "ab", "ac", "bc"]
@@ -1042,4 +2284,112 @@ mod test {
         let ordered = sample_order(entries.clone(), 1);
         assert_eq!(entries, ordered);
     }
+
+    #[test]
+    fn test_sample_order_deterministic() {
+        let entries = (0..50)
+            .map(|i| (format!("{}_{}", i % 7, i * 31 % 50), i))
+            .collect_vec();
+        let ordered = sample_order(entries.clone(), 3);
+        for _ in 0..10 {
+            assert_eq!(ordered, sample_order(entries.clone(), 3));
+        }
+        // independent of input order
+        let reversed = entries.iter().cloned().rev().collect_vec();
+        assert_eq!(ordered, sample_order(reversed, 3));
+
+        assert_ne!(ordered, sample_order(entries.clone(), 1));
+        assert_eq!(
+            entries.len(),
+            sample_order(entries, 1).iter().unique().count()
+        );
+    }
+
+    #[test]
+    fn test_parse_pattern_config() {
+        let patterns = parse_pattern_config(
+            r#"{
+                "GMalloc": ["48 8b 0d | X0x1000", "e8 [ ?? ?? ?? ?? ]"],
+                "FName": ["48 89 5c 24"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["#0 FName", "#0 GMalloc", "#1 GMalloc"],
+            patterns.iter().map(|p| p.name.as_str()).collect_vec()
+        );
+        assert_eq!(Sig("file GMalloc".into()), patterns[2].sig);
+        assert_eq!(
+            Some(&Pattern::new("e8 [ ?? ?? ?? ?? ]").unwrap()),
+            patterns[2].scan.scan_type.get_pattern()
+        );
+
+        let err = parse_pattern_config(r#"{"GMalloc": ["48 8b", "zz"]}"#).unwrap_err();
+        assert!(format!("{err:#}").contains("bad pattern #1 for GMalloc: zz"));
+        assert!(parse_pattern_config("[]").is_err());
+    }
+
+    #[test]
+    fn test_parse_patterns_file() {
+        let patterns = parse_patterns_file(
+            "# GMalloc\n48 8b 0d | X0x1000\n\n   \n  # indented comment\n  e8 [ ?? ?? ?? ?? ]  \n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["line 2", "line 6"],
+            patterns.iter().map(|p| p.name.as_str()).collect_vec()
+        );
+        assert_eq!(
+            Some(&Pattern::new("e8 [ ?? ?? ?? ?? ]").unwrap()),
+            patterns[1].scan.scan_type.get_pattern()
+        );
+        assert!(parse_patterns_file("# nothing\n\n").unwrap().is_empty());
+
+        let err = parse_patterns_file("48 8b\n# ok\n48 zz\n").unwrap_err();
+        assert_eq!("bad pattern on line 3", err.to_string());
+    }
+
+    #[test]
+    fn test_select_resolvers() {
+        let names = |resolvers: Vec<&NamedResolver>| resolvers.iter().map(|r| r.name).collect_vec();
+        let filter = regex::Regex::new("^FName").unwrap();
+
+        let selected = names(select_resolvers(&[], Some(&filter)));
+        assert_eq!(
+            resolvers()
+                .map(|r| r.name)
+                .filter(|n| n.starts_with("FName"))
+                .sorted()
+                .collect_vec(),
+            selected
+        );
+        assert!(selected.contains(&"FNamePool"));
+        assert!(selected.contains(&"FNameToString"));
+        assert!(!selected.contains(&"GMalloc"));
+
+        // explicit resolvers come first and are not duplicated
+        let explicit = resolvers()
+            .filter(|r| r.name == "GMalloc" || r.name == "FNamePool")
+            .sorted_by_key(|r| r.name)
+            .collect_vec();
+        let selected = names(select_resolvers(&explicit, Some(&filter)));
+        assert_eq!(&["FNamePool", "GMalloc"], &selected[..2]);
+        assert_eq!(1, selected.iter().filter(|n| **n == "FNamePool").count());
+
+        assert_eq!(
+            vec!["FNamePool", "GMalloc"],
+            names(select_resolvers(&explicit, None))
+        );
+
+        // duplicated explicit resolvers keep their first position
+        let duplicated = [explicit[1], explicit[0], explicit[1]];
+        assert_eq!(
+            vec!["GMalloc", "FNamePool"],
+            names(select_resolvers(&duplicated, None))
+        );
+        assert_eq!(
+            &["GMalloc", "FNamePool"],
+            &names(select_resolvers(&duplicated, Some(&filter)))[..2]
+        );
+    }
 }
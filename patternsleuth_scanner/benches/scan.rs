@@ -80,6 +80,50 @@ fn gig_multi(c: &mut Criterion) {
     });
 }
 
+fn sections(c: &mut Criterion) {
+    use rand::prelude::*;
+    let mut rng = rand::thread_rng();
+
+    // many small sections where per-call setup dominates
+    let sections = (0..4096)
+        .map(|i| {
+            let data = (0..256).map(|_| rng.gen::<u8>()).collect::<Vec<u8>>();
+            (0x140000000 + i * 0x1000, data)
+        })
+        .collect::<Vec<_>>();
+
+    let patterns = (0..64usize)
+        .map(|i| Pattern::new(format!("48 8D ?? X0x{:x}", 0x144F64F58 + i * 8)).unwrap())
+        .collect::<Vec<_>>();
+    let pattern_refs: Vec<_> = patterns.iter().collect();
+
+    let prepared = PreparedPatterns::new(&pattern_refs);
+    for (base_address, data) in &sections {
+        assert_eq!(
+            scan_pattern(&pattern_refs, *base_address, data),
+            prepared.scan(*base_address, data)
+        );
+    }
+
+    let mut group = c.benchmark_group("sections");
+    group.bench_function("per section", |b| {
+        b.iter(|| {
+            for (base_address, data) in &sections {
+                scan_pattern(&pattern_refs, *base_address, data);
+            }
+        })
+    });
+    group.bench_function("prepared", |b| {
+        b.iter(|| {
+            let prepared = PreparedPatterns::new(&pattern_refs);
+            for (base_address, data) in &sections {
+                prepared.scan(*base_address, data);
+            }
+        })
+    });
+    group.finish();
+}
+
 fn xref(c: &mut Criterion) {
     use object::Object;
     use object::ObjectSection;
@@ -244,5 +288,6 @@ criterion_group! {
     targets = gig_multi
 }
 criterion_group!(bench2, xref);
+criterion_group!(bench4, sections);
 
-criterion_main!(bench1, bench2, bench3);
+criterion_main!(bench1, bench2, bench3, bench4);
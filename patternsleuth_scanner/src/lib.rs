@@ -4,6 +4,9 @@ use anyhow::{bail, Context, Error, Result};
 pub struct PatternSimple {
     pub sig: Vec<u8>,
     pub mask: Vec<u8>,
+    /// (offset, byte) pairs which must *not* match. The corresponding `mask` byte is 0 so they
+    /// are never chosen as anchors when grouping patterns
+    pub negated: Vec<(usize, u8)>,
 }
 impl PatternSimple {
     #[inline(always)]
@@ -13,7 +16,9 @@ impl PatternSimple {
                 return false;
             }
         }
-        true
+        self.negated
+            .iter()
+            .all(|(offset, byte)| data[index + offset] != *byte)
     }
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
@@ -22,6 +27,30 @@ impl PatternSimple {
     pub fn iter(&self) -> std::iter::Zip<std::slice::Iter<u8>, std::slice::Iter<u8>> {
         self.sig.iter().zip(&self.mask)
     }
+    fn get_negated(&self, index: usize) -> Option<u8> {
+        self.negated
+            .iter()
+            .find_map(|(offset, byte)| (*offset == index).then_some(*byte))
+    }
+    fn fmt_byte(&self, f: &mut std::fmt::Formatter<'_>, index: usize) -> std::fmt::Result {
+        if let Some(byte) = self.get_negated(index) {
+            write!(f, "!{:02X}", byte)
+        } else {
+            fmt_byte(f, self.sig[index], self.mask[index])
+        }
+    }
+    /// Slice of pattern starting at `start` with negated offsets adjusted accordingly
+    fn slice_from(&self, start: usize) -> PatternSimple {
+        PatternSimple {
+            sig: self.sig[start..].to_vec(),
+            mask: self.mask[start..].to_vec(),
+            negated: self
+                .negated
+                .iter()
+                .filter_map(|(offset, byte)| Some((offset.checked_sub(start)?, *byte)))
+                .collect(),
+        }
+    }
 }
 
 fn fmt_byte(f: &mut std::fmt::Formatter<'_>, sig: u8, mask: u8) -> std::fmt::Result {
@@ -47,10 +76,10 @@ fn fmt_byte(f: &mut std::fmt::Formatter<'_>, sig: u8, mask: u8) -> std::fmt::Res
 
 impl Display for PatternSimple {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:02X}", self.sig[0])?;
-        for (sig, mask) in self.iter().skip(1) {
+        self.fmt_byte(f, 0)?;
+        for i in 1..self.len() {
             write!(f, " ")?;
-            fmt_byte(f, *sig, *mask)?;
+            self.fmt_byte(f, i)?;
         }
         Ok(())
     }
@@ -66,7 +95,13 @@ pub struct Pattern {
     pub simple: PatternSimple,
     pub custom_offset: usize,
     pub captures: Vec<std::ops::Range<usize>>,
+    /// Names of `captures` declared with `[name ... ]`, `None` for anonymous captures
+    pub capture_names: Vec<Option<String>>,
     pub xrefs: Vec<(usize, Xref)>,
+    /// (offset, xref) pairs for 8-byte relative displacements (`X8 0x...`)
+    pub xrefs_wide: Vec<(usize, XrefWide)>,
+    /// (offset, range) pairs constraining a wildcard byte to an inclusive range of values
+    pub constraints: Vec<(usize, RangeInclusive<u8>)>,
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -132,6 +167,41 @@ impl Pattern {
         }
     }
 
+    fn parse_negated_pattern(s: &str) -> Option<u8> {
+        let s = s.strip_prefix('!')?;
+        (s.len() == 2)
+            .then(|| u8::from_str_radix(s, 16).ok())
+            .flatten()
+    }
+
+    fn parse_range_pattern(s: &str) -> Option<Result<RangeInclusive<u8>>> {
+        let (lo, hi) = s.strip_prefix('[')?.strip_suffix(']')?.split_once('-')?;
+        let parse = |b: &str| {
+            (b.len() == 2)
+                .then(|| u8::from_str_radix(b, 16).ok())
+                .flatten()
+                .with_context(|| format!("failed to parse range bound {b:?} of {s}"))
+        };
+        Some((|| {
+            let (lo, hi) = (parse(lo)?, parse(hi)?);
+            if lo > hi {
+                bail!("range {s} is reversed");
+            }
+            Ok(lo..=hi)
+        })())
+    }
+
+    /// Parse the opening of a named capture, e.g. `[name`
+    fn parse_capture_name(s: &str) -> Option<&str> {
+        let name = s.strip_prefix('[')?;
+        let mut chars = name.chars();
+        (chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .then_some(name)
+    }
+
     fn parse_maybe_hex(s: &str) -> Result<usize> {
         Ok(s.strip_prefix("0x")
             .map(|s| usize::from_str_radix(s, 16))
@@ -147,14 +217,19 @@ impl Pattern {
     pub fn new<S: AsRef<str>>(s: S) -> Result<Self> {
         let mut sig = vec![];
         let mut mask = vec![];
+        let mut negated = vec![];
         let mut custom_offset = 0;
 
         let mut capture_stack = vec![];
         let mut captures = vec![];
+        let mut capture_names = vec![];
         let mut xrefs = vec![];
+        let mut xrefs_wide = vec![];
+        let mut constraints = vec![];
 
         let mut i = 0;
-        for w in s.as_ref().split_whitespace() {
+        let mut words = s.as_ref().split_whitespace();
+        while let Some(w) = words.next() {
             if let Some((s, m)) =
                 Self::parse_hex_pattern(w).or_else(|| Self::parse_binary_patern(w))
             {
@@ -167,17 +242,44 @@ impl Pattern {
                         custom_offset = i;
                     }
                     "[" => {
-                        capture_stack.push(i);
+                        capture_stack.push((i, None));
                     }
                     "]" => {
-                        if let Some(start) = capture_stack.pop() {
+                        if let Some((start, name)) = capture_stack.pop() {
                             captures.push(start..i);
+                            capture_names.push(name);
                         } else {
                             bail!("unexpected closing capture at word {i}");
                         }
                     }
+                    "X8" => {
+                        let target = words.next().context("expected address after X8")?;
+                        let xref = XrefWide(
+                            Self::parse_maybe_hex(target)
+                                .with_context(|| format!("failed to parse xref X8 {target}"))?,
+                        );
+                        xrefs_wide.push((sig.len(), xref));
+                        let width = XrefWidth::Rel64.size();
+                        sig.extend(std::iter::repeat_n(0, width));
+                        mask.extend(std::iter::repeat_n(0, width));
+                        i += width;
+                    }
                     _ => {
-                        if let Some(xref) = w.strip_prefix('X').map(Self::parse_maybe_hex) {
+                        if let Some(name) = Self::parse_capture_name(w) {
+                            capture_stack.push((i, Some(name.to_string())));
+                        } else if w.starts_with('!') {
+                            let byte = Self::parse_negated_pattern(w)
+                                .with_context(|| format!("failed to parse negated byte {w}"))?;
+                            negated.push((sig.len(), byte));
+                            sig.push(0);
+                            mask.push(0);
+                            i += 1;
+                        } else if let Some(range) = Self::parse_range_pattern(w) {
+                            constraints.push((sig.len(), range?));
+                            sig.push(0);
+                            mask.push(0);
+                            i += 1;
+                        } else if let Some(xref) = w.strip_prefix('X').map(Self::parse_maybe_hex) {
                             let xref =
                                 Xref(xref.with_context(|| format!("failed to parse xref {w}"))?);
                             xrefs.push((sig.len(), xref));
@@ -200,7 +302,7 @@ impl Pattern {
                 }
             }
         }
-        if let Some(start) = capture_stack.pop() {
+        if let Some((start, _)) = capture_stack.pop() {
             bail!("unclosed capture at word {start}");
         }
         if sig.is_empty() {
@@ -208,10 +310,13 @@ impl Pattern {
         }
 
         Ok(Self {
-            simple: PatternSimple { sig, mask },
+            simple: PatternSimple { sig, mask, negated },
             custom_offset,
             captures,
+            capture_names,
             xrefs,
+            xrefs_wide,
+            constraints,
         })
     }
     /// Create a pattern from a literal `Vec<u8>` with `mask` filled with 0xff and `custom_offset = 0`.
@@ -220,15 +325,189 @@ impl Pattern {
             simple: PatternSimple {
                 mask: vec![0xff; sig.len()],
                 sig,
+                negated: vec![],
+            },
+            custom_offset: 0,
+            captures: vec![],
+            capture_names: vec![],
+            xrefs: vec![],
+            xrefs_wide: vec![],
+            constraints: vec![],
+        })
+    }
+    /// Pattern matching a 4-byte rip-relative displacement to `target`, e.g. for scanning for
+    /// references to an address only known at runtime. Matches the address of the displacement
+    /// itself. Equivalent to parsing `X0x...` with the address formatted in, see [`scan_xref`] to
+    /// scan for many targets at once.
+    pub fn xref_to(target: u64) -> Self {
+        Self {
+            simple: PatternSimple {
+                sig: vec![0; 4],
+                mask: vec![0; 4],
+                negated: vec![],
+            },
+            custom_offset: 0,
+            captures: vec![],
+            capture_names: vec![],
+            xrefs: vec![(0, Xref(target as usize))],
+            xrefs_wide: vec![],
+            constraints: vec![],
+        }
+    }
+    /// Parse an IDA-style signature such as `48 8B 05 ? ? ? ?` where a lone `?` is a full byte
+    /// wildcard. Only bytes and wildcards are accepted.
+    pub fn from_ida<S: AsRef<str>>(s: S) -> Result<Self> {
+        let mut sig = vec![];
+        let mut mask = vec![];
+        for w in s.as_ref().split_whitespace() {
+            let (s, m) = if w == "?" {
+                (0, 0)
+            } else {
+                Self::parse_hex_pattern(w)
+                    .with_context(|| format!("bad IDA pattern word \"{w}\""))?
+            };
+            sig.push(s);
+            mask.push(m);
+        }
+        if sig.is_empty() {
+            bail!("pattern must match at least one byte");
+        }
+        Ok(Self {
+            simple: PatternSimple {
+                sig,
+                mask,
+                negated: vec![],
             },
             custom_offset: 0,
             captures: vec![],
+            capture_names: vec![],
             xrefs: vec![],
+            xrefs_wide: vec![],
+            constraints: vec![],
         })
     }
+    /// Emit an IDA-style signature with single `?` wildcards. Captures, xrefs and other
+    /// constraints are dropped and bit level masks are emitted as a full wildcard.
+    pub fn to_ida_string(&self) -> String {
+        self.simple
+            .iter()
+            .map(|(sig, mask)| match mask {
+                0xff => format!("{sig:02X}"),
+                0x0f => format!("?{:X}", sig & 0xf),
+                0xf0 => format!("{:X}?", sig >> 4),
+                _ => "?".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    /// Create a pattern from a code-style signature and mask pair as used by CheatEngine and
+    /// other sig scanners, e.g. `b"\x48\x8B\x00"` and `"xx?"`. `x`/`X` match the byte exactly
+    /// and `?`/`.` are wildcards.
+    pub fn from_code_style(sig: &[u8], mask: &str) -> Result<Self> {
+        if sig.len() != mask.len() {
+            bail!(
+                "signature length {} does not match mask length {}",
+                sig.len(),
+                mask.len()
+            );
+        }
+        if sig.is_empty() {
+            bail!("pattern must match at least one byte");
+        }
+        let mask = mask
+            .chars()
+            .map(|c| match c {
+                'x' | 'X' => Ok(0xff),
+                '?' | '.' => Ok(0),
+                _ => bail!("bad mask character {c:?}"),
+            })
+            .collect::<Result<Vec<u8>>>()?;
+        let mut pattern = Self::from_bytes(sig.iter().zip(&mask).map(|(s, m)| s & m).collect())?;
+        pattern.simple.mask = mask;
+        Ok(pattern)
+    }
+    /// Emit a code-style signature and mask pair. Wildcard bytes are emitted as `0x00` and any
+    /// partially masked byte is emitted as a wildcard.
+    pub fn to_code_style(&self) -> (Vec<u8>, String) {
+        self.simple
+            .iter()
+            .map(|(sig, mask)| match mask {
+                0xff => (*sig, 'x'),
+                _ => (0, '?'),
+            })
+            .unzip()
+    }
+    /// Append `other` to this pattern. Captures, xrefs, negated bytes and constraints of `other`
+    /// are shifted by `self.len()`. `self`'s `custom_offset` wins unless it is zero, in which
+    /// case `other`'s (shifted) is used.
+    pub fn concat(&self, other: &Pattern) -> Pattern {
+        let len = self.simple.len();
+        let shift = |offset: &usize| offset + len;
+
+        let mut simple = self.simple.clone();
+        simple.sig.extend(&other.simple.sig);
+        simple.mask.extend(&other.simple.mask);
+        simple.negated.extend(
+            other
+                .simple
+                .negated
+                .iter()
+                .map(|(offset, byte)| (shift(offset), *byte)),
+        );
+
+        let custom_offset = if self.custom_offset == 0 && other.custom_offset != 0 {
+            shift(&other.custom_offset)
+        } else {
+            self.custom_offset
+        };
+
+        Pattern {
+            simple,
+            custom_offset,
+            captures: self
+                .captures
+                .iter()
+                .cloned()
+                .chain(
+                    other
+                        .captures
+                        .iter()
+                        .map(|c| shift(&c.start)..shift(&c.end)),
+                )
+                .collect(),
+            capture_names: self
+                .capture_names
+                .iter()
+                .chain(&other.capture_names)
+                .cloned()
+                .collect(),
+            xrefs: self
+                .xrefs
+                .iter()
+                .cloned()
+                .chain(other.xrefs.iter().map(|(o, x)| (shift(o), *x)))
+                .collect(),
+            xrefs_wide: self
+                .xrefs_wide
+                .iter()
+                .cloned()
+                .chain(other.xrefs_wide.iter().map(|(o, x)| (shift(o), *x)))
+                .collect(),
+            constraints: self
+                .constraints
+                .iter()
+                .cloned()
+                .chain(other.constraints.iter().map(|(o, r)| (shift(o), r.clone())))
+                .collect(),
+        }
+    }
     #[inline(always)]
     pub fn is_match(&self, data: &[u8], base_address: usize, index: usize) -> bool {
         self.simple.is_match(data, index)
+            && self
+                .constraints
+                .iter()
+                .all(|(offset, range)| range.contains(&data[index + offset]))
             && self.xrefs.iter().all(|(offset, xref)| {
                 (base_address + index + offset + 4)
                     .checked_add_signed(i32::from_le_bytes(
@@ -237,6 +516,9 @@ impl Pattern {
                     .map(|x| x == xref.0)
                     .unwrap_or(false)
             })
+            && self.xrefs_wide.iter().all(|(offset, xref)| {
+                XrefWidth::Rel64.resolve(data, base_address, index + offset) == Some(xref.0)
+            })
     }
     pub fn captures<'data>(
         &self,
@@ -254,32 +536,187 @@ impl Pattern {
                 .collect()
         })
     }
+    /// Resolve the 4-byte rip-relative displacement captured by capture number `capture` into
+    /// its absolute target, assuming the displacement ends the instruction (e.g. `lea`, `call`).
+    /// Returns `None` if the pattern does not match at `index` or the capture is not 4 bytes.
+    pub fn capture_rip(
+        &self,
+        data: &[u8],
+        base_address: usize,
+        index: usize,
+        capture: usize,
+    ) -> Option<usize> {
+        let range = self.captures.get(capture)?;
+        if range.len() != XrefWidth::Rel32.size() || !self.is_match(data, base_address, index) {
+            return None;
+        }
+        XrefWidth::Rel32.resolve(data, base_address, index + range.start)
+    }
+    /// Like [`Self::captures`] but returns only named captures keyed by name
+    pub fn captures_named<'data>(
+        &self,
+        data: &'data [u8],
+        base_address: usize,
+        index: usize,
+    ) -> Option<HashMap<String, Capture<'data>>> {
+        self.captures(data, base_address, index).map(|captures| {
+            self.capture_names
+                .iter()
+                .zip(captures)
+                .filter_map(|(name, capture)| Some((name.clone()?, capture)))
+                .collect()
+        })
+    }
+    /// Report the byte the scanner anchors on when this pattern is scanned on its own. Patterns
+    /// anchored on common (banned) bytes scan slowly. When scanned together with other patterns
+    /// a shared byte may be chosen instead.
+    ///
+    /// Returns `None` if the pattern contains no fully masked byte, such patterns are scanned
+    /// unanchored.
+    pub fn anchor_info(&self) -> Option<AnchorInfo> {
+        let options = GroupOptions::default();
+        let pair = group_patterns_with(&[self], &options).swap_remove(0);
+        pair.is_anchored().then(|| {
+            let byte = self.simple.sig[pair.offset];
+            AnchorInfo {
+                byte,
+                position: pair.offset,
+                is_banned: options.banned_bytes.contains(&byte),
+            }
+        })
+    }
+    /// Report likely slow or overly broad patterns. Unlike parse errors these do not prevent the
+    /// pattern from being scanned.
+    pub fn validate(&self) -> Vec<PatternWarning> {
+        let mut warnings = vec![];
+
+        let literals = self.simple.mask.iter().filter(|m| **m == 0xff).count();
+        // xrefs constrain their displacement bytes as much as literals would
+        let constrained = literals + self.xrefs.len() * 4 + self.xrefs_wide.len() * 8;
+        if constrained < MIN_LITERAL_BYTES {
+            warnings.push(PatternWarning::FewLiteralBytes(constrained));
+        }
+
+        let leading = self.simple.mask.iter().take_while(|m| **m == 0).count();
+        if leading > 0 {
+            warnings.push(PatternWarning::LeadingWildcards(leading));
+        }
+
+        let banned = GroupOptions::default().banned_bytes;
+        if !self
+            .simple
+            .iter()
+            .any(|(sig, mask)| *mask == 0xff && !banned.contains(sig))
+        {
+            warnings.push(PatternWarning::NoAnchorableByte);
+        }
+
+        warnings
+    }
+    /// Compare every byte of the pattern against `data` at `index` for diagnosing near-misses.
+    /// Only literal, masked and negated bytes are compared: byte ranges and xrefs are reported as
+    /// wildcards. Bytes past the end of `data` are reported as mismatches.
+    pub fn explain(&self, data: &[u8], index: usize) -> Vec<ByteExplanation> {
+        (0..self.simple.len())
+            .map(|offset| {
+                let actual = data.get(index + offset).copied();
+                let (expected, mask, negated) = match self.simple.get_negated(offset) {
+                    Some(byte) => (byte, 0xff, true),
+                    None => (self.simple.sig[offset], self.simple.mask[offset], false),
+                };
+                ByteExplanation {
+                    offset,
+                    expected,
+                    mask,
+                    negated,
+                    actual,
+                }
+            })
+            .collect()
+    }
     /// compute virtual address from address relative to section as well as account for
     /// custom_offset
     pub fn compute_result(&self, _data: &[u8], base_address: usize, index: usize) -> usize {
         base_address + index + self.custom_offset
     }
+    fn fmt_byte(&self, f: &mut std::fmt::Formatter<'_>, index: usize) -> std::fmt::Result {
+        if let Some((_, range)) = self.constraints.iter().find(|(o, _)| *o == index) {
+            write!(f, "[{:02X}-{:02X}]", range.start(), range.end())
+        } else {
+            self.simple.fmt_byte(f, index)
+        }
+    }
 }
 
 impl Display for Pattern {
+    /// Captures and the custom offset are independent: `|` marks the offset of the match result
+    /// while `[ ]` delimit byte ranges, so `|` may appear inside a capture. At each position
+    /// captures are closed first, then `|` is written, then captures are opened so the output
+    /// always parses back to the same pattern.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:02X}", self.simple.sig[0])?;
-        let mut iter = self.simple.iter().enumerate().skip(1);
-        while let Some((i, (sig, mask))) = iter.next() {
-            if i == self.custom_offset {
-                write!(f, " |")?;
+        fn word(f: &mut std::fmt::Formatter<'_>, first: &mut bool) -> std::fmt::Result {
+            if !std::mem::take(first) {
+                write!(f, " ")?;
             }
-            write!(f, " ")?;
-            if *mask == 0 {
+            Ok(())
+        }
+
+        let mut first = true;
+        let mut skip = 0;
+        for i in 0..=self.simple.len() {
+            for _ in self.captures.iter().filter(|c| c.start < i && c.end == i) {
+                word(f, &mut first)?;
+                write!(f, "]")?;
+            }
+            if i != 0 && i == self.custom_offset {
+                word(f, &mut first)?;
+                write!(f, "|")?;
+            }
+            // outermost first: longest, then the one closed last
+            let mut opening = self
+                .captures
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.start == i)
+                .collect::<Vec<_>>();
+            opening.sort_by_key(|(n, c)| std::cmp::Reverse((c.end, *n)));
+            for (n, capture) in opening {
+                word(f, &mut first)?;
+                match self.capture_names.get(n).and_then(Option::as_deref) {
+                    Some(name) => write!(f, "[{name}")?,
+                    None => write!(f, "[")?,
+                }
+                if capture.is_empty() {
+                    word(f, &mut first)?;
+                    write!(f, "]")?;
+                }
+            }
+
+            if i == self.simple.len() {
+                break;
+            }
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            word(f, &mut first)?;
+            if self.simple.mask[i] == 0 {
                 if let Some((_offset, xref)) =
                     self.xrefs.iter().find(|(offset, _xref)| *offset == i)
                 {
                     write!(f, "X0x{:X}", xref.0)?;
-                    iter.nth(2); // skip 3
+                    skip = 3;
+                    continue;
+                }
+                if let Some((_offset, xref)) =
+                    self.xrefs_wide.iter().find(|(offset, _xref)| *offset == i)
+                {
+                    write!(f, "X8 0x{:X}", xref.0)?;
+                    skip = 7;
                     continue;
                 }
             }
-            fmt_byte(f, *sig, *mask)?;
+            self.fmt_byte(f, i)?;
         }
         Ok(())
     }
@@ -290,50 +727,182 @@ impl std::fmt::Debug for Pattern {
     }
 }
 
+/// Byte chosen as the scan anchor of a pattern, see [`Pattern::anchor_info`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AnchorInfo {
+    pub byte: u8,
+    /// Offset of the anchor byte from the start of the pattern
+    pub position: usize,
+    pub is_banned: bool,
+}
+
+/// Patterns constraining fewer bytes than this are reported by [`Pattern::validate`]
+pub const MIN_LITERAL_BYTES: usize = 3;
+
+/// Potential problem with a pattern reported by [`Pattern::validate`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PatternWarning {
+    /// Only this many bytes are constrained so the pattern likely matches very often
+    FewLiteralBytes(usize),
+    /// Pattern starts with this many wildcards which could be moved before a `|` instead
+    LeadingWildcards(usize),
+    /// Every literal byte is a common byte (or there are none) so scanning has to check a large
+    /// portion of all offsets
+    NoAnchorableByte,
+}
+impl std::fmt::Display for PatternWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FewLiteralBytes(n) => write!(f, "only {n} literal bytes"),
+            Self::LeadingWildcards(n) => write!(f, "{n} leading wildcards"),
+            Self::NoAnchorableByte => write!(f, "no uncommon literal byte to anchor on"),
+        }
+    }
+}
+
+/// Result of comparing a single pattern byte reported by [`Pattern::explain`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ByteExplanation {
+    /// Offset of the byte from the start of the pattern
+    pub offset: usize,
+    pub expected: u8,
+    pub mask: u8,
+    /// `expected` must *not* be present
+    pub negated: bool,
+    /// Byte present in the data or `None` if the pattern extends past the end of the data
+    pub actual: Option<u8>,
+}
+impl ByteExplanation {
+    pub fn is_match(&self) -> bool {
+        self.actual.is_some_and(|actual| {
+            if self.negated {
+                actual != self.expected
+            } else {
+                actual & self.mask == self.expected
+            }
+        })
+    }
+}
+impl std::fmt::Display for ByteExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "byte {} expected ", self.offset)?;
+        if self.negated {
+            write!(f, "!")?;
+        }
+        fmt_byte(f, self.expected, self.mask)?;
+        match self.actual {
+            Some(actual) => write!(f, " got {actual:02X}"),
+            None => write!(f, " got end of data"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Hash, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Xref(pub usize);
 
+/// Xref via an 8-byte relative displacement
+#[derive(Debug, Clone, Copy, Hash, Eq, Ord, PartialEq, PartialOrd)]
+pub struct XrefWide(pub usize);
+
+/// Width of a relative displacement, relative to the end of the displacement itself
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum XrefWidth {
+    Rel32,
+    Rel64,
+}
+
+impl XrefWidth {
+    pub fn size(self) -> usize {
+        match self {
+            XrefWidth::Rel32 => 4,
+            XrefWidth::Rel64 => 8,
+        }
+    }
+    /// Resolve the displacement at `index` into an absolute address. Returns `None` on overflow
+    /// or if the displacement does not fit in `data`.
+    #[inline(always)]
+    fn resolve(self, data: &[u8], base_address: usize, index: usize) -> Option<usize> {
+        let bytes = data.get(index..index + self.size())?;
+        let disp = match self {
+            XrefWidth::Rel32 => i32::from_le_bytes(bytes.try_into().unwrap()) as isize,
+            XrefWidth::Rel64 => i64::from_le_bytes(bytes.try_into().unwrap())
+                .try_into()
+                .ok()?,
+        };
+        (base_address + index + self.size()).checked_add_signed(disp)
+    }
+}
+
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fmt::Display,
+    ops::{Range, RangeInclusive},
 };
 
+/// A pattern paired with the suffix of itself starting at the byte chosen as its scan anchor
 #[derive(Debug, Eq, PartialEq)]
-struct PatternPair<'p> {
-    pattern: &'p Pattern,
-    partial: PatternSimple,
-    offset: usize,
+pub struct PatternPair<'p> {
+    pub pattern: &'p Pattern,
+    pub partial: PatternSimple,
+    pub offset: usize,
 }
 impl PatternPair<'_> {
+    /// Whether `partial` starts with a literal byte that can be searched for. Patterns without
+    /// any literal byte are instead checked at every offset.
+    fn is_anchored(&self) -> bool {
+        self.partial.mask.first() == Some(&0xff)
+    }
     #[inline(always)]
-    fn add_match(
-        &self,
-        data: &[u8],
-        base_address: usize,
-        offset: usize,
-        pattern_index: usize,
-        matches: &mut Vec<(usize, usize)>,
-    ) {
-        if self.partial.is_match(data, offset)
+    fn get_match(&self, data: &[u8], base_address: usize, offset: usize) -> Option<usize> {
+        (self.partial.is_match(data, offset)
             && offset >= self.offset
             && self
                 .pattern
-                .is_match(data, base_address, offset - self.offset)
-        {
-            matches.push((
-                pattern_index,
-                self.pattern
-                    .compute_result(data, base_address, offset - self.offset),
-            ));
+                .is_match(data, base_address, offset - self.offset))
+        .then(|| {
+            self.pattern
+                .compute_result(data, base_address, offset - self.offset)
+        })
+    }
+}
+
+/// Which end of a pattern anchor bytes are preferred from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScanDirection {
+    /// Prefer anchors near the start of the pattern
+    #[default]
+    Forward,
+    /// Prefer anchors near the end of the pattern, e.g. when the only rare byte is the last one
+    Reverse,
+}
+
+/// Tuning for the anchor byte selection done by [`group_patterns_with`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupOptions {
+    /// Common bytes that should be avoided as anchors if possible
+    pub banned_bytes: Vec<u8>,
+    pub direction: ScanDirection,
+}
+impl Default for GroupOptions {
+    fn default() -> Self {
+        Self {
+            banned_bytes: vec![0x00, 0x24, 0x48, 0xff],
+            direction: ScanDirection::Forward,
         }
     }
 }
 
-fn group_patterns<'p>(patterns: &[&'p Pattern]) -> Vec<PatternPair<'p>> {
+pub fn group_patterns<'p>(patterns: &[&'p Pattern]) -> Vec<PatternPair<'p>> {
+    group_patterns_with(patterns, &GroupOptions::default())
+}
+
+pub fn group_patterns_with<'p>(
+    patterns: &[&'p Pattern],
+    options: &GroupOptions,
+) -> Vec<PatternPair<'p>> {
     let mut pattern_pairs: Vec<Option<PatternPair>> = patterns.iter().map(|_| None).collect();
 
-    // common first bytes that should be avoided if possible
-    let bans = [0x00, 0x24, 0x48, 0xff];
+    let bans = &options.banned_bytes;
 
     #[derive(Debug, Default, Eq, PartialEq)]
     struct ByteSelector {
@@ -364,44 +933,62 @@ fn group_patterns<'p>(patterns: &[&'p Pattern]) -> Vec<PatternPair<'p>> {
             .enumerate()
             .filter_map(|(i, p)| p.map(|p| (i, &p.simple)))
         {
-            let unique = p
+            // position of the first (or last if reverse) occurrence of each byte
+            let literals = p
                 .iter()
                 .enumerate()
-                .filter_map(|(i, (sig, mask))| (*mask == 0xff).then_some((*sig, i)))
-                .rev()
-                .collect::<HashMap<u8, usize>>();
+                .filter_map(|(i, (sig, mask))| (*mask == 0xff).then_some((*sig, i)));
+            let unique = match options.direction {
+                ScanDirection::Forward => literals.rev().collect::<HashMap<u8, usize>>(),
+                ScanDirection::Reverse => literals.collect::<HashMap<u8, usize>>(),
+            };
             for (u, i) in unique {
                 let counts = counts.entry(u).or_insert_with(|| ByteSelector {
                     is_banned: bans.contains(&u),
                     ..Default::default()
                 });
-                // TODO potentially score by distance from end instead of distance from start
-                counts.position_score += i;
+                counts.position_score += match options.direction {
+                    ScanDirection::Forward => i,
+                    ScanDirection::Reverse => p.len() - 1 - i,
+                };
                 counts.pattern_indexes.insert(pi);
             }
         }
-        let (
+        let Some((
             max_key,
             ByteSelector {
                 pattern_indexes, ..
             },
-        ) = counts.iter().max_by_key(|a| a.1).unwrap();
+        )) = counts.iter().max_by_key(|a| a.1)
+        else {
+            // remaining patterns have no literal bytes to anchor on so match them as a whole
+            for (i, p) in patterns.iter().enumerate() {
+                if let Some(p) = p {
+                    pattern_pairs[i] = Some(PatternPair {
+                        pattern: p,
+                        partial: p.simple.clone(),
+                        offset: 0,
+                    });
+                }
+            }
+            break;
+        };
         for (i, p) in patterns.iter().enumerate() {
             if let Some(p) = p {
                 if pattern_indexes.contains(&i) {
-                    let pos = p
-                        .simple
-                        .iter()
-                        .enumerate()
-                        .find_map(|(i, (sig, mask))| (sig == max_key && *mask == 0xff).then_some(i))
-                        .unwrap();
+                    let is_anchor = |(i, (sig, mask)): (usize, (&u8, &u8))| {
+                        (sig == max_key && *mask == 0xff).then_some(i)
+                    };
+                    let mut positions = p.simple.iter().enumerate();
+                    let pos = match options.direction {
+                        ScanDirection::Forward => positions.find_map(is_anchor),
+                        ScanDirection::Reverse => positions.rev().find_map(is_anchor),
+                    }
+                    .unwrap();
 
                     pattern_pairs[i] = Some(PatternPair {
                         pattern: p,
-                        partial: PatternSimple {
-                            sig: p.simple.sig[pos..].to_vec(),
-                            mask: p.simple.mask[pos..].to_vec(),
-                        },
+                        partial: p.simple.slice_from(pos),
                         offset: pos,
                     });
                 }
@@ -416,133 +1003,448 @@ fn group_patterns<'p>(patterns: &[&'p Pattern]) -> Vec<PatternPair<'p>> {
 }
 
 pub fn scan_pattern(patterns: &[&Pattern], base_address: usize, data: &[u8]) -> Vec<Vec<usize>> {
-    use rayon::prelude::*;
+    scan_pattern_with(patterns, base_address, data, &GroupOptions::default())
+}
 
-    let mut result_bins = patterns.iter().map(|_| vec![]).collect::<Vec<_>>();
+/// Like [`scan_pattern`] but with custom [`GroupOptions`]
+pub fn scan_pattern_with(
+    patterns: &[&Pattern],
+    base_address: usize,
+    data: &[u8],
+    options: &GroupOptions,
+) -> Vec<Vec<usize>> {
+    PreparedPatterns::new_with(patterns, options).scan(base_address, data)
+}
 
-    if patterns.is_empty() {
-        return result_bins;
-    }
+/// Like [`scan_pattern`] but runs on `pool` rather than rayon's global pool, e.g. to bound the
+/// number of threads used when many scans run concurrently. Other scan functions can be bounded
+/// the same way by calling them from within [`rayon::ThreadPool::install`].
+pub fn scan_pattern_pooled(
+    pool: &rayon::ThreadPool,
+    patterns: &[&Pattern],
+    base_address: usize,
+    data: &[u8],
+) -> Vec<Vec<usize>> {
+    pool.install(|| scan_pattern(patterns, base_address, data))
+}
+
+/// Like [`scan_pattern`] but anchors on bytes near the end of each pattern. Useful for patterns
+/// whose only uncommon bytes are at the end. Returns the same matches as [`scan_pattern`].
+pub fn scan_pattern_rev(
+    patterns: &[&Pattern],
+    base_address: usize,
+    data: &[u8],
+) -> Vec<Vec<usize>> {
+    let options = GroupOptions {
+        direction: ScanDirection::Reverse,
+        ..Default::default()
+    };
+    scan_pattern_with(patterns, base_address, data, &options)
+}
 
-    const WIDE1: usize = 2;
-    const WIDE2: usize = 4;
+/// Like [`scan_pattern`] but invokes `f(pattern_index, address)` for each match as it is found
+/// rather than collecting them. `f` is called concurrently from multiple threads and in no
+/// particular order.
+pub fn scan_pattern_cb<F>(patterns: &[&Pattern], base_address: usize, data: &[u8], f: F)
+where
+    F: Fn(usize, usize) + Sync,
+{
+    scan_pattern_impl(
+        patterns,
+        base_address,
+        data,
+        false,
+        &GroupOptions::default(),
+        f,
+    )
+}
 
-    let pattern_pairs = group_patterns(patterns);
+/// Like [`scan_pattern`] but only returns the number of matches of each pattern. Avoids
+/// collecting addresses when only counts are needed.
+pub fn scan_pattern_counts(patterns: &[&Pattern], base_address: usize, data: &[u8]) -> Vec<usize> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let counts = patterns
+        .iter()
+        .map(|_| AtomicUsize::new(0))
+        .collect::<Vec<_>>();
+    let options = GroupOptions::default();
+    scan_pattern_impl(patterns, base_address, data, false, &options, |pi, _| {
+        counts[pi].fetch_add(1, Ordering::Relaxed);
+    });
+    counts.into_iter().map(AtomicUsize::into_inner).collect()
+}
 
-    let mut all_bins = HashSet::new();
-    let mut short_bins: HashMap<u8, Vec<_>> = Default::default();
-    let mut wide1_bins: HashMap<[u8; WIDE1], Vec<_>> = Default::default();
-    let mut wide2_bins: HashMap<[u8; WIDE2], Vec<_>> = Default::default();
-    for (pi, pair) in pattern_pairs.iter().enumerate() {
-        let p = &pair.partial;
+/// Like [`scan_pattern`] but only returns the lowest address matched by each pattern. Each
+/// pattern stops being checked in a chunk once it has matched.
+pub fn scan_pattern_first(
+    patterns: &[&Pattern],
+    base_address: usize,
+    data: &[u8],
+) -> Vec<Option<usize>> {
+    PreparedPatterns::new(patterns).scan_first(base_address, data)
+}
 
-        all_bins.insert(p.sig[0]);
-        if p.mask.iter().take(WIDE2).filter(|m| **m == 0xff).count() == WIDE2 {
-            let mut buf = [0; WIDE2];
-            buf.copy_from_slice(&p.sig[0..WIDE2]);
-            wide2_bins.entry(buf).or_default().push((pi, pair));
-        } else if p.mask.iter().take(WIDE1).filter(|m| **m == 0xff).count() == WIDE1 {
-            let mut buf = [0; WIDE1];
-            buf.copy_from_slice(&p.sig[0..WIDE1]);
-            wide1_bins.entry(buf).or_default().push((pi, pair));
-        } else {
-            short_bins.entry(p.sig[0]).or_default().push((pi, pair));
-        }
+fn scan_pattern_impl<F>(
+    patterns: &[&Pattern],
+    base_address: usize,
+    data: &[u8],
+    first_only: bool,
+    options: &GroupOptions,
+    f: F,
+) where
+    F: Fn(usize, usize) + Sync,
+{
+    PreparedPatterns::new_with(patterns, options).scan_cb(base_address, data, first_only, f)
+}
+
+const WIDE1: usize = 2;
+const WIDE2: usize = 4;
+
+/// Patterns grouped by anchor byte and sorted into lookup bins once so they can be scanned over
+/// many sections without repeating the setup [`scan_pattern`] does on every call. Results are
+/// identical to calling [`scan_pattern`] or [`scan_pattern_first`] on each section.
+pub struct PreparedPatterns<'p> {
+    pattern_pairs: Vec<PatternPair<'p>>,
+    /// Distinct first bytes of every anchored pattern, searched for with memchr
+    all_bins: Vec<u8>,
+    /// Indexes into `pattern_pairs` keyed by the leading literal bytes of their anchored suffix
+    short_bins: HashMap<u8, Vec<usize>>,
+    wide1_bins: HashMap<[u8; WIDE1], Vec<usize>>,
+    wide2_bins: HashMap<[u8; WIDE2], Vec<usize>>,
+    /// Patterns without a literal byte which are checked at every offset
+    unanchored: Vec<usize>,
+    /// Length of the longest pattern
+    max: usize,
+}
+impl<'p> PreparedPatterns<'p> {
+    pub fn new(patterns: &[&'p Pattern]) -> Self {
+        Self::new_with(patterns, &GroupOptions::default())
     }
-    let all_bins = Vec::from_iter(all_bins);
+    pub fn new_with(patterns: &[&'p Pattern], options: &GroupOptions) -> Self {
+        let pattern_pairs = group_patterns_with(patterns, options);
+
+        let mut all_bins = HashSet::new();
+        let mut short_bins: HashMap<u8, Vec<_>> = Default::default();
+        let mut wide1_bins: HashMap<[u8; WIDE1], Vec<_>> = Default::default();
+        let mut wide2_bins: HashMap<[u8; WIDE2], Vec<_>> = Default::default();
+        let mut unanchored = vec![];
+        for (pi, pair) in pattern_pairs.iter().enumerate() {
+            let p = &pair.partial;
+
+            if !pair.is_anchored() {
+                unanchored.push(pi);
+                continue;
+            }
+            all_bins.insert(p.sig[0]);
+            if p.mask.iter().take(WIDE2).filter(|m| **m == 0xff).count() == WIDE2 {
+                let mut buf = [0; WIDE2];
+                buf.copy_from_slice(&p.sig[0..WIDE2]);
+                wide2_bins.entry(buf).or_default().push(pi);
+            } else if p.mask.iter().take(WIDE1).filter(|m| **m == 0xff).count() == WIDE1 {
+                let mut buf = [0; WIDE1];
+                buf.copy_from_slice(&p.sig[0..WIDE1]);
+                wide1_bins.entry(buf).or_default().push(pi);
+            } else {
+                short_bins.entry(p.sig[0]).or_default().push(pi);
+            }
+        }
+
+        let max = patterns.iter().map(|p| p.simple.len()).max().unwrap_or(0);
 
-    let max = patterns.iter().map(|p| p.simple.len()).max().unwrap();
+        Self {
+            pattern_pairs,
+            all_bins: Vec::from_iter(all_bins),
+            short_bins,
+            wide1_bins,
+            wide2_bins,
+            unanchored,
+            max,
+        }
+    }
+    /// Like [`scan_pattern`] using the prepared patterns
+    pub fn scan(&self, base_address: usize, data: &[u8]) -> Vec<Vec<usize>> {
+        let mut result_bins = self.scan_impl(
+            base_address,
+            data,
+            false,
+            || vec![vec![]; self.pattern_pairs.len()],
+            |bins: &mut Vec<Vec<usize>>, pi, addr| bins[pi].push(addr),
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    a.extend(b);
+                }
+                a
+            },
+        );
+        for bin in &mut result_bins {
+            bin.sort_unstable();
+        }
+        result_bins
+    }
+    /// Like [`scan_pattern_first`] using the prepared patterns
+    pub fn scan_first(&self, base_address: usize, data: &[u8]) -> Vec<Option<usize>> {
+        fn min(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+            a.into_iter().chain(b).min()
+        }
+        self.scan_impl(
+            base_address,
+            data,
+            true,
+            || vec![None; self.pattern_pairs.len()],
+            |first: &mut Vec<Option<usize>>, pi, addr| first[pi] = min(first[pi], Some(addr)),
+            |a, b| a.into_iter().zip(b).map(|(a, b)| min(a, b)).collect(),
+        )
+    }
+    /// Calls [`Self::scan_impl`] with a callback invoked for every match
+    fn scan_cb<F>(&self, base_address: usize, data: &[u8], first_only: bool, f: F)
+    where
+        F: Fn(usize, usize) + Sync,
+    {
+        self.scan_impl(
+            base_address,
+            data,
+            first_only,
+            || (),
+            |_, pi, addr| f(pi, addr),
+            |_, _| (),
+        )
+    }
+    /// Scans `data` accumulating matches into a value created by `init` for each chunk. Chunk
+    /// results are combined with `merge` so no state is shared between threads while scanning.
+    fn scan_impl<A, I, F, M>(
+        &self,
+        base_address: usize,
+        data: &[u8],
+        first_only: bool,
+        init: I,
+        f: F,
+        merge: M,
+    ) -> A
+    where
+        A: Send,
+        I: Fn() -> A + Sync + Send,
+        F: Fn(&mut A, usize, usize) + Sync,
+        M: Fn(A, A) -> A + Sync + Send,
+    {
+        use rayon::prelude::*;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let pattern_pairs = &self.pattern_pairs;
+        if pattern_pairs.is_empty() {
+            return init();
+        }
 
-    // cut middle short such that even the longest pattern doesn't have to bounds check
-    let middle = &data[0..data.len().saturating_sub(max)];
+        // cut middle short such that even the longest pattern doesn't have to bounds check
+        let middle = &data[0..data.len().saturating_sub(self.max)];
 
-    let mut matches = vec![];
+        // whether each pattern matched anywhere in the middle
+        let found_middle = pattern_pairs
+            .iter()
+            .map(|_| AtomicBool::new(false))
+            .collect::<Vec<_>>();
 
-    // middle
-    let chunk_size = (middle.len()
-        / std::thread::available_parallelism().unwrap_or(std::num::NonZeroUsize::new(1).unwrap()))
-    .max(1);
-    let chunks: Vec<_> = middle.chunks(chunk_size).enumerate().collect();
-    matches.append(
-        &mut chunks
+        // middle
+        let chunk_size = (middle.len() / rayon::current_num_threads()).max(1);
+        let chunks: Vec<_> = middle.chunks(chunk_size).enumerate().collect();
+        let mut acc = chunks
             .par_iter()
             .map(|(index, chunk)| {
-                let mut matches = vec![];
+                let mut acc = init();
                 let offset = index * chunk_size;
 
-                for first in &all_bins {
+                // memchr yields increasing offsets and each pattern belongs to a single bin so
+                // the first match of a pattern in a chunk is also its lowest in the chunk
+                let mut found = vec![false; pattern_pairs.len()];
+                let mut add_match = |pi: usize, j: usize| {
+                    if first_only && found[pi] {
+                        return;
+                    }
+                    if let Some(addr) = pattern_pairs[pi].get_match(data, base_address, j) {
+                        found[pi] = true;
+                        f(&mut acc, pi, addr);
+                    }
+                };
+
+                for first in &self.all_bins {
                     for i in memchr::memchr_iter(*first, chunk) {
                         let j = offset + i;
-                        if let Some(patterns) = short_bins.get(first) {
-                            for (pi, p) in patterns.iter() {
-                                p.add_match(data, base_address, j, *pi, &mut matches)
+                        if let Some(patterns) = self.short_bins.get(first) {
+                            for pi in patterns {
+                                add_match(*pi, j)
                             }
                         }
-                        if !wide2_bins.is_empty() {
+                        if !self.wide2_bins.is_empty() {
                             let mut buf = [0; WIDE2];
                             buf.copy_from_slice(&data[j..j + WIDE2]);
-                            if let Some(patterns) = wide2_bins.get(&buf) {
-                                for (pi, p) in patterns.iter() {
-                                    p.add_match(data, base_address, j, *pi, &mut matches)
+                            if let Some(patterns) = self.wide2_bins.get(&buf) {
+                                for pi in patterns {
+                                    add_match(*pi, j)
                                 }
                             }
                         }
-                        if !wide1_bins.is_empty() {
+                        if !self.wide1_bins.is_empty() {
                             let mut buf = [0; WIDE1];
                             buf.copy_from_slice(&data[j..j + WIDE1]);
-                            if let Some(patterns) = wide1_bins.get(&buf) {
-                                for (pi, p) in patterns.iter() {
-                                    p.add_match(data, base_address, j, *pi, &mut matches)
+                            if let Some(patterns) = self.wide1_bins.get(&buf) {
+                                for pi in patterns {
+                                    add_match(*pi, j)
                                 }
                             }
                         }
                     }
                 }
-                matches
-            })
-            .flatten()
-            .collect(),
-    );
 
-    // suffix
-    let start = middle.len();
-    for (pi, p) in pattern_pairs.iter().enumerate() {
-        for i in (start.saturating_sub(p.offset))
-            ..start + (data.len() - middle.len()).saturating_sub(p.pattern.simple.len() - 1)
-        {
-            if p.pattern.is_match(data, base_address, i) {
-                matches.push((pi, base_address + i));
+                if !self.unanchored.is_empty() {
+                    for i in 0..chunk.len() {
+                        for pi in &self.unanchored {
+                            add_match(*pi, offset + i)
+                        }
+                    }
+                }
+
+                for (pi, found) in found.into_iter().enumerate() {
+                    if found {
+                        found_middle[pi].store(true, Ordering::Relaxed);
+                    }
+                }
+                acc
+            })
+            .reduce(&init, &merge);
+
+        // suffix
+        let start = middle.len();
+        for (pi, p) in pattern_pairs.iter().enumerate() {
+            if first_only && found_middle[pi].load(Ordering::Relaxed) {
+                // any suffix match would be at a higher address
+                continue;
+            }
+            let Some(last) = data.len().checked_sub(p.pattern.simple.len()) else {
+                // pattern is longer than data so can never match
+                continue;
+            };
+            for i in start.saturating_sub(p.offset)..=last {
+                if p.pattern.is_match(data, base_address, i) {
+                    f(
+                        &mut acc,
+                        pi,
+                        p.pattern.compute_result(data, base_address, i),
+                    );
+                    if first_only {
+                        break;
+                    }
+                }
             }
         }
+        acc
     }
+}
 
-    for (pi, addr) in matches {
-        result_bins[pi].push(addr);
+/// Like [`scan_pattern`] but only returns matches starting within the address `range`. Bytes
+/// past the end of `range` are still read so patterns straddling the end of `range` match.
+pub fn scan_pattern_in(
+    patterns: &[&Pattern],
+    base_address: usize,
+    data: &[u8],
+    range: Range<usize>,
+) -> Vec<Vec<usize>> {
+    let start = range.start.clamp(base_address, base_address + data.len()) - base_address;
+    let end = range.end.clamp(base_address, base_address + data.len()) - base_address;
+    if start >= end {
+        return patterns.iter().map(|_| vec![]).collect();
     }
 
-    result_bins
+    let max = patterns.iter().map(|p| p.simple.len()).max().unwrap_or(0);
+    let slice = &data[start..(end + max.saturating_sub(1)).min(data.len())];
+
+    let mut results = scan_pattern(patterns, base_address + start, slice);
+    for (res, pattern) in results.iter_mut().zip(patterns) {
+        res.retain(|addr| *addr - pattern.custom_offset < base_address + end);
+    }
+    results
 }
 
 pub fn scan_xref(patterns: &[&Xref], base_address: usize, data: &[u8]) -> Vec<Vec<usize>> {
+    let targets = patterns.iter().map(|p| p.0).collect::<Vec<_>>();
+    scan_xref_width(XrefWidth::Rel32, &targets, base_address, data)
+}
+
+/// Like [`scan_xref`] but runs on `pool`, see [`scan_pattern_pooled`]
+pub fn scan_xref_pooled(
+    pool: &rayon::ThreadPool,
+    patterns: &[&Xref],
+    base_address: usize,
+    data: &[u8],
+) -> Vec<Vec<usize>> {
+    pool.install(|| scan_xref(patterns, base_address, data))
+}
+
+pub fn scan_xref_wide(patterns: &[&XrefWide], base_address: usize, data: &[u8]) -> Vec<Vec<usize>> {
+    let targets = patterns.iter().map(|p| p.0).collect::<Vec<_>>();
+    scan_xref_width(XrefWidth::Rel64, &targets, base_address, data)
+}
+
+fn scan_xref_width(
+    width: XrefWidth,
+    targets: &[usize],
+    base_address: usize,
+    data: &[u8],
+) -> Vec<Vec<usize>> {
+    scan_decoded(targets, width.size(), base_address, data, |j| {
+        width.resolve(data, base_address, j)
+    })
+}
+
+/// Scan for relative `call`/`jmp` (`e8`/`e9` rel32) instructions to any of `targets`. Results are
+/// the addresses of the call sites (the opcode byte).
+pub fn scan_relative_calls(targets: &[usize], base_address: usize, data: &[u8]) -> Vec<Vec<usize>> {
+    scan_decoded(targets, 5, base_address, data, |j| {
+        matches!(data[j], 0xe8 | 0xe9)
+            .then(|| XrefWidth::Rel32.resolve(data, base_address, j + 1))
+            .flatten()
+    })
+}
+
+/// Scan for absolute 8-byte little-endian pointers to any of `targets`
+pub fn scan_absolute(targets: &[u64], base_address: usize, data: &[u8]) -> Vec<Vec<usize>> {
+    scan_decoded(targets, 8, base_address, data, |j| {
+        Some(u64::from_le_bytes(data[j..j + 8].try_into().unwrap()))
+    })
+}
+
+/// Decode a value from every `width` byte window of `data` and bin the window addresses by which
+/// of `targets` the value equals. Each window is decoded once regardless of the number of targets.
+fn scan_decoded<T, F>(
+    targets: &[T],
+    width: usize,
+    base_address: usize,
+    data: &[u8],
+    decode: F,
+) -> Vec<Vec<usize>>
+where
+    T: Copy + Ord + Send + Sync,
+    F: Fn(usize) -> Option<T> + Sync,
+{
     use rayon::prelude::*;
 
-    let mut bins = patterns.iter().map(|_| vec![]).collect::<Vec<_>>();
+    let mut bins = targets.iter().map(|_| vec![]).collect::<Vec<_>>();
 
-    if patterns.is_empty() {
+    if targets.is_empty() {
         return bins;
     }
 
-    let mut patterns = patterns.to_vec();
+    // (target, original index) sorted by target so bins line up with the input order
+    let mut patterns = targets
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (*t, i))
+        .collect::<Vec<_>>();
     patterns.sort();
 
     let mut matches = vec![];
 
-    let width = 4;
-
     let first_byte_data = &data[0..data.len().saturating_sub(width - 1)];
-    let chunk_size = (first_byte_data.len()
-        / std::thread::available_parallelism().unwrap_or(std::num::NonZeroUsize::new(1).unwrap()))
-    .max(1);
+    let chunk_size = (first_byte_data.len() / rayon::current_num_threads()).max(1);
 
     let chunks: Vec<_> = first_byte_data.chunks(chunk_size).enumerate().collect();
     matches.append(
@@ -553,12 +1455,8 @@ pub fn scan_xref(patterns: &[&Xref], base_address: usize, data: &[u8]) -> Vec<Ve
                 let offset = chunk_index * chunk_size;
 
                 for j in offset..offset + chunk.len() {
-                    if let Some(address) = (base_address + width + j).checked_add_signed(
-                        i32::from_le_bytes(data[j..j + width].try_into().unwrap())
-                            .try_into()
-                            .unwrap(),
-                    ) {
-                        if let Ok(i) = patterns.binary_search_by_key(&address, |p| p.0) {
+                    if let Some(value) = decode(j) {
+                        if let Ok(i) = patterns.binary_search_by_key(&value, |p| p.0) {
                             // match found
                             let addr = base_address + j;
                             {
@@ -567,10 +1465,10 @@ pub fn scan_xref(patterns: &[&Xref], base_address: usize, data: &[u8]) -> Vec<Ve
                                 while let Some(prev) =
                                     (i > 0).then(|| patterns.get(i - 1)).flatten()
                                 {
-                                    if prev.0 != address {
+                                    if prev.0 != value {
                                         break;
                                     }
-                                    matches.push((i - 1, addr));
+                                    matches.push((prev.1, addr));
                                     i -= 1;
                                 }
                             }
@@ -578,10 +1476,10 @@ pub fn scan_xref(patterns: &[&Xref], base_address: usize, data: &[u8]) -> Vec<Ve
                                 // walk forwards until unequal
                                 let mut i = i;
                                 while let Some(next) = patterns.get(i) {
-                                    if next.0 != address {
+                                    if next.0 != value {
                                         break;
                                     }
-                                    matches.push((i, addr));
+                                    matches.push((next.1, addr));
                                     i += 1;
                                 }
                             }
@@ -640,10 +1538,14 @@ mod test {
                 simple: PatternSimple {
                     sig: vec![0, 0],
                     mask: vec![0xff, 0],
+                    negated: vec![],
                 },
                 custom_offset: 0,
                 captures: vec![],
+                capture_names: vec![],
                 xrefs: vec![],
+                xrefs_wide: vec![],
+                constraints: vec![],
             },
             Pattern::new("00 ??").unwrap()
         );
@@ -652,10 +1554,14 @@ mod test {
                 simple: PatternSimple {
                     sig: vec![0x10, 0],
                     mask: vec![0xff, 0],
+                    negated: vec![],
                 },
                 custom_offset: 0,
                 captures: vec![],
+                capture_names: vec![],
                 xrefs: vec![],
+                xrefs_wide: vec![],
+                constraints: vec![],
             },
             Pattern::new("10 ??").unwrap()
         );
@@ -664,17 +1570,144 @@ mod test {
                 simple: PatternSimple {
                     sig: vec![0x10, 0, 0b01010011],
                     mask: vec![0xff, 0, 0b11011011],
+                    negated: vec![],
                 },
                 custom_offset: 0,
                 captures: vec![],
+                capture_names: vec![],
                 xrefs: vec![],
+                xrefs_wide: vec![],
+                constraints: vec![],
             },
             Pattern::new("10 ?? 01?10?11").unwrap()
         );
+        assert_eq!(
+            Pattern {
+                simple: PatternSimple {
+                    sig: vec![0x10, 0, 0x20],
+                    mask: vec![0xff, 0, 0xff],
+                    negated: vec![(1, 0xc3)],
+                },
+                custom_offset: 0,
+                captures: vec![],
+                capture_names: vec![],
+                xrefs: vec![],
+                xrefs_wide: vec![],
+                constraints: vec![],
+            },
+            Pattern::new("10 !C3 20").unwrap()
+        );
+        assert!(Pattern::new("10 !C").is_err());
+        assert!(Pattern::new("10 !C?").is_err());
+        assert!(Pattern::new("10 !C30").is_err());
+    }
+
+    #[test]
+    fn test_range_pattern() {
+        assert_eq!(
+            Pattern {
+                simple: PatternSimple {
+                    sig: vec![0x48, 0x83, 0xec, 0],
+                    mask: vec![0xff, 0xff, 0xff, 0],
+                    negated: vec![],
+                },
+                custom_offset: 0,
+                captures: vec![],
+                capture_names: vec![],
+                xrefs: vec![],
+                xrefs_wide: vec![],
+                constraints: vec![(3, 0x10..=0x20)],
+            },
+            Pattern::new("48 83 ec [10-20]").unwrap()
+        );
+        assert_eq!(
+            vec![(0, 0x10..=0x10)],
+            Pattern::new("[10-10] 20").unwrap().constraints
+        );
+        assert!(Pattern::new("48 [20-10]").is_err());
+        assert!(Pattern::new("48 [1-20]").is_err());
+        assert!(Pattern::new("48 [10-2?]").is_err());
+
+        assert_eq!(
+            Pattern::new("[10-20] 48 | [30-30]").unwrap().to_string(),
+            "[10-20] 48 | [30-30]"
+        );
+
+        let pattern = Pattern::new("48 [10-20]").unwrap();
+        assert!(!pattern.is_match(b"\x48\x0f", 0, 0));
+        assert!(pattern.is_match(b"\x48\x10", 0, 0));
+        assert!(pattern.is_match(b"\x48\x20", 0, 0));
+        assert!(!pattern.is_match(b"\x48\x21", 0, 0));
+
+        // range constrained bytes are never anchors
+        assert_eq!(
+            group_patterns(&[&Pattern::new("[10-20] 34").unwrap()]),
+            vec![PatternPair {
+                pattern: &Pattern::new("[10-20] 34").unwrap(),
+                partial: Pattern::new("34").unwrap().simple,
+                offset: 1,
+            },]
+        );
+    }
+
+    #[test]
+    fn test_negated_match() {
+        let pattern = Pattern::new("10 !C3 20").unwrap();
+        assert!(pattern.is_match(b"\x10\x00\x20", 0, 0));
+        assert!(pattern.is_match(b"\x10\xc2\x20", 0, 0));
+        assert!(!pattern.is_match(b"\x10\xc3\x20", 0, 0));
+
+        assert_eq!(
+            vec![vec![100, 106]],
+            scan_pattern(&[&pattern], 100, b"\x10\x00\x20\x10\xc3\x20\x10\xff\x20")
+        );
+    }
+
+    #[test]
+    fn test_display_captures() {
+        for p in [
+            "10 | [ 20 ] 30",
+            "[ 10 | 20 ]",
+            "[ 10 20 ] | 30",
+            "10 | [ 20 30 ]",
+            "[outer 10 [inner ?? ] [ 30 ] ] [_last_1 ?? ]",
+            "[a [b 10 ] ]",
+            "10 [ ] 20",
+            "[ X0x10 ] | 20",
+        ] {
+            let pattern = Pattern::new(p).unwrap();
+            assert_eq!(p, pattern.to_string());
+            assert_eq!(pattern, Pattern::new(pattern.to_string()).unwrap());
+        }
+
+        let pattern = Pattern::new("[ 10 | 20 ]").unwrap();
+        assert_eq!(1, pattern.custom_offset);
+        assert_eq!(vec![0..2], pattern.captures);
+        assert_eq!(
+            Some(vec![Capture {
+                address: 100,
+                data: &[0x10, 0x20]
+            }]),
+            pattern.captures(b"\x10\x20", 100, 0)
+        );
+
+        // `|` directly before or after a bracket is equivalent
+        assert_eq!(
+            Pattern::new("10 [ | 20 ] 30").unwrap(),
+            Pattern::new("10 | [ 20 ] 30").unwrap()
+        );
     }
 
     #[test]
     fn test_display_pattern() {
+        // leading wildcards round-trip
+        for p in ["?? ?? 0F 31", "?? 48 8B", "?0 | 48"] {
+            let pattern = Pattern::new(p).unwrap();
+            assert_eq!(p, pattern.to_string());
+            assert_eq!(p.replace("| ", ""), pattern.simple.to_string());
+            assert_eq!(pattern, Pattern::new(pattern.to_string()).unwrap());
+        }
+
         assert_eq!(
             Pattern::new("12 34 | 56").unwrap().to_string(),
             "12 34 | 56"
@@ -698,6 +1731,14 @@ mod test {
             Pattern::new("12 X0x34 56").unwrap().simple.to_string(),
             "12 ?? ?? ?? ?? 56"
         );
+        assert_eq!(
+            Pattern::new("12 X8 0x34 56").unwrap().to_string(),
+            "12 X8 0x34 56"
+        );
+        assert_eq!(
+            Pattern::new("12 X8 0x34 56").unwrap().simple.to_string(),
+            "12 ?? ?? ?? ?? ?? ?? ?? ?? 56"
+        );
 
         assert_eq!(
             Pattern::new("12 ?3 45").unwrap().simple.to_string(),
@@ -715,6 +1756,15 @@ mod test {
             Pattern::new("12 ??100??1 45").unwrap().simple.to_string(),
             "12 ??100??1 45"
         );
+
+        assert_eq!(
+            Pattern::new("!12 | !C3 45").unwrap().to_string(),
+            "!12 | !C3 45"
+        );
+        assert_eq!(
+            Pattern::new("!12 | !C3 45").unwrap().simple.to_string(),
+            "!12 !C3 45"
+        );
     }
 
     #[test]
@@ -727,10 +1777,14 @@ mod test {
                 simple: PatternSimple {
                     sig: vec![0, 0, 0x10, 0x20],
                     mask: vec![0xff, 0, 0xff, 0xff],
+                    negated: vec![],
                 },
                 custom_offset: 0,
                 captures: vec![2..2, 1..2, 2..4],
+                capture_names: vec![None, None, None],
                 xrefs: vec![],
+                xrefs_wide: vec![],
+                constraints: vec![],
             },
             Pattern::new("00 [ ?? [ ] ] [ 10 20 ]").unwrap()
         );
@@ -754,6 +1808,286 @@ mod test {
                 .unwrap()
                 .captures(b"\x10\x20\x30\x99\x24", 100, 1)
         );
+
+        let pattern = Pattern::new("[outer 10 [inner ?? ] [ 30 ] ] [_last_1 ?? ]").unwrap();
+        assert_eq!(vec![1..2, 2..3, 0..3, 3..4], pattern.captures);
+        assert_eq!(
+            vec![
+                Some("inner".to_string()),
+                None,
+                Some("outer".to_string()),
+                Some("_last_1".to_string())
+            ],
+            pattern.capture_names
+        );
+        assert!(Pattern::new("[name ??").is_err());
+        assert!(Pattern::new("[1name ?? ]").is_err());
+
+        let data = b"\x10\x20\x30\x99";
+        assert_eq!(
+            Some(Capture {
+                address: 101,
+                data: &[0x20]
+            }),
+            pattern
+                .captures(data, 100, 0)
+                .map(|c| c.into_iter().next().unwrap())
+        );
+        let named = pattern.captures_named(data, 100, 0).unwrap();
+        assert_eq!(3, named.len());
+        assert_eq!(
+            &Capture {
+                address: 100,
+                data: &[0x10, 0x20, 0x30]
+            },
+            &named["outer"]
+        );
+        assert_eq!(&[0x20], named["inner"].data);
+        assert_eq!(&[0x99], named["_last_1"].data);
+        assert_eq!(None, pattern.captures_named(b"\x11\x20\x30\x99", 100, 0));
+    }
+
+    #[test]
+    fn test_concat() {
+        let prefix = Pattern::new("48 8b [ ?? ] !cc").unwrap();
+        let tail = Pattern::new("e8 | X0x1000 [name [10-20] ]").unwrap();
+        let pattern = prefix.concat(&tail);
+
+        assert_eq!(
+            Pattern::new("48 8b [ ?? ] !cc e8 | X0x1000 [name [10-20] ]").unwrap(),
+            pattern
+        );
+        assert_eq!(10, pattern.simple.len());
+        assert_eq!(vec![2..3, 9..10], pattern.captures);
+        assert_eq!(vec![None, Some("name".to_string())], pattern.capture_names);
+        assert_eq!(vec![(5, Xref(0x1000))], pattern.xrefs);
+        assert_eq!(vec![(3, 0xcc)], pattern.simple.negated);
+        assert_eq!(vec![(9, 0x10..=0x20)], pattern.constraints);
+        assert_eq!(5, pattern.custom_offset);
+
+        // custom_offset of self wins unless zero
+        let pattern = Pattern::new("aa | bb").unwrap().concat(&tail);
+        assert_eq!(1, pattern.custom_offset);
+        let pattern = Pattern::new("aa bb").unwrap().concat(&prefix);
+        assert_eq!(0, pattern.custom_offset);
+
+        let pattern = prefix.concat(&tail);
+        let data =
+            b"\x48\x8b\x99\x00\xe8\xf7\xff\xff\xff\x15\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(pattern.is_match(data, 0x1000, 0));
+        assert!(!pattern.is_match(data, 0x1001, 0));
+        assert_eq!(vec![vec![0x1005]], scan_pattern(&[&pattern], 0x1000, data));
+    }
+
+    #[test]
+    fn test_xref_to() {
+        // lea rax, [rip + 0x20] / lea rcx, [rip - 0x10]
+        let data = [
+            0x90, 0x48, 0x8d, 0x05, 0x20, 0x00, 0x00, 0x00, 0x48, 0x8d, 0x0d, 0xf0, 0xff, 0xff,
+            0xff,
+        ];
+
+        let pattern = Pattern::xref_to(0x1028);
+        assert_eq!(Pattern::new("X0x1028").unwrap(), pattern);
+        assert!(pattern.is_match(&data, 0x1000, 4));
+        assert!(!pattern.is_match(&data, 0x1000, 11));
+        assert_eq!(vec![vec![0x1004]], scan_pattern(&[&pattern], 0x1000, &data));
+
+        // negative displacement
+        let pattern = Pattern::xref_to(0xfff);
+        assert_eq!(vec![vec![0x100b]], scan_pattern(&[&pattern], 0x1000, &data));
+
+        let pattern = Pattern::xref_to(0x1030);
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan_pattern(&[&pattern], 0x1000, &data)
+        );
+    }
+
+    #[test]
+    fn test_capture_rip() {
+        // lea rax, [rip + 0x20] / lea rcx, [rip - 0x10]
+        let data = [
+            0x90, 0x48, 0x8d, 0x05, 0x20, 0x00, 0x00, 0x00, 0x48, 0x8d, 0x0d, 0xf0, 0xff, 0xff,
+            0xff,
+        ];
+        let pattern =
+            Pattern::new("48 8d 05 [ ?? ?? ?? ?? ] 48 8d 0d [ ?? ?? ] [ ?? ?? ]").unwrap();
+
+        assert_eq!(
+            Some(0x1008 + 0x20),
+            pattern.capture_rip(&data, 0x1000, 1, 0)
+        );
+        // capture is not 4 bytes
+        assert_eq!(None, pattern.capture_rip(&data, 0x1000, 1, 1));
+        // no such capture
+        assert_eq!(None, pattern.capture_rip(&data, 0x1000, 1, 3));
+        // no match
+        assert_eq!(None, pattern.capture_rip(&data, 0x1000, 0, 0));
+
+        let pattern = Pattern::new("48 8d 0d [ ?? ?? ?? ?? ]").unwrap();
+        assert_eq!(
+            Some(0x100f - 0x10),
+            pattern.capture_rip(&data, 0x1000, 8, 0)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        use PatternWarning::*;
+        let validate = |p: &str| Pattern::new(p).unwrap().validate();
+
+        assert_eq!(
+            Vec::<PatternWarning>::new(),
+            validate("48 8b 05 ?? ?? ?? ?? e8")
+        );
+        assert_eq!(vec![FewLiteralBytes(2)], validate("e8 12"));
+        // xref displacement counts towards constrained bytes
+        assert_eq!(Vec::<PatternWarning>::new(), validate("e8 X0x1234"),);
+        assert_eq!(
+            vec![FewLiteralBytes(1), LeadingWildcards(2)],
+            validate("?? ?? 34")
+        );
+        assert_eq!(vec![LeadingWildcards(1)], validate("?? 12 34 56"));
+        assert_eq!(vec![NoAnchorableByte], validate("48 00 ff 24"));
+        assert_eq!(
+            vec![FewLiteralBytes(0), LeadingWildcards(2), NoAnchorableByte],
+            validate("?? ??")
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let pattern = Pattern::new("48 8b 05 ?? ?? ?? ?? 48 !00").unwrap();
+        let data = [0x48, 0x8b, 0x05, 0x11, 0x22, 0x33, 0x44, 0x4c, 0x01];
+
+        let explanation = pattern.explain(&data, 0);
+        assert_eq!(9, explanation.len());
+        let mismatches = explanation
+            .iter()
+            .filter(|b| !b.is_match())
+            .collect::<Vec<_>>();
+        assert_eq!(1, mismatches.len());
+        assert_eq!(7, mismatches[0].offset);
+        assert_eq!("byte 7 expected 48 got 4C", mismatches[0].to_string());
+
+        // negated byte and truncated data
+        let explanation = pattern.explain(&[0x48, 0x8b, 0x05, 0, 0, 0, 0, 0x48, 0x00], 0);
+        assert_eq!(
+            vec!["byte 8 expected !00 got 00".to_string()],
+            explanation
+                .iter()
+                .filter(|b| !b.is_match())
+                .map(|b| b.to_string())
+                .collect::<Vec<_>>()
+        );
+        let explanation = pattern.explain(&data[..8], 0);
+        assert_eq!(
+            vec![7, 8],
+            explanation
+                .iter()
+                .filter(|b| !b.is_match())
+                .map(|b| b.offset)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_anchor_info() {
+        let anchor = |p: &str| Pattern::new(p).unwrap().anchor_info();
+        let info = |byte, position, is_banned| {
+            Some(AnchorInfo {
+                byte,
+                position,
+                is_banned,
+            })
+        };
+
+        // patterns from test_group_patterns whose anchor does not depend on grouping
+        assert_eq!(info(0x34, 0, false), anchor("34 56"));
+        assert_eq!(info(0x12, 0, false), anchor("12 12 12 34"));
+        assert_eq!(info(0x12, 0, false), anchor("12 00"));
+        assert_eq!(info(0x56, 1, false), anchor("00 56"));
+        assert_eq!(info(0x34, 1, false), anchor("!12 34 !56"));
+        assert_eq!(info(0x00, 0, true), anchor("00"));
+
+        // grouped anchor differs from the standalone anchor
+        assert_eq!(info(0x12, 0, false), anchor("12 34"));
+        assert_eq!(
+            1,
+            group_patterns(&[
+                &Pattern::new("12 34").unwrap(),
+                &Pattern::new("34 56").unwrap(),
+            ])[0]
+                .offset
+        );
+
+        assert_eq!(info(0x8b, 1, false), anchor("48 8B"));
+        assert_eq!(info(0x48, 0, true), anchor("48 ?? 24"));
+
+        // nothing to anchor on, scanned unanchored
+        assert_eq!(None, anchor("?? ??"));
+        assert_eq!(None, anchor("??"));
+    }
+
+    #[test]
+    fn test_group_patterns_with() {
+        let pattern = Pattern::new("48 8B").unwrap();
+        let anchor = |banned_bytes: Vec<u8>| {
+            let options = GroupOptions {
+                banned_bytes,
+                ..Default::default()
+            };
+            group_patterns_with(&[&pattern], &options)[0].offset
+        };
+        // 0x48 is banned by default
+        assert_eq!(1, group_patterns(&[&pattern])[0].offset);
+        assert_eq!(1, anchor(GroupOptions::default().banned_bytes));
+        assert_eq!(0, anchor(vec![]));
+        assert_eq!(0, anchor(vec![0x8b]));
+        assert_eq!(0, anchor(vec![0x48, 0x8b]));
+
+        let patterns = [&pattern];
+        let data = [0, 0x48, 0x8b, 0x48];
+        assert_eq!(
+            scan_pattern(&patterns, 0, &data),
+            scan_pattern_with(
+                &patterns,
+                0,
+                &data,
+                &GroupOptions {
+                    banned_bytes: vec![],
+                    ..Default::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_scan_pattern_rev() {
+        // only the last byte is uncommon
+        let pattern = Pattern::new("00 00 ?? 48 8b 7f").unwrap();
+        let reverse = GroupOptions {
+            direction: ScanDirection::Reverse,
+            ..Default::default()
+        };
+        assert_eq!(4, group_patterns(&[&pattern])[0].offset);
+        assert_eq!(5, group_patterns_with(&[&pattern], &reverse)[0].offset);
+
+        let mut data = vec![0; 0x200];
+        for i in [0x10, 0x80, 0x1fa] {
+            data[i + 2] = 0x99;
+            data[i + 3..i + 6].copy_from_slice(&[0x48, 0x8b, 0x7f]);
+        }
+        // anchor byte present without a match
+        data[0x100] = 0x7f;
+
+        let patterns = [&pattern];
+        let expected = vec![vec![0x1010, 0x1080, 0x11fa]];
+        assert_eq!(expected, scan_pattern(&patterns, 0x1000, &data));
+        assert_eq!(expected, scan_pattern_rev(&patterns, 0x1000, &data));
+
+        test_scan_algo(scan_pattern_rev);
     }
 
     #[test]
@@ -838,6 +2172,16 @@ mod test {
             ]
         );
 
+        // negated bytes are never anchors
+        assert_eq!(
+            group_patterns(&[&Pattern::new("!12 34 !56").unwrap()]),
+            vec![PatternPair {
+                pattern: &Pattern::new("!12 34 !56").unwrap(),
+                partial: Pattern::new("34 !56").unwrap().simple,
+                offset: 1,
+            },]
+        );
+
         // test bans
         assert_eq!(
             group_patterns(&[&Pattern::new("00").unwrap(),]),
@@ -854,6 +2198,9 @@ mod test {
 
     type XrefScanFn = fn(patterns: &[&Xref], base_address: usize, data: &[u8]) -> Vec<Vec<usize>>;
 
+    type XrefWideScanFn =
+        fn(patterns: &[&XrefWide], base_address: usize, data: &[u8]) -> Vec<Vec<usize>>;
+
     #[test]
     fn test_scan_pattern() {
         test_scan_algo(scan_pattern);
@@ -877,7 +2224,7 @@ mod test {
         let patterns = [&Pattern::new("01 02").unwrap()];
 
         // obtuse generator to test every combination of chunk boundaries
-        let data: Vec<_> = std::iter::repeat([1, 2, 3]).take(32).flatten().collect();
+        let data: Vec<_> = std::iter::repeat_n([1, 2, 3], 32).flatten().collect();
         let matches: Vec<_> = (0..3)
             .map(|offset| {
                 (0..len / 3)
@@ -891,6 +2238,326 @@ mod test {
             let res = scan(&patterns, base, slice);
             assert_eq!(vec![matches[(3 - (i % 3)) % 3].clone()], res);
         }
+
+        // matches within the trailing bytes are checked separately and must still have the
+        // custom offset applied
+        let patterns = [&Pattern::new("01 | 02 03").unwrap()];
+        for size in 3..len {
+            let mut data = vec![0; size];
+            data[size - 3..].copy_from_slice(&[1, 2, 3]);
+            assert_eq!(vec![vec![base + size - 2]], scan(&patterns, base, &data));
+        }
+    }
+
+    #[test]
+    fn test_ida_pattern() {
+        assert_eq!(
+            Pattern::new("48 8B 05 ?? ?? ?? ??").unwrap(),
+            Pattern::from_ida("48 8b 05 ? ? ? ?").unwrap()
+        );
+        assert_eq!(
+            Pattern::new("48 8B 05 ?? ?? ?? ??").unwrap(),
+            Pattern::from_ida("48 8B 05 ?? ?? ?? ??").unwrap()
+        );
+        assert!(Pattern::new("48 8b 05 ? ? ? ?").is_err());
+        assert!(Pattern::from_ida("48 | 05").is_err());
+        assert!(Pattern::from_ida("48 [ 05 ]").is_err());
+        assert!(Pattern::from_ida("").is_err());
+
+        for s in ["48 8B 05 ? ? ? ?", "?8 8? ? 05", "? 01"] {
+            let pattern = Pattern::from_ida(s).unwrap();
+            assert_eq!(s, pattern.to_ida_string());
+            let round_trip = Pattern::from_ida(pattern.to_ida_string()).unwrap();
+            assert_eq!(pattern.simple.mask, round_trip.simple.mask);
+            assert_eq!(pattern.simple.sig, round_trip.simple.sig);
+        }
+
+        assert_eq!(
+            "E8 ? ? ? ? C3",
+            Pattern::new("e8 X0x1234 c3").unwrap().to_ida_string()
+        );
+    }
+
+    #[test]
+    fn test_code_style_pattern() {
+        let pattern =
+            Pattern::from_code_style(b"\x48\x8B\x05\xAA\x00\x00\x00\xC3", "xXx?..?x").unwrap();
+        assert_eq!(Pattern::new("48 8B 05 ?? ?? ?? ?? C3").unwrap(), pattern);
+        assert_eq!(
+            (
+                b"\x48\x8B\x05\x00\x00\x00\x00\xC3".to_vec(),
+                "xxx????x".to_string()
+            ),
+            pattern.to_code_style()
+        );
+
+        let (sig, mask) = Pattern::new("?? 8B 4? 01").unwrap().to_code_style();
+        assert_eq!((vec![0, 0x8b, 0, 1], "?x?x".to_string()), (sig, mask));
+
+        let err = Pattern::from_code_style(b"\x48\x8B", "xx?").unwrap_err();
+        assert_eq!(
+            "signature length 2 does not match mask length 3",
+            err.to_string()
+        );
+        assert!(Pattern::from_code_style(b"\x48\x8B", "xy").is_err());
+        assert!(Pattern::from_code_style(b"", "").is_err());
+    }
+
+    #[test]
+    fn test_scan_pattern_cb() {
+        let patterns = [
+            &Pattern::new("01 02").unwrap(),
+            &Pattern::new("02 ?? 01").unwrap(),
+            &Pattern::new("03 03").unwrap(),
+            &Pattern::new("?? ?? ?? 01").unwrap(),
+            &Pattern::new("01 | 02 03").unwrap(),
+        ];
+
+        let data: Vec<_> = std::iter::repeat_n([1, 2, 3], 32).flatten().collect();
+        for i in 0..data.len() {
+            let slice = &data[i..];
+            let matches = std::sync::Mutex::new(vec![]);
+            scan_pattern_cb(&patterns, 123, slice, |pi, addr| {
+                matches.lock().unwrap().push((pi, addr))
+            });
+            let mut matches = matches.into_inner().unwrap();
+            matches.sort();
+
+            let mut expected: Vec<_> = scan_pattern(&patterns, 123, slice)
+                .into_iter()
+                .enumerate()
+                .flat_map(|(pi, bin)| bin.into_iter().map(move |addr| (pi, addr)))
+                .collect();
+            expected.sort();
+
+            assert_eq!(expected, matches);
+        }
+    }
+
+    #[test]
+    fn test_scan_pattern_longer_than_data() {
+        let data = [1, 2, 3, 4];
+        let long = Pattern::new("01 02 03 04 05 06 07 08").unwrap();
+        let exact = Pattern::new("01 ?? 03 04").unwrap();
+        let short = Pattern::new("03 04").unwrap();
+
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan_pattern(&[&long], 0x1000, &data)
+        );
+        assert_eq!(vec![vec![0x1000]], scan_pattern(&[&exact], 0x1000, &data));
+        assert_eq!(
+            vec![vec![], vec![0x1000], vec![0x1002]],
+            scan_pattern(&[&long, &exact, &short], 0x1000, &data)
+        );
+        assert_eq!(
+            vec![None, Some(0x1000), Some(0x1002)],
+            scan_pattern_first(&[&long, &exact, &short], 0x1000, &data)
+        );
+        assert_eq!(
+            vec![Vec::<usize>::new(), vec![]],
+            scan_pattern(&[&long, &exact], 0x1000, &[])
+        );
+    }
+
+    #[test]
+    fn test_scan_pooled() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+
+        let patterns = [
+            &Pattern::new("01 02").unwrap(),
+            &Pattern::new("02 ?? 01").unwrap(),
+            &Pattern::new("?? ?? ?? 01").unwrap(),
+            &Pattern::new("01 | 02 03").unwrap(),
+        ];
+        let data: Vec<_> = std::iter::repeat_n([1, 2, 3], 1000).flatten().collect();
+        assert_eq!(
+            scan_pattern(&patterns, 0x1000, &data),
+            scan_pattern_pooled(&pool, &patterns, 0x1000, &data)
+        );
+        assert_eq!(1, pool.install(rayon::current_num_threads));
+
+        let xrefs = [&Xref(0x1300), &Xref(0x2000)];
+        let mut data = vec![0; 0x200];
+        for i in [0x10, 0x80, 0x1f0] {
+            data[i..i + 4].copy_from_slice(&(0x300 - (i as i32 + 4)).to_le_bytes());
+        }
+        let expected = scan_xref(&xrefs, 0x1000, &data);
+        assert_eq!(vec![vec![0x1010, 0x1080, 0x11f0], vec![]], expected);
+        assert_eq!(expected, scan_xref_pooled(&pool, &xrefs, 0x1000, &data));
+    }
+
+    #[test]
+    fn test_scan_pattern_counts() {
+        let patterns = [
+            &Pattern::new("01 02").unwrap(),
+            &Pattern::new("02 ?? 01").unwrap(),
+            &Pattern::new("03 03").unwrap(),
+            &Pattern::new("?? ?? ?? 01").unwrap(),
+            &Pattern::new("01 | 02 03").unwrap(),
+        ];
+
+        let data: Vec<_> = std::iter::repeat_n([1, 2, 3], 32).flatten().collect();
+        for i in 0..data.len() {
+            let slice = &data[i..];
+            let expected: Vec<_> = scan_pattern(&patterns, 123, slice)
+                .iter()
+                .map(Vec::len)
+                .collect();
+            assert_eq!(expected, scan_pattern_counts(&patterns, 123, slice));
+        }
+    }
+
+    #[test]
+    fn test_leading_wildcards() {
+        let data = [0x0f, 0x31, 0x01, 0x02, 0x0f, 0x31, 0x0f, 0x31, 0x90, 0x90];
+
+        let pattern = Pattern::new("?? ?? 0F 31").unwrap();
+        assert_eq!(2, pattern.anchor_info().unwrap().position);
+        assert_eq!(
+            vec![vec![0x102, 0x104]],
+            scan_pattern(&[&pattern], 0x100, &data)
+        );
+        assert_eq!(
+            vec![Some(0x102)],
+            scan_pattern_first(&[&pattern], 0x100, &data)
+        );
+
+        // no literal byte to anchor on at all
+        let patterns = [
+            &Pattern::new("?? ??").unwrap(),
+            &Pattern::new("?? 3? 0F").unwrap(),
+            &pattern,
+        ];
+        let long = std::iter::repeat_n(data, 8).flatten().collect::<Vec<_>>();
+        for data in [&data[..], &long] {
+            let brute_force = patterns
+                .iter()
+                .map(|p| {
+                    (0..(data.len() + 1).saturating_sub(p.simple.len()))
+                        .filter(|i| p.is_match(data, 0x100, *i))
+                        .map(|i| 0x100 + i)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(brute_force, scan_pattern(&patterns, 0x100, data));
+            assert_eq!(brute_force, scan_pattern_rev(&patterns, 0x100, data));
+            assert_eq!(
+                brute_force
+                    .iter()
+                    .map(|b| b.first().copied())
+                    .collect::<Vec<_>>(),
+                scan_pattern_first(&patterns, 0x100, data)
+            );
+        }
+    }
+
+    #[test]
+    fn test_prepared_patterns() {
+        let patterns = [
+            &Pattern::new("01 02 03 01").unwrap(),
+            &Pattern::new("02 ?? 01").unwrap(),
+            &Pattern::new("03 03").unwrap(),
+            &Pattern::new("?? ?? ?? 01").unwrap(),
+            &Pattern::new("01 | 02 03").unwrap(),
+            &Pattern::new("ff ff ff ff ff ff ff ff ff ff ff ff").unwrap(),
+        ];
+        let prepared = PreparedPatterns::new(&patterns);
+
+        let data: Vec<_> = std::iter::repeat_n([1, 2, 3], 64).flatten().collect();
+        // many small sections of varying size, some shorter than the longest pattern
+        let sections = (0..data.len())
+            .step_by(7)
+            .map(|start| {
+                (
+                    0x1000 + start * 0x10,
+                    &data[start..(start + start % 13).min(data.len())],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (base_address, data) in sections {
+            let brute_force = patterns
+                .iter()
+                .map(|p| {
+                    (0..(data.len() + 1).saturating_sub(p.simple.len()))
+                        .filter(|i| p.is_match(data, base_address, *i))
+                        .map(|i| p.compute_result(data, base_address, i))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(brute_force, scan_pattern(&patterns, base_address, data));
+            assert_eq!(brute_force, prepared.scan(base_address, data));
+            assert_eq!(
+                scan_pattern_first(&patterns, base_address, data),
+                prepared.scan_first(base_address, data)
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_pattern_first() {
+        let patterns = [
+            &Pattern::new("01 02").unwrap(),
+            &Pattern::new("02 ?? 01").unwrap(),
+            &Pattern::new("03 03").unwrap(),
+            &Pattern::new("?? ?? ?? 01").unwrap(),
+        ];
+
+        let data: Vec<_> = std::iter::repeat_n([1, 2, 3], 32).flatten().collect();
+        for i in 0..data.len() {
+            let slice = &data[i..];
+            let expected: Vec<_> = scan_pattern(&patterns, 123, slice)
+                .into_iter()
+                .map(|bin| bin.into_iter().min())
+                .collect();
+            assert_eq!(expected, scan_pattern_first(&patterns, 123, slice));
+        }
+        assert_eq!(
+            vec![Some(123), Some(124), None, Some(123)],
+            scan_pattern_first(&patterns, 123, &data)
+        );
+    }
+
+    #[test]
+    fn test_scan_pattern_in() {
+        let patterns = [&Pattern::new("01 02 03").unwrap()];
+        let data = [1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3];
+        let base = 0x100;
+
+        assert_eq!(
+            vec![vec![0x100, 0x104, 0x108, 0x10c]],
+            scan_pattern(&patterns, base, &data)
+        );
+
+        // match at the range start
+        assert_eq!(
+            vec![vec![0x104, 0x108]],
+            scan_pattern_in(&patterns, base, &data, 0x104..0x10c)
+        );
+        // match just before the range start
+        assert_eq!(
+            vec![vec![0x108]],
+            scan_pattern_in(&patterns, base, &data, 0x105..0x10c)
+        );
+        // match straddling the range end
+        assert_eq!(
+            vec![vec![0x104, 0x108]],
+            scan_pattern_in(&patterns, base, &data, 0x104..0x109)
+        );
+        // match straddling the end of the data
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan_pattern_in(&patterns, base, &data[..14], 0x109..0x200)
+        );
+        // range outside the data
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan_pattern_in(&patterns, base, &data, 0..0x100)
+        );
     }
 
     #[test]
@@ -898,6 +2565,126 @@ mod test {
         test_scan_xref_algo(scan_xref);
     }
 
+    #[test]
+    fn test_scan_xref_order() {
+        let res = scan_xref(
+            &[&Xref(0x605040c), &Xref(0x504030a)],
+            3,
+            &[1, 2, 3, 4, 5, 6, 7],
+        );
+        assert_eq!(vec![vec![5], vec![4]], res);
+    }
+
+    #[test]
+    fn test_scan_xref_wide() {
+        test_scan_xref_wide_algo(scan_xref_wide);
+    }
+
+    fn test_scan_xref_wide_algo(scan: XrefWideScanFn) {
+        let scans = [
+            &XrefWide(0x90807060504030e),
+            &XrefWide(0x90807060504030e),
+            &XrefWide(0x90807060504030e),
+            &XrefWide(0x90807060504030e),
+        ];
+
+        let mut res = scan(&scans, 3, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        res.sort();
+        assert_eq!(vec![vec![4], vec![4], vec![4], vec![4]], res);
+
+        // displacement ending exactly at the end of the data
+        let data = [0xaa, 0xbb, 0xcc, 0x10, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            vec![vec![0x103]],
+            scan(&[&XrefWide(0x103 + 8 + 0x10)], 0x100, &data)
+        );
+
+        // displacement truncated by the end of the data
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan(&[&XrefWide(0x103 + 8 + 0x10)], 0x100, &data[..10])
+        );
+
+        // data shorter than a single displacement
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan(&[&XrefWide(0x8)], 0, &[0, 0, 0, 0])
+        );
+
+        // negative displacement
+        let data = (-0x20i64).to_le_bytes();
+        assert_eq!(
+            vec![vec![0x1000]],
+            scan(&[&XrefWide(0x1000 + 8 - 0x20)], 0x1000, &data)
+        );
+    }
+
+    #[test]
+    fn test_scan_relative_calls() {
+        let data = [
+            0xe8, 0x0b, 0x00, 0x00, 0x00, // call 0x1010
+            0xe9, 0x06, 0x00, 0x00, 0x00, // jmp 0x1010
+            0xe8, 0xf1, 0xff, 0xff, 0xff, // call 0x1000
+            0xeb, 0xe8, 0x00, // trailing e8 without a full displacement
+        ];
+        assert_eq!(
+            vec![vec![0x1000, 0x1005], vec![0x100a], vec![]],
+            scan_relative_calls(&[0x1010, 0x1000, 0x1015], 0x1000, &data)
+        );
+    }
+
+    #[test]
+    fn test_scan_absolute() {
+        let mut data = vec![0xcc; 3];
+        data.extend(0x1122334455667788u64.to_le_bytes());
+        data.extend(0x8877665544332211u64.to_le_bytes());
+        data.extend(0x1122334455667788u64.to_le_bytes());
+
+        let res = scan_absolute(
+            &[
+                0x8877665544332211,
+                0x1122334455667788,
+                0x1122334455667788,
+                0x99,
+            ],
+            0x1000,
+            &data,
+        );
+        assert_eq!(
+            vec![
+                vec![0x100b],
+                vec![0x1003, 0x1013],
+                vec![0x1003, 0x1013],
+                vec![]
+            ],
+            res.into_iter()
+                .map(|mut r| {
+                    r.sort();
+                    r
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // pointer truncated by the end of the data
+        assert_eq!(
+            vec![Vec::<usize>::new()],
+            scan_absolute(&[0x1122334455667788], 0, &data[3..10])
+        );
+    }
+
+    #[test]
+    fn test_xref_wide_match() {
+        let pattern = Pattern::new("ff X8 0x1111 ee").unwrap();
+        let mut data = vec![0xff];
+        data.extend(0x1000i64.to_le_bytes());
+        data.push(0xee);
+        assert!(pattern.is_match(&data, 0x108, 0));
+        assert!(!pattern.is_match(&data, 0x100, 0));
+
+        assert!(Pattern::new("ff X8").is_err());
+        assert!(Pattern::new("ff X8 zz").is_err());
+    }
+
     fn test_scan_xref_algo(scan: XrefScanFn) {
         let scans = [
             &Xref(0x504030a),